@@ -0,0 +1,29 @@
+// Cost of `--mode charset-score` on the data part of real addresses
+// Run with `cargo bench --bench charset_score`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use xelis_common::crypto::{bech32::SEPARATOR, KeyPair};
+use xelis_vanity::{config::Placement, pretty::CharSubset};
+
+fn charset_score(c: &mut Criterion) {
+    let subset = CharSubset::new("xels").unwrap();
+    let addresses: Vec<String> = (0..1024)
+        .map(|_| KeyPair::new().get_public_key().to_address(true).to_string())
+        .collect();
+    let data: Vec<&[u8]> = addresses.iter()
+        .map(|address| address.split_once(SEPARATOR).unwrap().1.as_bytes())
+        .collect();
+
+    let mut group = c.benchmark_group("charset_score");
+    for placement in [Placement::Prefix, Placement::Suffix, Placement::Anywhere] {
+        group.bench_function(placement.to_string(), |b| b.iter(|| {
+            for data in &data {
+                black_box(subset.score(black_box(data), placement));
+            }
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, charset_score);
+criterion_main!(benches);
@@ -0,0 +1,33 @@
+// Encoding of the addresses in the hot loop, a new string for each key or a reused buffer
+// Run with `cargo bench --bench encoding`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use xelis_common::crypto::KeyPair;
+use xelis_vanity::{config::Network, encoder::AddressEncoder};
+
+fn encoding(c: &mut Criterion) {
+    let keypairs: Vec<KeyPair> = (0..256).map(|_| KeyPair::new()).collect();
+    let mut group = c.benchmark_group("encoding");
+    group.throughput(Throughput::Elements(keypairs.len() as u64));
+
+    // Before the encoder: the address of xelis_common converted to a new string
+    group.bench_function("to_string", |b| b.iter(|| {
+        for keypair in &keypairs {
+            black_box(keypair.get_public_key().to_address(true).to_string());
+        }
+    }));
+
+    group.bench_function("encoder", |b| {
+        let mut encoder = AddressEncoder::new(Network::Mainnet);
+        b.iter(|| {
+            for keypair in &keypairs {
+                black_box(encoder.encode(keypair, None));
+            }
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, encoding);
+criterion_main!(benches);
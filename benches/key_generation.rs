@@ -0,0 +1,39 @@
+// Keys per second of a thread, one key at a time and by batches of `--batch-size`
+// Run with `cargo bench --bench key_generation`
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use xelis_common::crypto::KeyPair;
+use xelis_vanity::keys::KeyGenerator;
+
+// Keys generated by each iteration, so every case is compared on the same count
+const KEYS: usize = 256;
+
+fn key_generation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("key_generation");
+    group.throughput(Throughput::Elements(KEYS as u64));
+
+    // Before the batches: a new keypair and a call to the random generator per key
+    group.bench_function("keypair_new", |b| b.iter(|| {
+        for _ in 0..KEYS {
+            black_box(KeyPair::new());
+        }
+    }));
+
+    for batch_size in [1, 16, 64, 256] {
+        group.bench_with_input(BenchmarkId::new("batch", batch_size), &batch_size, |b, &batch_size| {
+            let mut generator = KeyGenerator::new(false, batch_size);
+            let mut keys = Vec::with_capacity(batch_size);
+            b.iter(|| {
+                for _ in 0..KEYS / batch_size {
+                    generator.generate_batch(&mut keys);
+                    black_box(&keys);
+                }
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, key_generation);
+criterion_main!(benches);
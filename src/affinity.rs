@@ -0,0 +1,57 @@
+// Pinning of the worker threads to CPU cores
+// Only supported on Linux, other platforms are running the workers unpinned
+
+// Cores the process is allowed to run on, None if it can't be known on this platform
+#[cfg(target_os = "linux")]
+pub fn available_cores() -> Option<Vec<usize>> {
+    let mut set = unsafe { std::mem::zeroed::<libc::cpu_set_t>() };
+    if unsafe { libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) } != 0 {
+        return None;
+    }
+
+    Some((0..libc::CPU_SETSIZE as usize).filter(|core| unsafe { libc::CPU_ISSET(*core, &set) }).collect())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn available_cores() -> Option<Vec<usize>> {
+    None
+}
+
+// Pin the calling thread to the core
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(core: usize) -> Result<(), String> {
+    let mut set = unsafe { std::mem::zeroed::<libc::cpu_set_t>() };
+    unsafe { libc::CPU_SET(core, &mut set) };
+
+    // A pid of 0 targets the calling thread
+    if unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) } != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_: usize) -> Result<(), String> {
+    Err("thread pinning is not supported on this platform".to_string())
+}
+
+// Core of each worker thread
+// Thread `i` is pinned to the i-th requested core, or the i-th available one without a list
+// Returns None if the pinning isn't supported on this platform
+pub fn resolve_cores(threads: usize, cpu_list: &[usize]) -> Result<Option<Vec<usize>>, String> {
+    let Some(available) = available_cores() else {
+        return Ok(None);
+    };
+
+    if let Some(core) = cpu_list.iter().find(|core| !available.contains(core)) {
+        return Err(format!("core {} doesn't exist or isn't available, available cores: {:?}", core, available));
+    }
+
+    let cores = if cpu_list.is_empty() { &available } else { cpu_list };
+    if cores.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some((0..threads).map(|i| cores[i % cores.len()]).collect()))
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::VecDeque,
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::Instant
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{debug, warn};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use xelis_common::tokio::{
+    self,
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::broadcast
+};
+use crate::{
+    searcher::SearchControl,
+    stats::{RATE_HISTORY, TOTAL_ATTEMPTS}
+};
+
+// Read-only status of a running search over HTTP, see `--api-bind`
+// GET /status and GET /results answer JSON, GET /events is a WebSocket pushing each match
+
+// Results kept for GET /results, the oldest ones are dropped first
+const MAX_RESULTS: usize = 1000;
+
+// Largest request accepted, only the request line and the headers are read
+const MAX_REQUEST_SIZE: usize = 8192;
+
+// Matches waiting to be pushed to a slow WebSocket client
+const EVENTS_CAPACITY: usize = 64;
+
+// Appended to the key of the client to accept a WebSocket connection (RFC 6455)
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+// Address found, as returned by the API
+#[derive(Serialize, Clone)]
+pub struct ApiResult {
+    // None without address in `--output-fields`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub content: String,
+    pub placement: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u64>,
+    pub timestamp: u64,
+    // Only kept with `--api-expose-secrets`, and only sent with the bearer token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Status<'a> {
+    // Seconds since the start of the search
+    uptime: f64,
+    threads: usize,
+    // Threads working and pause, see the `pause` and `threads` commands
+    active_threads: usize,
+    paused: bool,
+    attempts: u64,
+    // Keys per second of the last status line and its rolling average
+    rate: f64,
+    average_rate: f64,
+    contents: &'a [String],
+    matches: usize,
+}
+
+// State shared by the reporter, the API server and the control socket
+// The counters are the ones of the status line, only the results are kept here
+pub struct ApiState {
+    started: Instant,
+    threads: usize,
+    control: Arc<SearchControl>,
+    contents: Vec<String>,
+    // Bearer token giving access to the private keys, see `--api-token`
+    token: Option<String>,
+    expose_secrets: bool,
+    results: Mutex<(usize, VecDeque<ApiResult>)>,
+    events: broadcast::Sender<String>,
+}
+
+impl ApiState {
+    pub fn new(threads: usize, control: Arc<SearchControl>, contents: Vec<String>, token: Option<String>, expose_secrets: bool) -> Self {
+        Self {
+            started: Instant::now(),
+            threads,
+            control,
+            contents,
+            token,
+            expose_secrets,
+            results: Mutex::new((0, VecDeque::new())),
+            events: broadcast::channel(EVENTS_CAPACITY).0,
+        }
+    }
+
+    // Called by the reporter with each match
+    pub fn record(&self, mut result: ApiResult) {
+        if !self.expose_secrets {
+            result.private_key = None;
+        }
+
+        // The events are public, never with the keys
+        let public = ApiResult { private_key: None, ..result.clone() };
+        if let Ok(event) = serde_json::to_string(&public) {
+            // No client is connected
+            let _ = self.events.send(event);
+        }
+
+        let mut results = self.results.lock().unwrap();
+        results.0 += 1;
+        if results.1.len() == MAX_RESULTS {
+            results.1.pop_front();
+        }
+        results.1.push_back(result);
+    }
+
+    fn authorized(&self, authorization: Option<&str>) -> bool {
+        let (Some(token), Some(value)) = (&self.token, authorization.and_then(|value| value.strip_prefix("Bearer "))) else {
+            return false;
+        };
+
+        // Compare all the bytes so the time doesn't depend on the first difference
+        token.len() == value.len() && token.bytes().zip(value.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+
+    pub async fn status(&self) -> Status<'_> {
+        let (rate, average_rate) = {
+            let history = RATE_HISTORY.lock().await;
+            (history.last(), history.average())
+        };
+
+        Status {
+            uptime: self.started.elapsed().as_secs_f64(),
+            threads: self.threads,
+            active_threads: self.control.active_threads(),
+            paused: self.control.is_paused(),
+            attempts: TOTAL_ATTEMPTS.load(Ordering::Relaxed),
+            rate,
+            average_rate,
+            contents: &self.contents,
+            matches: self.results.lock().unwrap().0,
+        }
+    }
+
+    // Last results, the private keys are only included with `secrets`
+    pub fn results(&self, secrets: bool) -> Vec<ApiResult> {
+        let results = self.results.lock().unwrap();
+        results.1.iter()
+            .map(|result| if secrets { result.clone() } else { ApiResult { private_key: None, ..result.clone() } })
+            .collect()
+    }
+}
+
+// Answer the requests until the task is aborted at the end of the search
+pub async fn serve(listener: TcpListener, state: Arc<ApiState>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let state = Arc::clone(&state);
+                tokio::spawn(async move {
+                    if let Err(e) = handle(stream, &state).await {
+                        debug!("API request failed: {}", e);
+                    }
+                });
+            },
+            Err(e) => warn!("Couldn't accept an API connection: {}", e),
+        }
+    }
+}
+
+async fn handle(mut stream: TcpStream, state: &ApiState) -> Result<(), String> {
+    let mut request = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Err("connection closed before the end of the request".to_string());
+        }
+
+        request.extend_from_slice(&buffer[..read]);
+        if request.len() > MAX_REQUEST_SIZE {
+            return respond(&mut stream, "431 Request Header Fields Too Large", "").await;
+        }
+    }
+
+    let request = String::from_utf8_lossy(&request);
+    let mut parts = request.lines().next().unwrap_or_default().split_whitespace();
+    let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+
+    if method != "GET" {
+        return respond(&mut stream, "405 Method Not Allowed", "").await;
+    }
+
+    match path {
+        "/status" => respond(&mut stream, "200 OK", &serde_json::to_string(&state.status().await).unwrap_or_default()).await,
+        "/results" => {
+            let secrets = state.expose_secrets && state.authorized(header(&request, "Authorization"));
+            respond(&mut stream, "200 OK", &serde_json::to_string(&state.results(secrets)).unwrap_or_default()).await
+        },
+        "/events" => match header(&request, "Sec-WebSocket-Key") {
+            Some(key) => push_events(stream, key, state).await,
+            None => respond(&mut stream, "426 Upgrade Required", "").await,
+        },
+        _ => respond(&mut stream, "404 Not Found", "").await,
+    }
+}
+
+// Value of a header of the request, the names are case insensitive
+fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+    request.lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) -> Result<(), String> {
+    let response = format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body);
+    stream.write_all(response.as_bytes()).await.map_err(|e| e.to_string())
+}
+
+// Accept the WebSocket connection and send each match as a text message
+// The messages of the client are ignored, the connection ends with its close message
+async fn push_events(mut stream: TcpStream, key: &str, state: &ApiState) -> Result<(), String> {
+    let mut events = state.events.subscribe();
+    let accept = STANDARD.encode(Sha1::digest(format!("{}{}", key, WEBSOCKET_GUID).as_bytes()));
+    let response = format!("HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n", accept);
+    stream.write_all(response.as_bytes()).await.map_err(|e| e.to_string())?;
+
+    let mut buffer = [0u8; 1024];
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => stream.write_all(&text_frame(&event)).await.map_err(|e| e.to_string())?,
+                // The client missed some matches, they're still in /results
+                Err(broadcast::error::RecvError::Lagged(_)) => {},
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            read = stream.read(&mut buffer) => match read {
+                // Opcode 8 is the close message
+                Ok(read) if read > 0 && buffer[0] & 0x0f != 8 => {},
+                _ => return stream.write_all(&[0x88, 0]).await.map_err(|e| e.to_string()),
+            },
+        }
+    }
+}
+
+// Unmasked text message, as sent by a server
+fn text_frame(text: &str) -> Vec<u8> {
+    let len = text.len();
+    let mut frame = vec![0x81];
+    if len < 126 {
+        frame.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        frame.push(126);
+        frame.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        frame.push(127);
+        frame.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+
+    frame.extend_from_slice(text.as_bytes());
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(address: Option<&str>) -> ApiResult {
+        ApiResult {
+            address: address.map(str::to_string),
+            content: "cafe".to_string(),
+            placement: "prefix".to_string(),
+            index: None,
+            timestamp: 0,
+            private_key: None,
+        }
+    }
+
+    #[test]
+    fn test_result_without_address() {
+        let value = serde_json::to_value(result(None)).unwrap();
+        assert!(value.get("address").is_none());
+        assert_eq!(value["content"], "cafe");
+
+        let value = serde_json::to_value(result(Some("xel:cafe"))).unwrap();
+        assert_eq!(value["address"], "xel:cafe");
+    }
+}
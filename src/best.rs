@@ -0,0 +1,64 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex
+};
+
+// Closest partial match of a content
+#[derive(Default)]
+struct BestSlot {
+    // Number of characters matched, checked without locking
+    len: AtomicUsize,
+    // Only the address is kept, never the keys of a partial match
+    address: Mutex<Option<String>>,
+}
+
+// Closest partial match seen so far for each content
+pub struct BestMatches {
+    contents: Vec<String>,
+    slots: Vec<BestSlot>,
+}
+
+impl BestMatches {
+    pub fn new(contents: &[String]) -> Self {
+        Self {
+            contents: contents.to_vec(),
+            slots: contents.iter().map(|_| BestSlot::default()).collect(),
+        }
+    }
+
+    // Characters matched by the best partial match of the content
+    pub fn len(&self, index: usize) -> usize {
+        self.slots[index].len.load(Ordering::Relaxed)
+    }
+
+    // Keep the address if it matches more characters than the current best
+    pub fn offer(&self, index: usize, len: usize, address: &str) {
+        let slot = &self.slots[index];
+        if len <= slot.len.load(Ordering::Relaxed) {
+            return;
+        }
+
+        if let Ok(mut best) = slot.address.lock() {
+            // Another worker may have found a better one meanwhile
+            if len > slot.len.load(Ordering::Relaxed) {
+                slot.len.store(len, Ordering::Relaxed);
+                *best = Some(address.to_string());
+            }
+        }
+    }
+
+    // Contents with a partial match, with the characters matched and the address
+    // Sorted from the closest one
+    pub fn snapshot(&self) -> Vec<(&str, usize, String)> {
+        let mut best: Vec<_> = self.contents.iter()
+            .zip(self.slots.iter())
+            .filter_map(|(content, slot)| {
+                let address = slot.address.lock().ok()?.clone()?;
+                Some((content.as_str(), slot.len.load(Ordering::Relaxed), address))
+            })
+            .collect();
+
+        best.sort_by(|a, b| b.1.cmp(&a.1));
+        best
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use xelis_common::{
+    async_handler,
+    prompt::{
+        argument::{Arg, ArgType, ArgumentManager},
+        command::{
+            Command,
+            CommandError,
+            CommandHandler,
+            CommandManager
+        }
+    },
+};
+use crate::{
+    best::BestMatches,
+    coordinator::Coordinator,
+    searcher::SearchControl,
+    snapshot::Snapshot,
+    stats::{self, RATE_HISTORY, RATE_TREND}
+};
+
+// Number of contents shown by the `best` command
+const MAX_BEST_SHOWN: usize = 10;
+
+// Register all the commands available in the interactive prompt
+pub fn register_commands(manager: &CommandManager, best: Arc<BestMatches>, control: Arc<SearchControl>, snapshot: Arc<Snapshot>) -> Result<(), CommandError> {
+    manager.register_default_commands()?;
+    manager.store_in_context(best)?;
+    manager.store_in_context(control)?;
+    manager.store_in_context(snapshot)?;
+    manager.add_command(Command::new("reset-stats", "Reset the average and peak hashrate", CommandHandler::Async(async_handler!(reset_stats))))?;
+    manager.add_command(Command::new("stats", "Show the hashrates, the work of each thread, the closest match and the work done by this search and all of them", CommandHandler::Async(async_handler!(show_stats))))?;
+    manager.add_command(Command::new("best", "Show the closest partial matches found so far", CommandHandler::Async(async_handler!(show_best))))?;
+    manager.add_command(Command::new("pause", "Pause the workers until `resume`", CommandHandler::Async(async_handler!(pause))))?;
+    manager.add_command(Command::new("resume", "Resume the paused workers", CommandHandler::Async(async_handler!(resume))))?;
+    manager.add_command(Command::with_required_arguments("threads", "Change the number of working threads, up to the number at startup", vec![Arg::new("count", ArgType::Number)], CommandHandler::Async(async_handler!(set_threads))))?;
+
+    Ok(())
+}
+
+// Register the commands of the coordinator prompt
+pub fn register_coordinator_commands(manager: &CommandManager, coordinator: Arc<Coordinator>) -> Result<(), CommandError> {
+    manager.register_default_commands()?;
+    manager.store_in_context(coordinator)?;
+    manager.add_command(Command::new("workers", "List the connected workers with their rates", CommandHandler::Async(async_handler!(show_workers))))?;
+
+    Ok(())
+}
+
+async fn reset_stats(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    RATE_HISTORY.lock().await.reset();
+    RATE_TREND.lock().await.reset();
+    manager.message("Hashrate statistics have been reset");
+    Ok(())
+}
+
+// Same lines as the ones logged on SIGUSR1
+async fn show_stats(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    // Not locking the context while the rates are read
+    let snapshot = {
+        let context = manager.get_context().lock()?;
+        Arc::clone(context.get::<Arc<Snapshot>>()?)
+    };
+
+    for line in snapshot.lines().await {
+        manager.message(line);
+    }
+
+    Ok(())
+}
+
+async fn show_best(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let best: &Arc<BestMatches> = context.get()?;
+
+    let matches = best.snapshot();
+    if matches.is_empty() {
+        manager.message("No partial match yet (only tracked for prefixes and suffixes)");
+        return Ok(());
+    }
+
+    for (content, len, address) in matches.into_iter().take(MAX_BEST_SHOWN) {
+        manager.message(format!("{}: {}/{} characters in {}", content, len, content.len(), address));
+    }
+
+    Ok(())
+}
+
+async fn pause(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let control: &Arc<SearchControl> = context.get()?;
+    control.pause();
+    manager.message("Search paused, use `resume` to continue");
+    Ok(())
+}
+
+async fn resume(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let control: &Arc<SearchControl> = context.get()?;
+    control.resume();
+    manager.message("Search resumed");
+    Ok(())
+}
+
+async fn set_threads(manager: &CommandManager, mut arguments: ArgumentManager) -> Result<(), CommandError> {
+    let threads = arguments.get_value("count")?.to_number()?;
+    let context = manager.get_context().lock()?;
+    let control: &Arc<SearchControl> = context.get()?;
+    match control.set_active_threads(threads as usize) {
+        Ok(()) => manager.message(format!("Searching with {} threads", threads)),
+        Err(e) => manager.message(e),
+    }
+
+    Ok(())
+}
+
+async fn show_workers(manager: &CommandManager, _: ArgumentManager) -> Result<(), CommandError> {
+    let context = manager.get_context().lock()?;
+    let coordinator: &Arc<Coordinator> = context.get()?;
+
+    let peers = coordinator.peers();
+    if peers.is_empty() {
+        manager.message("No worker connected");
+        return Ok(());
+    }
+
+    for peer in peers {
+        manager.message(format!("{}: {} threads, job #{}, {}, last seen {}s ago", peer.name, peer.threads, peer.job, stats::format_rate(peer.rate), peer.last_seen.elapsed().as_secs()));
+    }
+
+    Ok(())
+}
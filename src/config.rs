@@ -0,0 +1,1086 @@
+use std::{
+    ffi::OsString,
+    fmt,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    str::FromStr
+};
+use clap::{
+    error::ErrorKind,
+    Args,
+    CommandFactory,
+    FromArgMatches,
+    Parser,
+    Subcommand
+};
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+use serde::{Deserialize, Serialize};
+use xelis_common::{
+    config::{
+        PREFIX_ADDRESS,
+        TESTNET_PREFIX_ADDRESS,
+        VERSION
+    },
+    prompt::LogLevel
+};
+use crate::{
+    config_file::{self, ConfigFile},
+    derivation::{parse_master_arg, parse_shard, Shard, MASTER_SIZE},
+    keys::PaymentId,
+    language::{languages, parse_language},
+    lifetime::DEFAULT_STATE_FILE,
+    split::{parse_public_key, parse_tweak}
+};
+
+// Default directory of the log file
+pub const DEFAULT_LOG_DIR: &str = "logs/";
+// Default name of the log file
+pub const DEFAULT_LOG_FILE: &str = "logs.log";
+// Content read from stdin, see `--content -`
+pub const STDIN_CONTENT: &str = "-";
+
+#[derive(clap::ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    pub fn is_mainnet(&self) -> bool {
+        matches!(self, Network::Mainnet)
+    }
+
+    // The other network, used to display both addresses of a key
+    pub fn other(&self) -> Network {
+        match self {
+            Network::Mainnet => Network::Testnet,
+            Network::Testnet => Network::Mainnet,
+        }
+    }
+
+    // Human readable part of the addresses on this network
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Network::Mainnet => PREFIX_ADDRESS,
+            Network::Testnet => TESTNET_PREFIX_ADDRESS,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Mainnet => f.write_str("mainnet"),
+            Network::Testnet => f.write_str("testnet"),
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Placement {
+    Prefix,
+    Suffix,
+    Anywhere,
+}
+
+// The names of the placements are the ones of clap, so a placement is parsed the same way
+// by `--placement`, in a content (`suffix:dog`) and in the configuration file: ignoring the case
+impl FromStr for Placement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as clap::ValueEnum>::from_str(s.trim(), true).map_err(|_| {
+            let names = <Self as clap::ValueEnum>::value_variants().iter().map(|placement| placement.to_string()).collect::<Vec<_>>();
+            format!("unknown placement '{}', expected one of: {}", s, names.join(", "))
+        })
+    }
+}
+
+// Used as a clap value parser, for the same error as in a content
+pub fn parse_placement(value: &str) -> Result<Placement, String> {
+    value.parse()
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match clap::ValueEnum::to_possible_value(self) {
+            Some(value) => f.write_str(value.get_name()),
+            None => Ok(()),
+        }
+    }
+}
+
+// Searches of a pretty address instead of a content
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchMode {
+    // Longest run of the same character, see `--min-run`
+    LongestRun,
+    // Palindrome of `--length` characters at the start or the end of the address
+    Palindrome,
+    // At least `--min-length` digits at the end of the address
+    DigitsSuffix,
+    // Longest run of the `--chars` characters at the placements
+    CharsetScore,
+}
+
+impl fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchMode::LongestRun => f.write_str("longest-run"),
+            SearchMode::Palindrome => f.write_str("palindrome"),
+            SearchMode::DigitsSuffix => f.write_str("digits-suffix"),
+            SearchMode::CharsetScore => f.write_str("charset-score"),
+        }
+    }
+}
+
+// Parts of a match written to the logs, the output file and the status API, see `--output-fields`
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputField {
+    // The address on both networks
+    Address,
+    #[value(alias = "private_key")]
+    PrivateKey,
+    // The seed in each language of `--language`
+    Seed,
+    #[value(alias = "public_key")]
+    PublicKey,
+}
+
+// Fields written without `--output-fields`, and always by the coordinator
+pub const DEFAULT_OUTPUT_FIELDS: [OutputField; 3] = [OutputField::Address, OutputField::PrivateKey, OutputField::Seed];
+
+// Unit of the rates logged, see `--rate-unit`
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateUnit {
+    // Scaled to the rate, like 1.23 MH/s
+    Auto,
+    #[value(alias = "h/s")]
+    H,
+    #[value(alias = "kh/s")]
+    Kh,
+    #[value(alias = "mh/s")]
+    Mh,
+    #[value(alias = "gh/s")]
+    Gh,
+}
+
+impl RateUnit {
+    // Size and name of the unit, none when scaled to the rate
+    pub fn scale(&self) -> Option<(f64, &'static str)> {
+        match self {
+            RateUnit::Auto => None,
+            RateUnit::H => Some((1.0, "H/s")),
+            RateUnit::Kh => Some((1e3, "KH/s")),
+            RateUnit::Mh => Some((1e6, "MH/s")),
+            RateUnit::Gh => Some((1e9, "GH/s")),
+        }
+    }
+}
+
+// Also the default value shown by `--help`, parsed back by clap
+impl fmt::Display for RateUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match clap::ValueEnum::to_possible_value(self) {
+            Some(value) => f.write_str(value.get_name()),
+            None => Ok(()),
+        }
+    }
+}
+
+// Used as a clap value parser for `--log-level` and `--file-log-level`, ignoring the case
+pub fn parse_log_level(value: &str) -> Result<LogLevel, String> {
+    match value.trim().to_lowercase().as_str() {
+        "off" => Ok(LogLevel::Off),
+        "error" => Ok(LogLevel::Error),
+        "warn" => Ok(LogLevel::Warn),
+        "info" => Ok(LogLevel::Info),
+        "debug" => Ok(LogLevel::Debug),
+        "trace" => Ok(LogLevel::Trace),
+        _ => Err(format!("unknown log level '{}', expected one of: off, error, warn, info, debug, trace", value))
+    }
+}
+
+// Highest number of threads accepted by `--num-threads`
+pub const MAX_THREADS: usize = 65535;
+
+// Number of threads, absolute or relative to the available ones
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThreadCount {
+    Count(usize),
+    // Percentage of the available threads (1-100)
+    Percent(u8),
+}
+
+impl ThreadCount {
+    // Resolve the number of threads against the available ones
+    pub fn resolve(&self, available: usize) -> usize {
+        match self {
+            ThreadCount::Count(count) => *count,
+            ThreadCount::Percent(percent) => available * *percent as usize / 100,
+        }
+    }
+}
+
+impl FromStr for ThreadCount {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('%') {
+            Some(percent) => match percent.trim().parse::<u8>() {
+                Ok(value) if (1..=100).contains(&value) => Ok(ThreadCount::Percent(value)),
+                _ => Err(format!("invalid percentage '{}', expected a value between 1% and 100%", s)),
+            },
+            None => match s.parse() {
+                Ok(0) => Err("0 threads can't search, omit --num-threads to use all the detected threads or give a positive number".to_string()),
+                Ok(value) if value > MAX_THREADS => Err(format!("{} threads is too many, the maximum is {}", value, MAX_THREADS)),
+                Ok(value) => Ok(ThreadCount::Count(value)),
+                Err(_) => Err(format!("invalid number of threads '{}', expected a count or a percentage like 75%", s)),
+            },
+        }
+    }
+}
+
+#[derive(Parser)]
+#[clap(version = VERSION, about = "XELIS is an innovative cryptocurrency built from scratch with BlockDAG, Homomorphic Encryption, Zero-Knowledge Proofs, and Smart Contracts.")]
+#[command(styles = xelis_common::get_cli_styles())]
+#[command(arg_required_else_help = true)]
+pub struct Config {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+impl Config {
+    // Parse the command line arguments
+    // When no subcommand is given (`xelis-vanity --content dog`),
+    // we fallback to the `search` subcommand to keep old invocations working
+    pub fn parse_args() -> Self {
+        let mut args: Vec<OsString> = std::env::args_os().collect();
+        let use_default = args.get(1)
+            .and_then(|arg| arg.to_str())
+            .map(|arg| arg.starts_with('-') && !matches!(arg, "-h" | "--help" | "-V" | "--version"))
+            .unwrap_or(false);
+
+        if use_default {
+            args.insert(1, OsString::from("search"));
+        }
+
+        // First pass to know the subcommand and the options given on the command line,
+        // the required ones may only be in the configuration file
+        let command = Self::command();
+        let Ok(matches) = command.clone().ignore_errors(true).try_get_matches_from(&args) else {
+            // Help, version or invalid subcommand
+            return Self::parse_from(args);
+        };
+
+        let Some((name, sub_matches)) = matches.subcommand() else {
+            return Self::parse_from(args);
+        };
+
+        let mut file = ConfigFile::load(sub_matches.get_one::<PathBuf>("config").map(PathBuf::as_path))
+            .unwrap_or_else(|e| command.clone().error(ErrorKind::InvalidValue, e).exit());
+
+        if let Some(file) = &mut file {
+            let file_args = file.arguments(&command, name, sub_matches)
+                .unwrap_or_else(|e| command.clone().error(ErrorKind::InvalidValue, e).exit());
+            // Right after the subcommand, so the command line options are parsed last
+            args.splice(2..2, file_args);
+        }
+
+        let matches = command.clone().try_get_matches_from(&args).unwrap_or_else(|e| {
+            match file.as_ref().and_then(|file| file.explain(&e)) {
+                Some(message) => command.clone().error(e.kind(), message).exit(),
+                None => e.exit(),
+            }
+        });
+
+        let mut config = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        if config.command.common().print_config {
+            if let Some((name, sub_matches)) = matches.subcommand() {
+                config_file::print_effective(&command, name, sub_matches, file.as_ref());
+            }
+            std::process::exit(0);
+        }
+
+        // Also set when the default file was loaded, to log it
+        config.command.common_mut().config = file.map(|file| file.path);
+        config
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Search for an address containing the requested content (default)
+    Search(SearchArgs),
+    /// Measure how many keys per second this machine can generate
+    Bench(BenchArgs),
+    /// Estimate the difficulty to find an address with the requested content
+    Estimate(EstimateArgs),
+    /// Derive the address of a private key or seed and check it against a content
+    Verify(VerifyArgs),
+    /// Print the address of a private key or seed
+    Derive(DeriveArgs),
+    /// Combine a private key with the tweak found by a split-key search
+    Combine(CombineArgs),
+    /// List the languages available for the seed
+    Languages(LanguagesArgs),
+    /// Search the jobs of a coordinator and send it the addresses found
+    Worker(WorkerArgs),
+    /// Hand a search to remote workers and collect their results
+    Coordinator(CoordinatorArgs),
+}
+
+impl Command {
+    // Options shared by all the subcommands
+    pub fn common(&self) -> &CommonArgs {
+        match self {
+            Command::Search(args) => &args.common,
+            Command::Bench(args) => &args.common,
+            Command::Estimate(args) => &args.common,
+            Command::Verify(args) => &args.common,
+            Command::Derive(args) => &args.common,
+            Command::Combine(args) => &args.common,
+            Command::Languages(args) => &args.common,
+            Command::Worker(args) => &args.common,
+            Command::Coordinator(args) => &args.common,
+        }
+    }
+
+    pub fn common_mut(&mut self) -> &mut CommonArgs {
+        match self {
+            Command::Search(args) => &mut args.common,
+            Command::Bench(args) => &mut args.common,
+            Command::Estimate(args) => &mut args.common,
+            Command::Verify(args) => &mut args.common,
+            Command::Derive(args) => &mut args.common,
+            Command::Combine(args) => &mut args.common,
+            Command::Languages(args) => &mut args.common,
+            Command::Worker(args) => &mut args.common,
+            Command::Coordinator(args) => &mut args.common,
+        }
+    }
+
+    // Only the search is running long enough to need an interactive prompt
+    pub fn is_interactive(&self) -> bool {
+        match self {
+            Command::Search(args) => args.is_interactive(),
+            Command::Coordinator(args) => args.is_interactive(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Args, Clone)]
+pub struct CommonArgs {
+    /// Numbers of threads to use (at least 1, max: 65535), or a percentage of the available ones (75%)
+    /// By default, this will try to detect the number of threads available on your CPU.
+    #[clap(short, long, value_parser = ThreadCount::from_str)]
+    pub num_threads: Option<ThreadCount>,
+    /// Network of the addresses
+    #[clap(long, default_value_t = Network::Mainnet)]
+    pub network: Network,
+    /// Number of keys generated at once by each thread
+    /// The random bytes of a whole batch are read at once, and the rate counter is updated once per batch.
+    #[clap(long, default_value_t = 64, value_parser = clap::value_parser!(u16).range(1..))]
+    pub batch_size: u16,
+    /// Pin each worker thread to a CPU core
+    #[clap(long)]
+    pub pin_threads: bool,
+    /// Cores to pin the worker threads to (comma separated), implies `--pin-threads`
+    /// Thread i is pinned to the i-th core of the list.
+    #[clap(long, value_delimiter = ',')]
+    pub cpu_list: Vec<usize>,
+    /// Generate the keys with a fast non-cryptographic random generator
+    /// INSECURE: only for benchmarks and statistics, never use the keys found with it.
+    #[clap(long)]
+    pub fast_rng: bool,
+    /// Disable the usage of colors in log
+    #[clap(long)]
+    pub disable_log_color: bool,
+    /// Unit of the rates in the status line and the reports: auto, h, kh, mh or gh
+    /// A fixed unit keeps the logs of several machines comparable, auto scales it to each rate.
+    #[clap(long, default_value_t = RateUnit::Auto, ignore_case = true)]
+    pub rate_unit: RateUnit,
+    /// Disable terminal interactive mode
+    /// You will not be able to write CLI commands in it or to have an updated prompt
+    #[clap(long)]
+    pub disable_interactive_mode: bool,
+    /// Level of the logs: off, error, warn, info, debug or trace
+    /// Debug also logs the start and stop of each worker thread and the timing of their batches.
+    #[clap(long, default_value = "info", value_parser = parse_log_level)]
+    pub log_level: LogLevel,
+    /// Level of the log file, `--log-level` by default
+    #[clap(long, value_parser = parse_log_level)]
+    pub file_log_level: Option<LogLevel>,
+    /// Directory of the log file
+    #[clap(long)]
+    pub log_dir: Option<String>,
+    /// Name of the log file
+    #[clap(long)]
+    pub log_file: Option<String>,
+    /// Disable the log file, the found keys are logged
+    #[clap(long, conflicts_with_all = ["log_dir", "log_file"])]
+    pub disable_file_logging: bool,
+    /// File to append the found addresses to, one JSON object per line
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+    /// TOML file setting the options, the command line options are overriding it
+    /// Defaults to `xelis-vanity.toml` in the working directory if it exists, see the README.
+    #[clap(long)]
+    pub config: Option<PathBuf>,
+    /// Print the effective configuration after merging the file and the command line, then exit
+    #[clap(long)]
+    pub print_config: bool,
+}
+
+impl CommonArgs {
+    pub fn pinning(&self) -> bool {
+        self.pin_threads || !self.cpu_list.is_empty()
+    }
+
+    pub fn file_logging(&self) -> bool {
+        !self.disable_file_logging
+    }
+
+    // Directory of the log file, always ending with a separator
+    pub fn log_dir(&self) -> String {
+        let mut dir = self.log_dir.clone().unwrap_or_else(|| DEFAULT_LOG_DIR.to_string());
+        if !dir.ends_with('/') {
+            dir.push('/');
+        }
+        dir
+    }
+
+    pub fn log_file(&self) -> &str {
+        self.log_file.as_deref().unwrap_or(DEFAULT_LOG_FILE)
+    }
+}
+
+// Confirmation of the long searches, shared by the search and the coordinator
+#[derive(Args)]
+pub struct EtaArgs {
+    /// Hours to have a 50% chance to find an address above which the search needs `--yes` to start
+    /// Measured with a short benchmark at startup, a non-interactive search only logs a warning.
+    #[clap(long, default_value_t = 24, value_parser = clap::value_parser!(u64).range(1..))]
+    pub eta_threshold: u64,
+    /// Start the search even if it's expected to take longer than `--eta-threshold`
+    #[clap(short, long)]
+    pub yes: bool,
+    /// Years to have a 50% chance to find an address above which the search must be confirmed
+    /// `--yes` is not enough: the confirmation is asked in the terminal, or `--yes-i-know` must be given.
+    #[clap(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub astronomical_threshold: u64,
+    /// Start the search even if it's expected to take longer than `--astronomical-threshold`, without asking
+    #[clap(long)]
+    pub yes_i_know: bool,
+}
+
+#[derive(Args)]
+pub struct SearchArgs {
+    /// The content for the address to search for, `-` to read one content per line from stdin
+    /// Repeat it to search for several contents at once, the first one found is reported.
+    #[clap(short, long, required_unless_present_any = ["dictionary", "desired", "mode"])]
+    pub content: Vec<String>,
+    /// Search for a pretty address instead of a content
+    /// longest-run: report each address beating the longest run of a repeated character found so far.
+    /// palindrome: first (prefix) or last (suffix) `--length` data characters forming a palindrome.
+    /// digits-suffix: address ending with at least `--min-length` digits.
+    /// charset-score: report each address beating the longest run of `--chars` characters at the start (prefix), the end (suffix) or anywhere.
+    #[clap(long, conflicts_with_all = ["content", "dictionary", "desired"])]
+    pub mode: Option<SearchMode>,
+    /// Shortest run reported first with `--mode longest-run` and `--mode charset-score`, the next ones must be longer
+    #[clap(long, default_value_t = 6, value_parser = clap::value_parser!(u64).range(2..))]
+    pub min_run: u64,
+    /// Only accept the addresses whose public key characters are all one of these, like `acdef023456789`
+    /// Extremely unlikely: each key character must be one of them, the estimate accounts for it.
+    #[clap(long, conflicts_with = "mode")]
+    pub only: Option<String>,
+    /// Characters scored with `--mode charset-score`, like `xels`
+    #[clap(long, required_if_eq("mode", "charset-score"))]
+    pub chars: Option<String>,
+    /// Stop once a record reaches this score with `--mode charset-score`
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub target_score: Option<u64>,
+    /// Number of characters of the palindrome with `--mode palindrome`
+    #[clap(long, default_value_t = 8, value_parser = clap::value_parser!(u64).range(2..))]
+    pub length: u64,
+    /// Minimum number of digits at the end of the address with `--mode digits-suffix`
+    #[clap(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    pub min_length: u64,
+    /// Word to search for as typed, converted to a content available in addresses
+    /// It's lowercased and the unsupported characters are replaced or dropped (o -> 0, i dropped, ...).
+    #[clap(long)]
+    pub desired: Option<String>,
+    /// Word list file (one word per line) to search for, in addition to the contents
+    /// Words with characters not available in addresses are skipped.
+    #[clap(long)]
+    pub dictionary: Option<PathBuf>,
+    /// Minimum length of the dictionary words to search for
+    #[clap(long, default_value_t = 4, requires = "dictionary")]
+    pub min_word_len: usize,
+    /// Language for the seed, by name (english, french, ...) or index
+    #[clap(short, long, default_value = "english", value_parser = parse_language)]
+    pub language: usize,
+    /// Print the seed in each of these languages (names or indexes, comma separated)
+    /// By default, the seed is only printed in the `--language` one.
+    #[clap(long, value_delimiter = ',', value_parser = parse_language)]
+    pub seed_languages: Vec<usize>,
+    /// Placements of the content in the address (comma separated: prefix, suffix or anywhere), the first one found is reported
+    #[clap(short, long, value_delimiter = ',', default_value = "prefix", value_parser = parse_placement)]
+    pub placement: Vec<Placement>,
+    /// Match a suffix at the end of the public key characters instead of the end of the address
+    /// The address type and the checksum characters are skipped, see the README.
+    #[clap(long)]
+    pub before_checksum: bool,
+    /// Search a content anywhere in the whole address, including the network prefix and the separator
+    /// By default, only the data part after the separator is searched, like the prefixes.
+    #[clap(long)]
+    pub raw_match: bool,
+    /// Also report the near misses, up to this number of edits (Levenshtein distance) from a content
+    #[clap(long, default_value_t = 0)]
+    pub fuzzy: usize,
+    /// Search for an integrated address embedding a payment ID
+    #[clap(long)]
+    pub integrated: bool,
+    /// Payment ID to embed in the integrated address
+    /// By default, a random payment ID is used for each address.
+    #[clap(long, requires = "integrated")]
+    pub payment_id: Option<u64>,
+    /// Restore the address from the seed of each match before reporting it
+    #[clap(long)]
+    pub verify: bool,
+    /// Show the QR code of each address found, to scan it with a wallet
+    #[clap(long)]
+    pub qr: bool,
+    /// Write the QR code of each address found as a PNG, in the directory of the output file
+    #[clap(long, requires = "output")]
+    pub qr_png: bool,
+    /// Percentage of the time the workers are running (1-100), they sleep the rest of the time
+    /// Useful to limit the heat on machines that can't sustain a full load.
+    #[clap(long, value_parser = clap::value_parser!(u32).range(1..=100))]
+    pub throttle: Option<u32>,
+    /// Run the worker threads at the lowest scheduling priority, to only use the idle CPU time
+    #[clap(long)]
+    pub low_priority: bool,
+    /// Number of addresses to find before stopping
+    /// Without it, the search runs until it's stopped, and succeeds if at least one address was found.
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub count: Option<u64>,
+    /// Number of addresses to find for each content, the search stops once all of them are found
+    /// A content is not searched anymore once its addresses are found.
+    #[clap(long, conflicts_with = "count", value_parser = clap::value_parser!(u64).range(1..))]
+    pub count_per_pattern: Option<u64>,
+    /// Stop the search after this duration in seconds
+    #[clap(long, visible_alias = "max-duration")]
+    pub timeout: Option<u64>,
+    /// Stop the search after generating this number of keys
+    #[clap(long)]
+    pub max_attempts: Option<u64>,
+    /// Write the progress as one JSON object per second to `stdout`, `stderr` or a file
+    #[clap(long, value_name = "TARGET")]
+    pub progress_json: Option<String>,
+    /// Log a line each time the number of keys generated crosses a multiple of this value
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub milestone: Option<u64>,
+    /// Matches logged per second at most, the next ones are only counted and written to the output file
+    /// A summary of their number is logged every second instead, to keep the terminal readable with easy contents.
+    /// Only applies with `--output`, without it every match is logged so no key is lost.
+    #[clap(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_logged_rate: u32,
+    /// Serve a read-only status API on this address (host:port): GET /status, GET /results and the /events WebSocket
+    /// The private keys are never served without `--api-expose-secrets`.
+    #[clap(long)]
+    pub api_bind: Option<String>,
+    /// Bearer token required to see the private keys in GET /results
+    #[clap(long, requires = "api_bind")]
+    pub api_token: Option<String>,
+    /// Include the private keys in GET /results for the requests with the `--api-token` bearer token
+    #[clap(long, requires = "api_token")]
+    pub api_expose_secrets: bool,
+    /// Accept the commands status, pause, resume, threads <n>, stop and found on this Unix socket, one per line
+    /// Each command is answered by a JSON line. Only the owner can connect, the file is removed once the search stopped.
+    #[clap(long)]
+    pub control_socket: Option<PathBuf>,
+    /// Run in the background (Unix only): detach from the terminal and log to the file only
+    /// The interactive mode must be disabled. The configuration is validated before detaching, SIGTERM stops the search like the `exit` command.
+    #[clap(long, requires = "disable_interactive_mode", conflicts_with_all = ["disable_file_logging", "dry_run"])]
+    pub daemon: bool,
+    /// Notify systemd of the readiness, the status and the watchdog (Linux only)
+    /// Enabled anyway when started by systemd with `Type=notify`, this only warns if it's not the case.
+    #[clap(long)]
+    pub systemd: bool,
+    /// Write the ID of the process to this file, removed once the search stopped
+    #[clap(long)]
+    pub pid_file: Option<PathBuf>,
+    /// Parts of each match in the logs, the output file and the status API (comma separated)
+    /// Without private-key nor seed, a key can only be found again from `--derive-from` and its index.
+    #[clap(long, value_delimiter = ',', default_value = "address,private-key,seed", ignore_case = true)]
+    pub output_fields: Vec<OutputField>,
+    /// Log the parts of each address found: the human-readable part, the data part and the checksum
+    /// Shows which part holds the content, to understand the placements.
+    #[clap(long)]
+    pub verbose_output: bool,
+    /// Log each match on a single line: the address, then its seed and its key
+    #[clap(long, conflicts_with = "verbose_output")]
+    pub compact: bool,
+    /// Command run for each match, with the match as a JSON line on its stdin and in VANITY_* variables
+    /// Split like a shell would, with quotes and backslashes, but run without one.
+    #[clap(long)]
+    pub exec: Option<String>,
+    /// Give the private key, the tweak and the seeds to the `--exec` command
+    #[clap(long, requires = "exec")]
+    pub exec_include_secrets: bool,
+    /// Seconds after which the `--exec` command is killed
+    #[clap(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+    pub exec_timeout: u64,
+    /// Log an address generated at each status tick, to see what the contents are compared to
+    /// Repeat it (`-vv`) to also log the part of the address compared for each placement.
+    #[clap(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Don't log the configuration summary at startup, nor show the spinner in the status line
+    #[clap(short, long)]
+    pub quiet: bool,
+    #[clap(flatten)]
+    pub eta: EtaArgs,
+    /// Validate the configuration, measure the rate and log the estimate, without searching
+    /// The output file is neither created nor locked.
+    #[clap(long)]
+    pub dry_run: bool,
+    /// Validate the configuration and log the estimate of each content, without generating any key
+    /// No thread is started and nothing is written, an output file is only created empty if it doesn't exist.
+    #[clap(long, conflicts_with = "dry_run")]
+    pub validate_only: bool,
+    /// Seed the generator of each worker with this value and its index, to reproduce a search
+    /// INSECURE: only for tests, the same seed and threads always give the same keys.
+    #[clap(long, conflicts_with_all = ["fast_rng", "derive_from"])]
+    pub rng_seed: Option<u64>,
+    /// Derive the keys from this master seed instead of generating random ones: a file holding it, env:<variable> or 64 hex characters
+    /// Anyone knowing the master seed can derive the found keys, keep it as secret as a private key.
+    #[clap(long, value_parser = parse_master_arg, conflicts_with = "fast_rng")]
+    pub derive_from: Option<[u8; MASTER_SIZE]>,
+    /// Public key (64 hex characters) of a customer keeping its private key
+    /// Only the tweak to combine with its private key is reported (see `combine`), never a private key.
+    #[clap(long, value_parser = parse_public_key, conflicts_with_all = ["derive_from", "rng_seed", "fast_rng"])]
+    pub split_key: Option<RistrettoPoint>,
+    /// Generate a new master seed, print it and derive the keys from it like `--derive-from`
+    #[clap(long, conflicts_with_all = ["derive_from", "fast_rng", "rng_seed", "split_key"])]
+    pub new_master: bool,
+    /// First derivation index to search from
+    #[clap(long, default_value_t = 0, requires = "derive_from")]
+    pub start_offset: u64,
+    /// Derivation index to stop the search at (excluded)
+    #[clap(long, requires = "derive_from")]
+    pub end_offset: Option<u64>,
+    /// File to save the derivation offset to, the search resumes from it when it exists
+    #[clap(long, requires = "derive_from")]
+    pub offset_file: Option<PathBuf>,
+    /// Search only this part of the indexes, written <index>/<total> (like 2/8, starting at 1)
+    /// The indexes from `--start-offset` to `--end-offset` are split in equal ranges, one per machine.
+    #[clap(long, value_parser = parse_shard, requires = "derive_from")]
+    pub shard: Option<Shard>,
+    /// File keeping the measured difficulty and rate of each search, improving the estimates of the next runs
+    #[clap(long)]
+    pub stats_file: Option<PathBuf>,
+    /// File keeping the work done by this machine over all its searches, shown by `stats` and at the end
+    /// Defaults to `xelis-vanity-state.json` in the log directory. It's only written at the end of a search.
+    #[clap(long)]
+    pub state_file: Option<PathBuf>,
+    /// Don't load nor save the lifetime statistics of the machine
+    #[clap(long, conflicts_with = "state_file")]
+    pub disable_state_file: bool,
+    /// JSON file to write a summary of the search to once it stopped (attempts, rates, matches, threads and configuration)
+    #[clap(long)]
+    pub report: Option<PathBuf>,
+    #[clap(flatten)]
+    pub common: CommonArgs,
+}
+
+impl SearchArgs {
+    // State file loaded and saved by every search, unless `--disable-state-file`
+    pub fn state_file(&self) -> Option<PathBuf> {
+        if self.disable_state_file {
+            return None;
+        }
+
+        Some(self.state_file.clone().unwrap_or_else(|| Path::new(&self.common.log_dir()).join(DEFAULT_STATE_FILE)))
+    }
+
+    // The prompt is disabled when the output is not a terminal (piped, redirected to a file, systemd)
+    // or when the contents are read from stdin, as it's not available for the commands anymore
+    pub fn is_interactive(&self) -> bool {
+        !self.common.disable_interactive_mode && !self.reads_stdin() && io::stdout().is_terminal()
+    }
+
+    pub fn reads_stdin(&self) -> bool {
+        self.content.iter().any(|content| content == STDIN_CONTENT)
+    }
+
+    // A daemon has no terminal: nothing can be read from it nor written to it
+    pub fn validate_daemon(&self) -> Result<(), String> {
+        if self.reads_stdin() {
+            return Err("--daemon can't read the contents from stdin".to_string());
+        }
+
+        if matches!(self.progress_json.as_deref(), Some("stdout" | "stderr")) {
+            return Err("--daemon can't write the progress to stdout or stderr, use a file".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Index and name of the languages to print the seed in
+    pub fn seed_languages(&self) -> Vec<(usize, &'static str)> {
+        let languages = languages();
+        let indexes = if self.seed_languages.is_empty() {
+            vec![self.language]
+        } else {
+            self.seed_languages.clone()
+        };
+
+        indexes.into_iter()
+            .map(|index| (index, languages[index].name))
+            .collect()
+    }
+
+    // Payment ID to use if we search for an integrated address
+    pub fn payment_id(&self) -> Option<PaymentId> {
+        if !self.integrated {
+            return None;
+        }
+
+        Some(match self.payment_id {
+            Some(value) => PaymentId::Fixed(value),
+            None => PaymentId::Random,
+        })
+    }
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Duration of the benchmark in seconds
+    #[clap(short, long, default_value_t = 10)]
+    pub duration: u64,
+    /// Compare the naive scan of each pattern with the automaton used for the anywhere placement, and measure the charset score
+    #[clap(long)]
+    pub compare_matchers: bool,
+    #[clap(flatten)]
+    pub common: CommonArgs,
+}
+
+#[derive(Args)]
+pub struct EstimateArgs {
+    /// The content for the address to search for
+    #[clap(short, long)]
+    pub content: String,
+    /// Placements of the content in the address (comma separated: prefix, suffix or anywhere)
+    #[clap(short, long, value_delimiter = ',', default_value = "prefix", value_parser = parse_placement)]
+    pub placement: Vec<Placement>,
+    /// Match a suffix at the end of the public key characters instead of the end of the address
+    /// The address type and the checksum characters are skipped, see the README.
+    #[clap(long)]
+    pub before_checksum: bool,
+    /// Estimate for an integrated address embedding a payment ID
+    #[clap(long)]
+    pub integrated: bool,
+    /// Keys per second to use for the time estimation
+    /// By default, a short benchmark is run to measure it.
+    #[clap(short, long)]
+    pub rate: Option<f64>,
+    #[clap(flatten)]
+    pub common: CommonArgs,
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Private key in hex format
+    #[clap(short = 'k', long, conflicts_with_all = ["seed", "derive_from"], required_unless_present_any = ["seed", "derive_from"])]
+    pub private_key: Option<String>,
+    /// Mnemonic seed, words separated by spaces
+    #[clap(short, long, conflicts_with = "derive_from")]
+    pub seed: Option<String>,
+    /// Master seed to derive the key at `--index` from: a file, env:<variable> or 64 hex characters, like `search --derive-from`
+    #[clap(long, value_parser = parse_master_arg, requires = "index")]
+    pub derive_from: Option<[u8; MASTER_SIZE]>,
+    /// Derivation index of the key, logged with each match of a derived search
+    #[clap(long, requires = "derive_from")]
+    pub index: Option<u64>,
+    /// Content expected in the derived address
+    #[clap(short, long)]
+    pub content: Option<String>,
+    /// Placement of the content in the address: prefix, suffix or anywhere
+    #[clap(short, long, default_value_t = Placement::Prefix, value_parser = parse_placement)]
+    pub placement: Placement,
+    /// Match a suffix at the end of the public key characters instead of the end of the address
+    /// The address type and the checksum characters are skipped, see the README.
+    #[clap(long)]
+    pub before_checksum: bool,
+    /// Search a content anywhere in the whole address, including the network prefix and the separator
+    /// By default, only the data part after the separator is searched, like the prefixes.
+    #[clap(long)]
+    pub raw_match: bool,
+    /// Payment ID to derive an integrated address
+    #[clap(long)]
+    pub payment_id: Option<u64>,
+    #[clap(flatten)]
+    pub common: CommonArgs,
+}
+
+#[derive(Args)]
+pub struct DeriveArgs {
+    /// Private key in hex format
+    #[clap(short = 'k', long, conflicts_with = "seed", required_unless_present = "seed")]
+    pub private_key: Option<String>,
+    /// Mnemonic seed, words separated by spaces
+    #[clap(short, long)]
+    pub seed: Option<String>,
+    /// Language of the seed (name or index), detected from its words by default
+    #[clap(short, long, requires = "seed", value_parser = parse_language)]
+    pub language: Option<usize>,
+    /// Payment ID to derive an integrated address
+    #[clap(long)]
+    pub payment_id: Option<u64>,
+    #[clap(flatten)]
+    pub common: CommonArgs,
+}
+
+#[derive(Args)]
+pub struct CombineArgs {
+    /// Private key in hex format, the one of the public key given to `search --split-key`
+    #[clap(short = 'k', long, conflicts_with = "seed", required_unless_present = "seed")]
+    pub private_key: Option<String>,
+    /// Mnemonic seed, words separated by spaces
+    #[clap(short, long)]
+    pub seed: Option<String>,
+    /// Tweak reported by the split-key search (64 hex characters)
+    #[clap(short, long, value_parser = parse_tweak)]
+    pub tweak: Scalar,
+    /// Address reported by the split-key search, the combined key must derive it
+    #[clap(short, long)]
+    pub address: Option<String>,
+    /// Language of the seed printed (name or index)
+    #[clap(short, long, default_value = "english", value_parser = parse_language)]
+    pub language: usize,
+    /// Payment ID of the integrated address searched
+    #[clap(long)]
+    pub payment_id: Option<u64>,
+    #[clap(flatten)]
+    pub common: CommonArgs,
+}
+
+#[derive(Args)]
+pub struct LanguagesArgs {
+    #[clap(flatten)]
+    pub common: CommonArgs,
+}
+
+#[derive(Args)]
+pub struct WorkerArgs {
+    /// Address of the coordinator (host:port)
+    #[clap(long)]
+    pub connect: String,
+    /// Name of this worker shown by the coordinator
+    #[clap(long)]
+    pub name: Option<String>,
+    /// Public key of the coordinator (64 hex characters), the jobs sealed to another key are refused
+    /// Without it, the key sent with the first job is trusted and required for the next ones.
+    #[clap(long, value_parser = parse_public_key)]
+    pub coordinator_key: Option<RistrettoPoint>,
+    /// Run the worker threads at the lowest scheduling priority, to only use the idle CPU time
+    #[clap(long)]
+    pub low_priority: bool,
+    #[clap(flatten)]
+    pub common: CommonArgs,
+}
+
+#[derive(Args)]
+pub struct CoordinatorArgs {
+    /// Address to accept the workers on (host:port)
+    #[clap(long)]
+    pub bind: String,
+    /// The content for the address to search for
+    /// Repeat it to search for several contents at once, the first one found is reported.
+    #[clap(short, long, required = true)]
+    pub content: Vec<String>,
+    /// Placements of the content in the address (comma separated: prefix, suffix or anywhere), the first one found is reported
+    #[clap(short, long, value_delimiter = ',', default_value = "prefix", value_parser = parse_placement)]
+    pub placement: Vec<Placement>,
+    /// Number of addresses to find before stopping the workers
+    /// Without it, the search runs until it's stopped, and succeeds if at least one address was found.
+    #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+    pub count: Option<u64>,
+    /// Derive the keys from this master seed (a file, env:<variable> or 64 hex characters), split in ranges between the workers
+    /// The master seed is sent to the workers, only use it with trusted ones.
+    #[clap(long, value_parser = parse_master_arg)]
+    pub derive_from: Option<[u8; MASTER_SIZE]>,
+    /// Generate a new master seed, print it and derive the keys from it like `--derive-from`
+    #[clap(long, conflicts_with = "derive_from")]
+    pub new_master: bool,
+    /// First derivation index to search from
+    #[clap(long, default_value_t = 0)]
+    pub start_offset: u64,
+    /// Number of indexes in the range of a worker, a new range is assigned once it's searched
+    #[clap(long, default_value_t = 1_000_000_000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub range_size: u64,
+    /// Language for the seed, by name (english, french, ...) or index
+    #[clap(short, long, default_value = "english", value_parser = parse_language)]
+    pub language: usize,
+    /// Restore the address from the seed of each match before reporting it
+    #[clap(long)]
+    pub verify: bool,
+    #[clap(flatten)]
+    pub eta: EtaArgs,
+    #[clap(flatten)]
+    pub common: CommonArgs,
+}
+
+impl CoordinatorArgs {
+    // The prompt is disabled when the output is not a terminal, like the search
+    pub fn is_interactive(&self) -> bool {
+        !self.common.disable_interactive_mode && io::stdout().is_terminal()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_threads_is_refused() {
+        assert!("0".parse::<ThreadCount>().unwrap_err().starts_with("0 threads can't search"));
+        assert!("0%".parse::<ThreadCount>().is_err());
+    }
+
+    #[test]
+    fn test_one_thread() {
+        assert_eq!("1".parse::<ThreadCount>(), Ok(ThreadCount::Count(1)));
+        assert_eq!(ThreadCount::Count(1).resolve(16), 1);
+        assert_eq!("1%".parse::<ThreadCount>(), Ok(ThreadCount::Percent(1)));
+        // Less than a thread, refused once resolved
+        assert_eq!(ThreadCount::Percent(1).resolve(16), 0);
+    }
+
+    #[test]
+    fn test_too_many_threads_is_refused() {
+        assert_eq!(MAX_THREADS.to_string().parse::<ThreadCount>(), Ok(ThreadCount::Count(MAX_THREADS)));
+        assert_eq!((MAX_THREADS + 1).to_string().parse::<ThreadCount>(), Err(format!("{} threads is too many, the maximum is {}", MAX_THREADS + 1, MAX_THREADS)));
+        assert!("101%".parse::<ThreadCount>().is_err());
+        assert!("99999999999999999999999".parse::<ThreadCount>().is_err());
+        assert!("-1".parse::<ThreadCount>().is_err());
+    }
+
+    #[test]
+    fn test_thread_percentage() {
+        assert_eq!("75%".parse::<ThreadCount>(), Ok(ThreadCount::Percent(75)));
+        assert_eq!(ThreadCount::Percent(75).resolve(16), 12);
+        assert_eq!(ThreadCount::Percent(100).resolve(16), 16);
+    }
+
+    #[test]
+    fn test_placement_from_str() {
+        assert_eq!("prefix".parse::<Placement>(), Ok(Placement::Prefix));
+        assert_eq!("suffix".parse::<Placement>(), Ok(Placement::Suffix));
+        assert_eq!(" anywhere ".parse::<Placement>(), Ok(Placement::Anywhere));
+    }
+
+    #[test]
+    fn test_placement_ignores_case() {
+        assert_eq!("Suffix".parse::<Placement>(), Ok(Placement::Suffix));
+        assert_eq!("PREFIX".parse::<Placement>(), Ok(Placement::Prefix));
+        assert_eq!("AnyWhere".parse::<Placement>(), Ok(Placement::Anywhere));
+    }
+
+    #[test]
+    fn test_invalid_placement() {
+        assert_eq!("middle".parse::<Placement>(), Err("unknown placement 'middle', expected one of: prefix, suffix, anywhere".to_string()));
+        assert!("".parse::<Placement>().is_err());
+    }
+
+    #[test]
+    fn test_cli_placement_error_matches() {
+        let error = Config::try_parse_from(["xelis-vanity", "search", "--content", "cafe", "--placement", "middle"]).err().unwrap();
+        assert!(error.to_string().contains("unknown placement 'middle', expected one of: prefix, suffix, anywhere"));
+
+        let Command::Search(args) = Config::try_parse_from(["xelis-vanity", "search", "--content", "cafe", "--placement", "Suffix,PREFIX"]).unwrap().command else {
+            panic!("expected a search");
+        };
+        assert_eq!(args.placement, [Placement::Suffix, Placement::Prefix]);
+    }
+
+    #[test]
+    fn test_daemon_needs_the_interactive_mode_disabled() {
+        let search = |options: &[&str]| Config::try_parse_from(["xelis-vanity", "search", "--content", "cafe"].iter().chain(options));
+        assert!(search(&["--daemon"]).is_err());
+        assert!(search(&["--daemon", "--disable-interactive-mode", "--disable-file-logging"]).is_err());
+        assert!(search(&["--daemon", "--disable-interactive-mode", "--dry-run"]).is_err());
+
+        let Command::Search(args) = search(&["--daemon", "--disable-interactive-mode"]).unwrap().command else {
+            panic!("expected a search");
+        };
+        assert!(args.daemon && !args.is_interactive());
+        assert!(args.validate_daemon().is_ok());
+        assert!(search(&["--daemon", "--disable-interactive-mode", "--validate-only"]).is_ok());
+    }
+
+    // The names are the ones parsed by clap, so a default value or a saved name is parsed back
+    fn assert_displayed_as_parsed<T: clap::ValueEnum + fmt::Display + PartialEq + fmt::Debug>() {
+        for value in T::value_variants() {
+            assert_eq!(&<T as clap::ValueEnum>::from_str(&value.to_string(), false).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn test_display_names_are_parsed_back() {
+        assert_displayed_as_parsed::<Network>();
+        assert_displayed_as_parsed::<Placement>();
+        assert_displayed_as_parsed::<SearchMode>();
+        assert_displayed_as_parsed::<RateUnit>();
+        assert_eq!(format!("{} {}", Network::Testnet, Placement::Suffix), "testnet suffix");
+        assert_eq!(SearchMode::CharsetScore.to_string(), "charset-score");
+    }
+
+    #[test]
+    fn test_log_levels() {
+        assert!(matches!(parse_log_level("info"), Ok(LogLevel::Info)));
+        assert!(matches!(parse_log_level("TRACE"), Ok(LogLevel::Trace)));
+        assert!(matches!(parse_log_level("off"), Ok(LogLevel::Off)));
+        assert_eq!(parse_log_level("verbose").err().unwrap(), "unknown log level 'verbose', expected one of: off, error, warn, info, debug, trace");
+
+        let Command::Search(args) = Config::try_parse_from(["xelis-vanity", "search", "--content", "cafe", "--file-log-level", "Debug"]).unwrap().command else {
+            panic!("expected a search");
+        };
+        assert!(matches!(args.common.log_level, LogLevel::Info));
+        assert!(matches!(args.common.file_log_level, Some(LogLevel::Debug)));
+    }
+
+    #[test]
+    fn test_combine_language_defaults_to_english() {
+        let tweak = format!("01{}", "00".repeat(31));
+        let Command::Combine(args) = Config::try_parse_from(["xelis-vanity", "combine", "--private-key", "00", "--tweak", tweak.as_str()]).unwrap().command else {
+            panic!("expected a combine");
+        };
+        assert_eq!(args.language, parse_language("english").unwrap());
+    }
+
+    #[test]
+    fn test_state_file_defaults_to_the_log_dir() {
+        let search = |options: &[&str]| match Config::try_parse_from(["xelis-vanity", "search", "--content", "cafe"].iter().chain(options)).unwrap().command {
+            Command::Search(args) => args.state_file(),
+            _ => panic!("expected a search"),
+        };
+        assert_eq!(search(&[]), Some(PathBuf::from("logs/xelis-vanity-state.json")));
+        assert_eq!(search(&["--log-dir", "/var/log/vanity"]), Some(PathBuf::from("/var/log/vanity/xelis-vanity-state.json")));
+        assert_eq!(search(&["--state-file", "state.json"]), Some(PathBuf::from("state.json")));
+        assert_eq!(search(&["--disable-state-file"]), None);
+        assert!(Config::try_parse_from(["xelis-vanity", "search", "--content", "cafe", "--state-file", "state.json", "--disable-state-file"]).is_err());
+    }
+
+    #[test]
+    fn test_daemon_has_no_terminal() {
+        let Command::Search(args) = Config::try_parse_from(["xelis-vanity", "search", "--content", "-", "--daemon", "--disable-interactive-mode"]).unwrap().command else {
+            panic!("expected a search");
+        };
+        assert!(args.validate_daemon().unwrap_err().contains("stdin"));
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf}
+};
+use clap::{
+    error::{ContextKind, ContextValue},
+    parser::ValueSource,
+    Arg,
+    ArgAction,
+    ArgMatches,
+    Command
+};
+use toml::{Spanned, Value};
+
+// Configuration file loaded when `--config` is not given, if it exists
+pub const DEFAULT_CONFIG_FILE: &str = "xelis-vanity.toml";
+
+// Options that can only be given on the command line
+const COMMAND_LINE_ONLY: [&str; 2] = ["config", "print_config"];
+
+// Options deciding where the keys go, how they are generated or what runs with them
+// The default file is loaded from the working directory without being asked for, so it can't set them:
+// they are only accepted from a file given with `--config`
+const SENSITIVE: [&str; 20] = [
+    "exec", "exec_include_secrets", "derive_from", "rng_seed", "fast_rng", "split_key",
+    "api_bind", "api_token", "api_expose_secrets", "control_socket", "connect", "coordinator_key",
+    "output", "output_fields", "log_dir", "log_file", "log_level", "file_log_level", "state_file", "pid_file",
+];
+
+// Printed by `--print-config` instead of the value of a sensitive option
+const REDACTED: &str = "<redacted>";
+
+// Value of an option set by the file
+struct Applied {
+    key: String,
+    long: String,
+    line: usize,
+}
+
+// Configuration file, its keys are the options of the subcommands (`content`, `num_threads`, ...)
+// The options given on the command line are overriding its values
+pub struct ConfigFile {
+    pub path: PathBuf,
+    // Given with `--config`, instead of the default file found in the working directory
+    explicit: bool,
+    source: String,
+    values: BTreeMap<String, Spanned<Value>>,
+    applied: Vec<Applied>,
+}
+
+impl ConfigFile {
+    // Read and parse the file, None if no file is given and the default one doesn't exist
+    pub fn load(path: Option<&Path>) -> Result<Option<Self>, String> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+        };
+
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == ErrorKind::NotFound && !explicit => return Ok(None),
+            Err(e) => return Err(format!("couldn't read configuration file {}: {}", path.display(), e)),
+        };
+
+        // The error is already naming the line and the column
+        let values = toml::from_str(&source)
+            .map_err(|e| format!("invalid configuration file {}: {}", path.display(), e))?;
+
+        Ok(Some(Self {
+            path,
+            explicit,
+            source,
+            values,
+            applied: Vec::new(),
+        }))
+    }
+
+    // Line of a value, for the error messages
+    fn line(&self, value: &Spanned<Value>) -> usize {
+        self.source[..value.span().start].matches('\n').count() + 1
+    }
+
+    fn error(&self, key: &str, value: &Spanned<Value>, message: &str) -> String {
+        format!("{} in configuration file {}, key '{}' at line {}", message, self.path.display(), key, self.line(value))
+    }
+
+    // Arguments of the subcommand set by the file, to insert before the command line ones
+    // Keys of the other subcommands are ignored so a single file can be shared by all of them
+    pub fn arguments(&mut self, command: &Command, subcommand: &str, matches: &ArgMatches) -> Result<Vec<OsString>, String> {
+        let mut arguments = Vec::new();
+        for (name, value) in &self.values {
+            // Keys can be written like the options too (`num-threads`)
+            let key = name.replace('-', "_");
+            if COMMAND_LINE_ONLY.contains(&key.as_str()) {
+                return Err(self.error(name, value, "option can only be given on the command line"));
+            }
+            if !self.explicit && SENSITIVE.contains(&key.as_str()) {
+                return Err(self.error(name, value, "option is only accepted from a file given with --config, not from the default one"));
+            }
+
+            let Some(arg) = find_argument(command, subcommand, &key) else {
+                if command.get_subcommands().any(|other| other.get_arguments().any(|arg| arg.get_id() == key.as_str())) {
+                    continue;
+                }
+
+                return Err(self.error(name, value, "unknown option"));
+            };
+
+            // The command line is overriding the file
+            if matches.value_source(&key) == Some(ValueSource::CommandLine) {
+                continue;
+            }
+
+            let Some(long) = arg.get_long() else {
+                return Err(self.error(name, value, "option can't be set from a file"));
+            };
+
+            match arg.get_action() {
+                ArgAction::SetTrue => match value.get_ref() {
+                    Value::Boolean(true) => arguments.push(OsString::from(format!("--{}", long))),
+                    Value::Boolean(false) => {},
+                    _ => return Err(self.error(name, value, "expected a boolean")),
+                },
+                // Repeated like on the command line, `verbose = 2` is `-vv`
+                ArgAction::Count => match value.get_ref() {
+                    Value::Integer(count @ 0..=255) => arguments.extend((0..*count).map(|_| OsString::from(format!("--{}", long)))),
+                    _ => return Err(self.error(name, value, "expected a number between 0 and 255")),
+                },
+                ArgAction::Set | ArgAction::Append => {
+                    let values = match value.get_ref() {
+                        Value::Array(values) if matches!(arg.get_action(), ArgAction::Append) => values.iter().collect(),
+                        Value::Array(_) => return Err(self.error(name, value, "expected a single value")),
+                        value => vec![value],
+                    };
+
+                    // Items of an array are not spanned, the errors point to the key
+                    for item in values {
+                        let item = to_argument(item).ok_or_else(|| self.error(name, value, "expected a string, a number or a boolean"))?;
+                        // Attached to the option so a value starting with '-' is not taken as an option
+                        arguments.push(OsString::from(format!("--{}={}", long, item)));
+                    }
+                },
+                _ => return Err(self.error(name, value, "option can't be set from a file")),
+            }
+
+            let line = self.line(value);
+            self.applied.push(Applied {
+                key: name.clone(),
+                long: long.to_string(),
+                line,
+            });
+        }
+
+        Ok(arguments)
+    }
+
+    // Point to the key of the file whose value was rejected, if the error comes from one
+    pub fn explain(&self, error: &clap::Error) -> Option<String> {
+        let Some(ContextValue::String(invalid)) = error.get(ContextKind::InvalidArg) else {
+            return None;
+        };
+
+        let applied = self.applied.iter().find(|applied| {
+            let option = format!("--{}", applied.long);
+            invalid == &option || invalid.starts_with(&format!("{} ", option)) || invalid.starts_with(&format!("{}=", option))
+        })?;
+
+        // First line of the rendered error, without its "error: " header
+        let rendered = error.render().to_string();
+        let message = rendered.lines().next().unwrap_or_default();
+        Some(format!("{} (configuration file {}, key '{}' at line {})", message.trim_start_matches("error: "), self.path.display(), applied.key, applied.line))
+    }
+
+    // Check if an option of the subcommand was set by the file
+    pub fn is_applied(&self, key: &str) -> bool {
+        self.applied.iter().any(|applied| applied.key.replace('-', "_") == key)
+    }
+}
+
+fn find_argument<'a>(command: &'a Command, subcommand: &str, key: &str) -> Option<&'a Arg> {
+    command.find_subcommand(subcommand)?
+        .get_arguments()
+        .find(|arg| arg.get_id() == key)
+}
+
+fn to_argument(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Integer(value) => Some(value.to_string()),
+        Value::Float(value) => Some(value.to_string()),
+        Value::Boolean(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+// Print the effective configuration of the subcommand in the file format
+// Each option is followed by where its value comes from, to debug the precedence
+pub fn print_effective(command: &Command, subcommand: &str, matches: &ArgMatches, file: Option<&ConfigFile>) {
+    for line in effective_lines(command, subcommand, matches, file) {
+        println!("{}", line);
+    }
+}
+
+// Lines printed by `print_effective`
+// The values of the sensitive options are redacted, they may be a master seed, a token or a command line
+// and this output ends up in terminals and CI logs. Their flags are only true or false, they're kept
+fn effective_lines(command: &Command, subcommand: &str, matches: &ArgMatches, file: Option<&ConfigFile>) -> Vec<String> {
+    let Some(subcommand) = command.find_subcommand(subcommand) else {
+        return Vec::new();
+    };
+
+    let mut lines = vec![format!("# Effective configuration of the '{}' subcommand", subcommand.get_name())];
+    match file {
+        Some(file) => lines.push(format!("# Configuration file: {}", file.path.display())),
+        None => lines.push("# Configuration file: none".to_string()),
+    }
+
+    for arg in subcommand.get_arguments() {
+        let key = arg.get_id().as_str();
+        if COMMAND_LINE_ONLY.contains(&key) || !matches!(arg.get_action(), ArgAction::SetTrue | ArgAction::Set | ArgAction::Append) {
+            continue;
+        }
+
+        let Some(values) = matches.get_raw(key) else {
+            lines.push(format!("# {} is not set", key));
+            continue;
+        };
+
+        let values = values.map(|value| value.to_string_lossy().to_string()).collect::<Vec<_>>();
+        let value = match arg.get_action() {
+            ArgAction::SetTrue => values.join(""),
+            _ if SENSITIVE.contains(&key) => to_toml(REDACTED),
+            ArgAction::Append => format!("[{}]", values.iter().map(|value| to_toml(value)).collect::<Vec<_>>().join(", ")),
+            _ => values.first().map(|value| to_toml(value)).unwrap_or_default(),
+        };
+
+        let origin = if file.is_some_and(|file| file.is_applied(key)) {
+            "configuration file"
+        } else {
+            match matches.value_source(key) {
+                Some(ValueSource::CommandLine) => "command line",
+                Some(ValueSource::EnvVariable) => "environment",
+                _ => "default",
+            }
+        };
+
+        lines.push(format!("{} = {} # {}", key, value, origin));
+    }
+
+    lines
+}
+
+// Numbers are written as is, everything else as a string
+// A content like "0123" stays a string to keep its leading zero
+fn to_toml(value: &str) -> String {
+    match value.parse::<i64>() {
+        Ok(number) if number.to_string() == value => value.to_string(),
+        _ => Value::String(value.to_string()).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+    use crate::config::Config;
+    use super::*;
+
+    fn lines(args: &[&str]) -> Vec<String> {
+        let command = Config::command();
+        let matches = command.clone().try_get_matches_from(args).unwrap();
+        let (name, sub_matches) = matches.subcommand().unwrap();
+        effective_lines(&command, name, sub_matches, None)
+    }
+
+    #[test]
+    fn test_print_config_redacts_the_secrets() {
+        let master = "ab".repeat(32);
+        let lines = lines(&["xelis-vanity", "search", "--content", "cafe", "--derive-from", &master, "--exec", "notify-send found", "--exec-include-secrets"]);
+        assert!(lines.iter().all(|line| !line.contains(&master) && !line.contains("notify-send")));
+        assert!(lines.contains(&"derive_from = \"<redacted>\" # command line".to_string()));
+        assert!(lines.contains(&"exec = \"<redacted>\" # command line".to_string()));
+        assert!(lines.contains(&"exec_include_secrets = true # command line".to_string()));
+        assert!(lines.contains(&"content = [\"cafe\"] # command line".to_string()));
+    }
+}
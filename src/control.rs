@@ -0,0 +1,175 @@
+use std::{path::Path, sync::Arc};
+#[cfg(unix)]
+use std::{
+    fs,
+    os::unix::{fs::FileTypeExt, net::UnixStream as StdUnixStream},
+    time::Duration
+};
+#[cfg(unix)]
+use log::debug;
+use log::{info, warn};
+use serde_json::{json, Value};
+use xelis_common::tokio::sync::Notify;
+#[cfg(unix)]
+use xelis_common::tokio::{
+    self,
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream}
+};
+use crate::{
+    api::ApiState,
+    searcher::SearchControl
+};
+
+// Line-based control of a running search over a Unix socket, see `--control-socket`
+// Each command is answered by a single JSON line: {"ok":true,"data":...} or {"ok":false,"error":"..."}
+
+// Longest command accepted, the others are rejected and the connection closed
+const MAX_COMMAND_SIZE: u64 = 1024;
+
+// What the commands act on
+pub struct Controller {
+    pub search: Arc<SearchControl>,
+    // Counters and results of the search, the private keys are never sent
+    pub state: Arc<ApiState>,
+    // Notified by `stop`, the search then stops like with the `exit` command
+    pub stop: Arc<Notify>,
+}
+
+impl Controller {
+    async fn execute(&self, line: &str) -> Value {
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        let result = match words.as_slice() {
+            ["status"] => Ok(json!(self.state.status().await)),
+            ["pause"] => {
+                self.search.pause();
+                info!("Search paused by the control socket");
+                Ok(json!({ "paused": true }))
+            },
+            ["resume"] => {
+                self.search.resume();
+                info!("Search resumed by the control socket");
+                Ok(json!({ "paused": false }))
+            },
+            ["threads", threads] => threads.parse()
+                .map_err(|_| format!("invalid number of threads: {}", threads))
+                .and_then(|threads| self.search.set_active_threads(threads).map(|()| threads))
+                .map(|threads| {
+                    info!("Searching with {} threads, set by the control socket", threads);
+                    json!({ "active_threads": threads })
+                }),
+            ["stop"] => {
+                info!("Stop requested by the control socket");
+                self.stop.notify_one();
+                Ok(json!({ "stopping": true }))
+            },
+            ["found"] => Ok(json!(self.state.results(false))),
+            _ => Err(format!("unknown command '{}', expected status, pause, resume, threads <n>, stop or found", line)),
+        };
+
+        match result {
+            Ok(data) => json!({ "ok": true, "data": data }),
+            Err(e) => json!({ "ok": false, "error": e }),
+        }
+    }
+}
+
+// Listening socket, its file is deleted by `remove` once the search stopped
+pub struct ControlSocket {
+    #[cfg(unix)]
+    listener: UnixListener,
+}
+
+#[cfg(unix)]
+impl ControlSocket {
+    // Only the owner can connect to the socket
+    // A socket left by a crashed run is replaced, one still answering is kept
+    pub fn bind(path: &Path) -> Result<Self, String> {
+        if let Ok(metadata) = fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(format!("{} already exists and is not a socket", path.display()));
+            }
+
+            if StdUnixStream::connect(path).is_ok() {
+                return Err(format!("{} is used by another running search", path.display()));
+            }
+
+            warn!("Replacing the control socket {} left by a previous run", path.display());
+            fs::remove_file(path)
+                .map_err(|e| format!("couldn't remove the control socket {}: {}", path.display(), e))?;
+        }
+
+        // The umask applies from the creation, so the socket is never open to the others
+        let umask = unsafe { libc::umask(0o177) };
+        let listener = UnixListener::bind(path);
+        unsafe { libc::umask(umask) };
+
+        Ok(Self {
+            listener: listener.map_err(|e| format!("couldn't listen on {}: {}", path.display(), e))?,
+        })
+    }
+
+    // Answer the commands until the task is aborted at the end of the search
+    pub async fn serve(self, controller: Arc<Controller>) {
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, _)) => {
+                    let controller = Arc::clone(&controller);
+                    tokio::spawn(async move {
+                        if let Err(e) = handle(stream, &controller).await {
+                            debug!("Control connection failed: {}", e);
+                        }
+                    });
+                },
+                Err(e) => {
+                    warn!("Couldn't accept a control connection: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+impl ControlSocket {
+    pub fn bind(_: &Path) -> Result<Self, String> {
+        Err("--control-socket is only available on Unix".to_string())
+    }
+
+    pub async fn serve(self, _: Arc<Controller>) {
+        std::future::pending().await
+    }
+}
+
+// Remove the socket file once the server task is stopped
+pub fn remove(path: &Path) {
+    if let Err(e) = std::fs::remove_file(path) {
+        warn!("Couldn't remove the control socket {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(unix)]
+async fn handle(stream: UnixStream, controller: &Controller) -> Result<(), String> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let read = (&mut reader).take(MAX_COMMAND_SIZE).read_line(&mut line).await.map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Ok(());
+        }
+
+        let too_long = !line.ends_with('\n') && read as u64 == MAX_COMMAND_SIZE;
+        let response = if too_long {
+            json!({ "ok": false, "error": format!("command longer than {} bytes", MAX_COMMAND_SIZE) })
+        } else {
+            controller.execute(line.trim()).await
+        };
+
+        writer.write_all(format!("{}\n", response).as_bytes()).await.map_err(|e| e.to_string())?;
+        if too_long {
+            return Ok(());
+        }
+    }
+}
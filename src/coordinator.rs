@@ -0,0 +1,438 @@
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    net::SocketAddr,
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::{Duration, Instant}
+};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use log::{error, info, warn};
+use xelis_common::{
+    crypto::KeyPair,
+    time::get_current_time_in_millis,
+    tokio::{
+        self,
+        net::{TcpListener, TcpStream},
+        sync::{mpsc::{self, UnboundedSender}, watch}
+    }
+};
+use crate::{
+    config::{Network, Placement},
+    derivation::{self, Derivation},
+    keys,
+    matcher::PatternSet,
+    protocol::{self, CoordinatorMessage, Job, JobRange, SealingKey, Submission, WorkerMessage, PROTOCOL_VERSION},
+    searcher::{FoundKey, FoundResult},
+    split,
+    stats::TOTAL_ATTEMPTS
+};
+
+// Time given to the workers to receive the stop message
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Worker connected to the coordinator
+#[derive(Clone)]
+pub struct Peer {
+    // Name given by the worker, or its address
+    pub name: String,
+    pub threads: usize,
+    pub job: u64,
+    // Keys per second of the last report, and keys generated for the current job since the connection
+    pub rate: f64,
+    pub attempts: u64,
+    // Offset of the last report in a derived job, the range is released from it
+    pub offset: Option<u64>,
+    pub last_seen: Instant,
+}
+
+// Jobs and workers, only locked for short updates
+#[derive(Default)]
+struct State {
+    peers: BTreeMap<u64, Peer>,
+    next_peer: u64,
+    // Next job ID and first index of the next range of a derived search
+    next_job: u64,
+    next_index: u64,
+    // Ranges of the workers that left before finishing them
+    released: VecDeque<Job>,
+    // Addresses already reported, a worker sends its results again after reconnecting
+    reported: HashSet<String>,
+}
+
+// Hands the jobs to the workers and collects their results, see `coordinator --bind`
+pub struct Coordinator {
+    network: Network,
+    patterns: PatternSet,
+    placements: Vec<Vec<Placement>>,
+    key: SealingKey,
+    // Master seed split in ranges of `range_size` indexes between the workers
+    derivation: Option<Derivation>,
+    range_size: u64,
+    state: Mutex<State>,
+    // Taken once the search is over so the reporter stops
+    sender: Mutex<Option<UnboundedSender<FoundResult>>>,
+    stop: watch::Sender<bool>,
+}
+
+impl Coordinator {
+    pub fn new(network: Network, patterns: PatternSet, placements: Vec<Vec<Placement>>, derivation: Option<Derivation>, range_size: u64, sender: UnboundedSender<FoundResult>) -> Self {
+        let state = State {
+            next_job: 1,
+            next_index: derivation.map_or(0, |derivation| derivation.start),
+            ..Default::default()
+        };
+
+        Self {
+            network,
+            patterns,
+            placements,
+            key: SealingKey::generate(),
+            derivation,
+            range_size,
+            state: Mutex::new(state),
+            sender: Mutex::new(Some(sender)),
+            stop: watch::channel(false).0,
+        }
+    }
+
+    // Public key the workers seal the keys to, for `worker --coordinator-key`
+    pub fn public_key(&self) -> String {
+        self.key.public_key()
+    }
+
+    // Connected workers, by connection order
+    pub fn peers(&self) -> Vec<Peer> {
+        self.state.lock().unwrap().peers.values().cloned().collect()
+    }
+
+    // Sum of the rates reported by the workers
+    pub fn rate(&self) -> f64 {
+        self.state.lock().unwrap().peers.values().map(|peer| peer.rate).sum()
+    }
+
+    // Accept the workers until the task is aborted
+    pub async fn listen(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, address)) => {
+                    tokio::spawn(Arc::clone(&self).handle(stream, address));
+                },
+                Err(e) => {
+                    warn!("Couldn't accept a worker: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    }
+
+    // Send the stop message to the workers and wait for them to disconnect
+    pub async fn stop(&self) {
+        self.stop.send_replace(true);
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while !self.state.lock().unwrap().peers.is_empty() && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    // Stop delivering results, the reporter stops once it reported the previous ones
+    pub fn close(&self) {
+        self.sender.lock().unwrap().take();
+    }
+
+    async fn handle(self: Arc<Self>, stream: TcpStream, address: SocketAddr) {
+        let (mut reader, mut writer) = stream.into_split();
+
+        // Reading a message is not cancel safe, so it's done by its own task
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        let reader_task = tokio::spawn(async move {
+            loop {
+                let message = protocol::read_message::<_, WorkerMessage>(&mut reader).await;
+                let failed = message.is_err();
+                if sender.send(message).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let (name, threads, previous, public_key) = match messages.recv().await {
+            Some(Ok(WorkerMessage::Hello { version, name, threads, job, public_key })) if version == PROTOCOL_VERSION => (name.unwrap_or_else(|| address.to_string()), threads, job, public_key),
+            Some(Ok(WorkerMessage::Hello { version, .. })) => {
+                warn!("Worker {} uses the protocol version {}, expected {}", address, version, PROTOCOL_VERSION);
+                reader_task.abort();
+                return;
+            },
+            Some(Ok(_)) => {
+                warn!("Worker {} didn't introduce itself", address);
+                reader_task.abort();
+                return;
+            },
+            Some(Err(e)) => {
+                warn!("Worker {} disconnected: {}", address, e);
+                return;
+            },
+            None => return,
+        };
+
+        // The master seed of the derived jobs is sealed to it
+        let worker_key = match split::parse_public_key(&public_key) {
+            Ok(key) => key,
+            Err(e) => {
+                warn!("Worker {} sent an invalid public key: {}", address, e);
+                reader_task.abort();
+                return;
+            }
+        };
+
+        let mut job = match self.assign(previous, &worker_key) {
+            Ok(job) => job,
+            Err(e) => {
+                error!("Couldn't assign a job to the worker {}: {}", address, e);
+                reader_task.abort();
+                return;
+            }
+        };
+        let id = {
+            let mut state = self.state.lock().unwrap();
+            let id = state.next_peer;
+            state.next_peer += 1;
+            state.peers.insert(id, Peer {
+                name: name.clone(),
+                threads,
+                job: job.id,
+                rate: 0f64,
+                attempts: 0,
+                offset: None,
+                last_seen: Instant::now(),
+            });
+            id
+        };
+        info!("Worker {} joined with {} threads", name, threads);
+
+        let mut stop = self.stop.subscribe();
+        let result: Result<(), String> = async {
+            if *stop.borrow_and_update() {
+                return protocol::write_message(&mut writer, &CoordinatorMessage::Stop).await;
+            }
+
+            protocol::write_message(&mut writer, &CoordinatorMessage::Job(job.clone())).await?;
+            loop {
+                tokio::select! {
+                    message = messages.recv() => match message.unwrap_or_else(|| Err("connection closed".to_string()))? {
+                        WorkerMessage::Rate { job: reported, rate, attempts, offset } => self.update(id, reported, rate, attempts, offset),
+                        WorkerMessage::Found(submission) => self.accept(id, &name, submission),
+                        WorkerMessage::Finished { job: done } if done == job.id && self.derivation.is_some() => {
+                            job = self.assign(None, &worker_key)?;
+                            self.start_job(id, job.id);
+                            info!("Worker {} finished its range, assigned job #{}", name, job.id);
+                            protocol::write_message(&mut writer, &CoordinatorMessage::Job(job.clone())).await?;
+                        },
+                        WorkerMessage::Finished { .. } | WorkerMessage::Hello { .. } => {},
+                    },
+                    _ = stop.changed() => return protocol::write_message(&mut writer, &CoordinatorMessage::Stop).await,
+                }
+            }
+        }.await;
+
+        reader_task.abort();
+        if let Err(e) = result {
+            warn!("Worker {} disconnected: {}", name, e);
+        } else {
+            info!("Worker {} stopped", name);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let offset = state.peers.remove(&id).and_then(|peer| peer.offset);
+        // Another worker searches the rest of the range
+        if let Some(job) = release(job, offset) {
+            state.released.push_back(job);
+        }
+    }
+
+    // Job of a new connection
+    // In a derived search, a worker reconnecting gets its range back if nobody took it
+    // The master seed is sealed to the worker, a released range is sealed again to its new worker
+    fn assign(&self, previous: Option<u64>, worker_key: &RistrettoPoint) -> Result<Job, String> {
+        let mut state = self.state.lock().unwrap();
+        let Some(derivation) = &self.derivation else {
+            return Ok(self.job(0, None));
+        };
+        let master = protocol::seal(worker_key, &derivation::format_master(&derivation.master))?;
+
+        let released = previous.and_then(|id| state.released.iter().position(|job| job.id == id))
+            .and_then(|position| state.released.remove(position))
+            .or_else(|| state.released.pop_front());
+        if let Some(mut job) = released {
+            if let Some(range) = &mut job.derivation {
+                range.master = master;
+            }
+            return Ok(job);
+        }
+
+        let start = state.next_index;
+        let end = start.saturating_add(self.range_size);
+        state.next_index = end;
+        let id = state.next_job;
+        state.next_job += 1;
+
+        Ok(self.job(id, Some(JobRange {
+            master,
+            start,
+            end,
+        })))
+    }
+
+    fn job(&self, id: u64, derivation: Option<JobRange>) -> Job {
+        Job {
+            id,
+            network: self.network,
+            contents: self.patterns.contents().to_vec(),
+            placements: self.placements.clone(),
+            derivation,
+            public_key: self.key.public_key(),
+        }
+    }
+
+    fn start_job(&self, peer: u64, job: u64) {
+        if let Some(peer) = self.state.lock().unwrap().peers.get_mut(&peer) {
+            peer.job = job;
+            peer.attempts = 0;
+            peer.offset = None;
+        }
+    }
+
+    // Rate report of a worker, with the keys generated since its previous report
+    // They're counted once even if the worker reconnected meanwhile
+    fn update(&self, peer: u64, job: u64, rate: f64, attempts: u64, offset: Option<u64>) {
+        let mut state = self.state.lock().unwrap();
+        let Some(peer) = state.peers.get_mut(&peer) else {
+            return;
+        };
+
+        TOTAL_ATTEMPTS.fetch_add(attempts, Ordering::Relaxed);
+        if peer.job == job {
+            peer.attempts += attempts;
+            peer.offset = offset;
+        }
+
+        peer.rate = rate;
+        peer.last_seen = Instant::now();
+    }
+
+    // Check a result of a worker and deliver it to the reporter
+    // The key must derive the address, and the address must match a content
+    fn accept(&self, peer: u64, name: &str, submission: Submission) {
+        let private_key = match self.key.open(&submission.secret).and_then(|key| keys::parse_private_key(&key)) {
+            Ok(private_key) => private_key,
+            Err(e) => {
+                warn!("Invalid result from worker {}: {}", name, e);
+                return;
+            }
+        };
+
+        let keypair = KeyPair::from_private_key(private_key);
+        let address = keys::derive_address(&keypair, self.network, None);
+        if address != submission.address {
+            warn!("Worker {} sent the key of {} for {}", name, address, submission.address);
+            return;
+        }
+
+        let Some(found) = self.patterns.find(&address) else {
+            warn!("Worker {} sent {} which doesn't match any content", name, address);
+            return;
+        };
+
+        // The index is only kept if it derives the key
+        let index = submission.index.filter(|index| self.derivation.is_some_and(|derivation| {
+            let derived = keys::keypair_from_entropy(derivation::derive_entropy(&derivation.master, *index));
+            derived.get_public_key().compress().as_bytes() == keypair.get_public_key().compress().as_bytes()
+        }));
+
+        let attempts = {
+            let mut state = self.state.lock().unwrap();
+            if !state.reported.insert(address.clone()) {
+                return;
+            }
+
+            state.peers.get(&peer).map_or(0, |peer| peer.attempts)
+        };
+
+        info!("Worker {} found {}", name, address);
+        let result = FoundResult {
+            key: FoundKey::KeyPair(keypair),
+            address,
+            payment_id: None,
+            index,
+            content: self.patterns.contents()[found.index].clone(),
+            placement: Some(found.placement),
+            range: found.range,
+            distance: found.distance,
+            thread: peer as usize,
+            attempts,
+            timestamp: get_current_time_in_millis(),
+        };
+
+        match self.sender.lock().unwrap().as_ref() {
+            Some(sender) => if sender.send(result).is_err() {
+                error!("Couldn't deliver the result of worker {}, the reporter is stopped", name);
+            },
+            None => warn!("Result of worker {} received after the end of the search", name),
+        }
+    }
+}
+
+// Rest of the range of a worker leaving, from the last offset it reported
+// The keys derived since that report are searched again, a random search has nothing to release
+fn release(mut job: Job, offset: Option<u64>) -> Option<Job> {
+    let range = job.derivation.as_mut()?;
+    if let Some(offset) = offset {
+        range.start = range.start.max(offset);
+    }
+
+    (range.start < range.end).then_some(job)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::protocol::SealedSecret;
+    use super::*;
+
+    fn job(derivation: Option<(u64, u64)>) -> Job {
+        Job {
+            id: 3,
+            network: Network::Mainnet,
+            contents: vec!["cafe".to_string()],
+            placements: vec![vec![Placement::Prefix]],
+            derivation: derivation.map(|(start, end)| JobRange {
+                master: SealedSecret {
+                    ephemeral: String::new(),
+                    nonce: String::new(),
+                    ciphertext: String::new(),
+                },
+                start,
+                end,
+            }),
+            public_key: String::new(),
+        }
+    }
+
+    fn released_range(job: Option<Job>) -> Option<(u64, u64)> {
+        job.and_then(|job| job.derivation).map(|range| (range.start, range.end))
+    }
+
+    #[test]
+    fn test_release_from_the_reported_offset() {
+        assert_eq!(released_range(release(job(Some((1000, 2000))), Some(1500))), Some((1500, 2000)));
+    }
+
+    #[test]
+    fn test_release_whole_range_without_report() {
+        assert_eq!(released_range(release(job(Some((1000, 2000))), None)), Some((1000, 2000)));
+        // An offset of a previous job is below the range
+        assert_eq!(released_range(release(job(Some((1000, 2000))), Some(10))), Some((1000, 2000)));
+    }
+
+    #[test]
+    fn test_release_nothing_once_searched() {
+        assert!(release(job(Some((1000, 2000))), Some(2000)).is_none());
+        assert!(release(job(None), Some(1500)).is_none());
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc
+};
+use log::{info, warn};
+use crate::snapshot::Snapshot;
+
+// Background run of a search, see `--daemon` and `--pid-file`
+
+// Detach the process from the terminal: fork, start a new session and close the standard streams
+// Must be called before starting the runtime, its threads don't survive a fork
+#[cfg(unix)]
+pub fn detach() -> Result<(), String> {
+    // The parent exits so the shell gets its prompt back
+    fork()?;
+    if unsafe { libc::setsid() } == -1 {
+        return Err(format!("couldn't start a new session: {}", std::io::Error::last_os_error()));
+    }
+
+    // No longer a session leader, so no terminal can be acquired again
+    fork()?;
+
+    let null = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")
+        .map_err(|e| format!("couldn't open /dev/null: {}", e))?;
+    let fd = std::os::unix::io::AsRawFd::as_raw_fd(&null);
+    for stream in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        if unsafe { libc::dup2(fd, stream) } == -1 {
+            return Err(format!("couldn't redirect the standard streams: {}", std::io::Error::last_os_error()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+fn fork() -> Result<(), String> {
+    match unsafe { libc::fork() } {
+        -1 => Err(format!("couldn't fork: {}", std::io::Error::last_os_error())),
+        0 => Ok(()),
+        _ => std::process::exit(0),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn detach() -> Result<(), String> {
+    Err("--daemon is only available on Unix".to_string())
+}
+
+// Resolve on SIGTERM, to stop like the `exit` command
+#[cfg(unix)]
+pub async fn terminated() {
+    use xelis_common::tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            terminate.recv().await;
+        },
+        Err(e) => {
+            warn!("Couldn't listen for SIGTERM: {}", e);
+            std::future::pending().await
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn terminated() {
+    std::future::pending().await
+}
+
+// Log the statistics on each SIGUSR1 until the task is aborted, without stopping the search
+// Without a prompt, it's the only way to see them: `kill -USR1 <pid>`
+#[cfg(unix)]
+pub async fn dump_stats_on_signal(snapshot: Arc<Snapshot>) {
+    use xelis_common::tokio::signal::unix::{signal, SignalKind};
+
+    let mut requests = match signal(SignalKind::user_defined1()) {
+        Ok(requests) => requests,
+        Err(e) => {
+            warn!("Couldn't listen for SIGUSR1: {}", e);
+            return;
+        }
+    };
+
+    while requests.recv().await.is_some() {
+        info!("Received SIGUSR1, statistics of the search:");
+        for line in snapshot.lines().await {
+            info!("  {}", line);
+        }
+    }
+}
+
+// There is no SIGUSR1, the `stats` command is still available
+#[cfg(not(unix))]
+pub async fn dump_stats_on_signal(_: Arc<Snapshot>) {}
+
+// File containing the ID of the running process, removed when dropped
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    // A file left by a crashed run is replaced, one of a running process is kept
+    pub fn create(path: &Path) -> Result<Self, String> {
+        if let Ok(content) = fs::read_to_string(path) {
+            match content.trim().parse::<u32>() {
+                Ok(pid) if is_running(pid) => return Err(format!("{} belongs to the running process {}", path.display(), pid)),
+                Ok(pid) => warn!("Replacing the stale pid file {} of the process {}", path.display(), pid),
+                Err(_) => warn!("Replacing the invalid pid file {}", path.display()),
+            }
+        }
+
+        let pid = std::process::id();
+        fs::write(path, format!("{}\n", pid))
+            .map_err(|e| format!("couldn't write the pid file {}: {}", path.display(), e))?;
+        info!("Process {} written to {}", pid, path.display());
+
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("Couldn't remove the pid file {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    // The signal 0 only checks the process, EPERM means it exists but belongs to another user
+    pid != std::process::id()
+        && (unsafe { libc::kill(pid as libc::pid_t, 0) } == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM))
+}
+
+// The process can't be checked, the file is considered stale
+#[cfg(not(unix))]
+fn is_running(_: u32) -> bool {
+    false
+}
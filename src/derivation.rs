@@ -0,0 +1,197 @@
+use std::{
+    env,
+    fmt,
+    fs,
+    io::ErrorKind,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering}
+};
+use rand::{rngs::OsRng, RngCore};
+use xelis_common::crypto::hash;
+
+// Size of the master seed in bytes
+pub const MASTER_SIZE: usize = 32;
+
+// Prefix of a `--derive-from` value naming the environment variable holding the master seed
+const MASTER_ENV_PREFIX: &str = "env:";
+
+// Deterministic derivation of the keys from a master seed
+// The key at `index` is derived from H(master || index), so the search space
+// is enumerable and can be resumed or split by indexes
+#[derive(Clone, Copy)]
+pub struct Derivation {
+    pub master: [u8; MASTER_SIZE],
+    // First index to derive
+    pub start: u64,
+    // Index to stop at (excluded), None to never stop
+    pub end: Option<u64>,
+}
+
+// Entropy of the key at `index`
+pub fn derive_entropy(master: &[u8; MASTER_SIZE], index: u64) -> [u8; MASTER_SIZE] {
+    let mut input = [0u8; MASTER_SIZE + 8];
+    input[..MASTER_SIZE].copy_from_slice(master);
+    input[MASTER_SIZE..].copy_from_slice(&index.to_be_bytes());
+
+    *hash(&input).as_bytes()
+}
+
+// New master seed from the secure random generator of the OS
+pub fn generate_master() -> [u8; MASTER_SIZE] {
+    let mut master = [0u8; MASTER_SIZE];
+    OsRng.fill_bytes(&mut master);
+    master
+}
+
+// Hex representation of a master seed, accepted by `parse_master`
+pub fn format_master(master: &[u8; MASTER_SIZE]) -> String {
+    master.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Master seed of `--derive-from`: read from `env:<variable>`, from a file, or given in hex
+// Used as a clap value parser, the hex value is visible to the other users in the process list
+pub fn parse_master_arg(value: &str) -> Result<[u8; MASTER_SIZE], String> {
+    if let Some(name) = value.strip_prefix(MASTER_ENV_PREFIX) {
+        let value = env::var(name).map_err(|e| format!("couldn't read the master seed from ${}: {}", name, e))?;
+        return parse_master(&value).map_err(|e| format!("invalid master seed in ${}: {}", name, e));
+    }
+
+    let path = Path::new(value);
+    if path.is_file() {
+        let content = fs::read_to_string(path).map_err(|e| format!("couldn't read the master seed from {}: {}", path.display(), e))?;
+        return parse_master(&content).map_err(|e| format!("invalid master seed in {}: {}", path.display(), e));
+    }
+
+    parse_master(value)
+}
+
+// Check if a `--derive-from` value is the master seed itself, not a file nor a variable
+pub fn is_inline_master(value: &str) -> bool {
+    !value.starts_with(MASTER_ENV_PREFIX) && !Path::new(value).is_file() && parse_master(value).is_ok()
+}
+
+// Parse a master seed from its hex representation
+pub fn parse_master(value: &str) -> Result<[u8; MASTER_SIZE], String> {
+    let value = value.trim();
+    if let Some(position) = value.chars().position(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex character at position {}", position));
+    }
+
+    if value.len() != MASTER_SIZE * 2 {
+        return Err(format!("expected {} hex characters, got {}", MASTER_SIZE * 2, value.len()));
+    }
+
+    let mut master = [0u8; MASTER_SIZE];
+    for (i, byte) in master.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16)
+            .map_err(|e| format!("invalid hex byte at position {}: {}", i * 2, e))?;
+    }
+
+    Ok(master)
+}
+
+// Part of the indexes searched by a machine of a cluster, see `--shard`
+// Shard `index` of `total` (starting at 1) searches the `index`-th of `total` equal ranges
+#[derive(Clone, Copy)]
+pub struct Shard {
+    pub index: u64,
+    pub total: u64,
+}
+
+impl Shard {
+    // Range of this shard in the indexes from `start` to `end` (excluded), the last one also gets the remainder
+    // Without an end, the indexes up to u64::MAX are split and the last shard never stops
+    pub fn range(&self, start: u64, end: Option<u64>) -> Result<(u64, Option<u64>), String> {
+        let size = (end.unwrap_or(u64::MAX).saturating_sub(start)) / self.total;
+        if size == 0 {
+            return Err(format!("the range of indexes is too small to be split in {} shards", self.total));
+        }
+
+        let first = start + (self.index - 1) * size;
+        let last = if self.index == self.total { end } else { Some(first + size) };
+        Ok((first, last))
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.total)
+    }
+}
+
+// Parse a shard written `<index>/<total>`, like 2/8
+// Used as a clap value parser
+pub fn parse_shard(value: &str) -> Result<Shard, String> {
+    let (index, total) = value.split_once('/')
+        .ok_or_else(|| "expected <index>/<total>, like 2/8".to_string())?;
+    let index = index.trim().parse::<u64>().map_err(|e| format!("invalid shard index: {}", e))?;
+    let total = total.trim().parse::<u64>().map_err(|e| format!("invalid number of shards: {}", e))?;
+    if total == 0 || index == 0 || index > total {
+        return Err(format!("shard index must be between 1 and the number of shards, got {}/{}", index, total));
+    }
+
+    Ok(Shard { index, total })
+}
+
+// Next index of each worker
+// Worker `i` of `n` derives the indexes `start + i`, `start + i + n`, ...
+pub struct Progress {
+    next: Vec<AtomicU64>,
+}
+
+impl Progress {
+    pub fn new(start: u64, threads: usize) -> Self {
+        Self {
+            next: (0..threads as u64).map(|i| AtomicU64::new(start + i)).collect(),
+        }
+    }
+
+    pub fn set(&self, thread: usize, next: u64) {
+        self.next[thread].store(next, Ordering::Relaxed);
+    }
+
+    // Offset to resume from: every index below it was already derived by a worker
+    // Resuming from it works with any number of threads, at the cost of a few duplicated keys
+    pub fn offset(&self) -> u64 {
+        self.next.iter()
+            .map(|next| next.load(Ordering::Relaxed))
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+// Read the offset saved by a previous run, None if the file doesn't exist
+pub fn load_offset(path: &Path) -> Result<Option<u64>, String> {
+    match fs::read_to_string(path) {
+        Ok(content) => content.trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("invalid offset in {}: {}", path.display(), e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("couldn't read offset file {}: {}", path.display(), e)),
+    }
+}
+
+// Save the offset, writing a temporary file first so a crash never leaves a partial value
+pub fn save_offset(path: &Path, offset: u64) -> Result<(), String> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    fs::write(&tmp, offset.to_string())
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|e| format!("couldn't save offset to {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shard_is_displayed_as_parsed() {
+        let shard = parse_shard("2/8").unwrap();
+        assert_eq!(shard.to_string(), "2/8");
+        assert_eq!(parse_shard(&shard.to_string()).unwrap().to_string(), "2/8");
+        assert!(parse_shard("0/8").is_err());
+        assert!(parse_shard("9/8").is_err());
+    }
+}
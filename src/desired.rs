@@ -0,0 +1,105 @@
+use xelis_common::crypto::bech32::CHARSET;
+
+// Replacements of the characters not available in addresses
+// None means the character is dropped
+const SUBSTITUTIONS: [(char, Option<char>); 4] = [
+    ('o', Some('0')),
+    ('b', Some('8')),
+    ('1', Some('l')),
+    ('i', None),
+];
+
+// A word as imagined by the user, converted to a content that can be found in an address
+pub struct DesiredWord {
+    pub word: String,
+    // Content to search for
+    pub content: String,
+    // Characters of the word that were replaced or dropped
+    pub substitutions: Vec<(char, Option<char>)>,
+}
+
+impl DesiredWord {
+    // Lowercase the word and replace the characters not available in addresses
+    pub fn new(word: &str) -> Self {
+        let mut content = String::new();
+        let mut substitutions = Vec::new();
+        for c in word.chars() {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+            if CHARSET.contains(lower) {
+                content.push(lower);
+                continue;
+            }
+
+            let replacement = SUBSTITUTIONS.iter()
+                .find(|(from, _)| *from == lower)
+                .and_then(|(_, to)| *to);
+
+            if let Some(to) = replacement {
+                content.push(to);
+            }
+            substitutions.push((c, replacement));
+        }
+
+        Self {
+            word: word.to_string(),
+            content,
+            substitutions,
+        }
+    }
+
+    // Human readable list of the substitutions
+    pub fn describe_substitutions(&self) -> String {
+        if self.substitutions.is_empty() {
+            return "none".to_string();
+        }
+
+        self.substitutions.iter()
+            .map(|(from, to)| match to {
+                Some(to) => format!("'{}' -> '{}'", from, to),
+                None => format!("'{}' dropped", from),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // How close an address containing the content is to the desired word
+    // Addresses are lowercase only, so the case differences are reported too
+    pub fn closeness(&self) -> String {
+        let total = self.word.chars().count();
+        let changed = self.substitutions.len();
+        let case_changed = self.word.chars()
+            .filter(|c| c.is_uppercase() && CHARSET.contains(c.to_lowercase().next().unwrap_or(*c)))
+            .count();
+
+        format!("{}/{} characters kept ({} replaced or dropped, {} lowercased)", total - changed, total, changed, case_changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{config::Network, matcher};
+    use super::*;
+
+    #[test]
+    fn test_substitutions() {
+        let desired = DesiredWord::new("BoB1i");
+        assert_eq!(desired.content, "808l");
+        assert_eq!(desired.substitutions, [('B', Some('8')), ('o', Some('0')), ('B', Some('8')), ('1', Some('l')), ('i', None)]);
+        assert_eq!(desired.describe_substitutions(), "'B' -> '8', 'o' -> '0', 'B' -> '8', '1' -> 'l', 'i' dropped");
+
+        let desired = DesiredWord::new("Cafe");
+        assert_eq!(desired.content, "cafe");
+        assert_eq!(desired.describe_substitutions(), "none");
+        assert_eq!(desired.closeness(), "4/4 characters kept (0 replaced or dropped, 1 lowercased)");
+    }
+
+    #[test]
+    fn test_content_is_always_valid() {
+        for word in ["XELIS", "Hello World", "xel:dog", "Café", "b1o", "ÉLAN-42"] {
+            let desired = DesiredWord::new(word);
+            assert!(matcher::check_content(&desired.content, Network::Mainnet).is_ok(), "{} -> {}", word, desired.content);
+        }
+
+        assert!(DesiredWord::new("ii").content.is_empty());
+    }
+}
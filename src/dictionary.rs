@@ -0,0 +1,99 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::BufRead,
+    path::Path
+};
+use log::{debug, info, warn};
+use crate::{config::Network, matcher};
+
+// Number of skipped words shown in the warning
+const SKIPPED_SAMPLES: usize = 5;
+
+// Load the words of a dictionary that can be found in an address
+// Words are lowercased, the ones rejected by the validation of the contents
+// (like with 'b', 'i', 'o' or '1') or shorter than `min_len` are skipped
+pub fn load_dictionary(path: &Path, min_len: usize, network: Network) -> Result<Vec<String>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("couldn't read dictionary {}: {}", path.display(), e))?;
+
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+    let mut skipped = Vec::new();
+    let mut too_short = 0;
+    for word in content.lines().map(|line| line.trim().to_lowercase()).filter(|word| !word.is_empty()) {
+        if word.chars().count() < min_len {
+            too_short += 1;
+            continue;
+        }
+
+        if let Err(e) = matcher::check_content(&word, network) {
+            debug!("Skipping dictionary word '{}': {}", word, e);
+            skipped.push(word);
+            continue;
+        }
+
+        if seen.insert(word.clone()) {
+            words.push(word);
+        }
+    }
+
+    if !skipped.is_empty() {
+        warn!("Skipped {} dictionary words with characters not available in addresses (e.g. {})", skipped.len(), skipped.iter().take(SKIPPED_SAMPLES).cloned().collect::<Vec<_>>().join(", "));
+    }
+
+    if words.is_empty() {
+        return Err(format!("no word of {} can be found in an address", path.display()));
+    }
+
+    info!("Loaded {} dictionary words ({} shorter than {} characters ignored)", words.len(), too_short, min_len);
+    Ok(words)
+}
+
+// Read one content per line until the end of the input, see `--content -`
+// Unlike the dictionary words, they are kept as is and validated like `--content`
+pub fn read_contents(reader: impl BufRead) -> Result<Vec<String>, String> {
+    let mut seen = HashSet::new();
+    let mut contents = Vec::new();
+    for line in reader.lines() {
+        let line = line.map_err(|e| format!("couldn't read the contents from stdin: {}", e))?;
+        let content = line.trim();
+        if !content.is_empty() && seen.insert(content.to_string()) {
+            contents.push(content.to_string());
+        }
+    }
+
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+    use super::*;
+
+    #[test]
+    fn test_load_dictionary_validates_every_word() {
+        let path = env::temp_dir().join(format!("xelis-vanity-dictionary-{}.txt", process::id()));
+        fs::write(&path, "Cafe\nbig\nxel:dog\ncafe\n  face  \nhi\n\nmoon\nsun1\nwax\n").unwrap();
+        let words = load_dictionary(&path, 3, Network::Mainnet);
+        fs::remove_file(&path).unwrap();
+
+        // Only the words `--content` would accept, lowercased and once
+        assert_eq!(words.unwrap(), ["cafe", "face", "wax"]);
+    }
+
+    #[test]
+    fn test_load_dictionary_without_valid_word() {
+        let path = env::temp_dir().join(format!("xelis-vanity-empty-dictionary-{}.txt", process::id()));
+        fs::write(&path, "big\nbob\n").unwrap();
+        let words = load_dictionary(&path, 3, Network::Mainnet);
+        fs::remove_file(&path).unwrap();
+        assert!(words.is_err());
+    }
+
+    #[test]
+    fn test_read_contents() {
+        let contents = read_contents("cafe\n\n  dog \ncafe\nsuffix:xel\n".as_bytes()).unwrap();
+        assert_eq!(contents, ["cafe", "dog", "suffix:xel"]);
+    }
+}
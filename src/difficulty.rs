@@ -0,0 +1,86 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf}
+};
+use serde::{Deserialize, Serialize};
+
+// Matches needed before the measured probability replaces the theoretical one
+// With fewer matches, the measure is too noisy to be useful
+pub const MIN_MEASURED_MATCHES: u64 = 10;
+
+// Difficulty measured by the previous runs of a search
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+pub struct Measured {
+    // Keys generated and matches found by all the runs
+    pub attempts: u64,
+    pub matches: u64,
+    // Average rate of the last run and its number of threads
+    pub rate: f64,
+    pub threads: usize,
+}
+
+impl Measured {
+    // Measured probability for a single key to match, if enough matches were found
+    pub fn probability(&self) -> Option<f64> {
+        (self.matches >= MIN_MEASURED_MATCHES).then(|| self.matches as f64 / self.attempts as f64)
+    }
+
+    // Rate of the last run, only if it used the same number of threads
+    pub fn rate(&self, threads: usize) -> Option<f64> {
+        (self.threads == threads && self.rate > 0f64).then_some(self.rate)
+    }
+}
+
+// Difficulty statistics saved between the runs, keyed by network, placements and contents
+// It is a small JSON file, see `--stats-file`
+pub struct DifficultyCache {
+    path: PathBuf,
+    entries: BTreeMap<String, Measured>,
+}
+
+impl DifficultyCache {
+    // Read the statistics saved by the previous runs, empty if the file doesn't exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let entries = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("invalid statistics file {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("couldn't read statistics file {}: {}", path.display(), e)),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Measured> {
+        self.entries.get(key)
+    }
+
+    // Add the results of a run to the ones of the previous runs
+    pub fn add(&mut self, key: String, attempts: u64, matches: u64, rate: f64, threads: usize) {
+        let measured = self.entries.entry(key).or_default();
+        measured.attempts += attempts;
+        measured.matches += matches;
+        if rate.is_finite() && rate > 0f64 {
+            measured.rate = rate;
+            measured.threads = threads;
+        }
+    }
+
+    // Save the statistics, writing a temporary file first so a crash never leaves a partial file
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(&self.entries)
+            .map_err(|e| format!("couldn't serialize statistics: {}", e))?;
+
+        let mut tmp = self.path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        fs::write(&tmp, content)
+            .and_then(|_| fs::rename(&tmp, &self.path))
+            .map_err(|e| format!("couldn't save statistics to {}: {}", self.path.display(), e))
+    }
+}
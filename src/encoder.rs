@@ -0,0 +1,150 @@
+use xelis_common::{
+    api::{DataElement, DataValue},
+    crypto::{
+        bech32::{CHARSET, SEPARATOR},
+        AddressType,
+        KeyPair
+    },
+    serializer::Serializer
+};
+use crate::{
+    config::Network,
+    matcher::CHECKSUM_LEN
+};
+
+// Generator of the bech32 checksum
+const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+// Feed 5-bit values to the bech32 checksum
+fn polymod(mut checksum: u32, values: impl IntoIterator<Item = u8>) -> u32 {
+    for value in values {
+        let top = checksum >> 25;
+        checksum = ((checksum & 0x1ffffff) << 5) ^ value as u32;
+        for (i, generator) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                checksum ^= generator;
+            }
+        }
+    }
+
+    checksum
+}
+
+// Serialized address type, encoded after the public key
+fn address_type_bytes(payment_id: Option<u64>) -> Vec<u8> {
+    match payment_id {
+        Some(id) => AddressType::Data(DataElement::Value(DataValue::U64(id))).to_bytes(),
+        None => AddressType::Normal.to_bytes(),
+    }
+}
+
+// Encode addresses in a reused buffer
+// It produces the same string as `keys::derive_address` without allocating for every key
+// The bech32 functions of xelis_common return a new string for each address, which costs more
+// than the encoding itself in the hot loop, compare them with `cargo bench --bench encoding`
+pub struct AddressEncoder {
+    // Network prefix followed by the separator
+    prefix: String,
+    // Checksum state after the expanded network prefix
+    prefix_checksum: u32,
+    // Payment ID of the address type bytes currently in `tail`
+    payment_id: Option<u64>,
+    tail: Vec<u8>,
+    bytes: Vec<u8>,
+    groups: Vec<u8>,
+    buffer: String,
+}
+
+impl AddressEncoder {
+    pub fn new(network: Network) -> Self {
+        let hrp = network.prefix();
+        let expanded = hrp.bytes().map(|c| c >> 5)
+            .chain([0])
+            .chain(hrp.bytes().map(|c| c & 31));
+
+        Self {
+            prefix: format!("{}{}", hrp, SEPARATOR),
+            prefix_checksum: polymod(1, expanded),
+            payment_id: None,
+            tail: address_type_bytes(None),
+            bytes: Vec::new(),
+            groups: Vec::new(),
+            buffer: String::new(),
+        }
+    }
+
+    // Encode the address of a keypair, the returned string is valid until the next call
+    pub fn encode(&mut self, keypair: &KeyPair, payment_id: Option<u64>) -> &str {
+        self.encode_key(keypair.get_public_key().compress().as_bytes(), payment_id)
+    }
+
+    // Same as `encode` from an already compressed public key
+    pub fn encode_key(&mut self, key: &[u8], payment_id: Option<u64>) -> &str {
+        if payment_id != self.payment_id {
+            self.tail = address_type_bytes(payment_id);
+            self.payment_id = payment_id;
+        }
+
+        self.bytes.clear();
+        self.bytes.extend_from_slice(key);
+        self.bytes.extend_from_slice(&self.tail);
+
+        // Convert the bytes to 5-bit groups, padding the last one
+        self.groups.clear();
+        let mut acc = 0u32;
+        let mut bits = 0;
+        for byte in self.bytes.iter() {
+            // Only the bits not consumed yet are kept
+            acc = ((acc << 8) | *byte as u32) & 0x1fff;
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                self.groups.push(((acc >> bits) & 31) as u8);
+            }
+        }
+        if bits > 0 {
+            self.groups.push(((acc << (5 - bits)) & 31) as u8);
+        }
+
+        let checksum = polymod(self.prefix_checksum, self.groups.iter().copied().chain([0; CHECKSUM_LEN])) ^ 1;
+
+        let charset = CHARSET.as_bytes();
+        self.buffer.clear();
+        self.buffer.push_str(&self.prefix);
+        self.buffer.extend(self.groups.iter().map(|group| charset[*group as usize] as char));
+        self.buffer.extend((0..CHECKSUM_LEN).map(|i| charset[((checksum >> (5 * (CHECKSUM_LEN - 1 - i))) & 31) as usize] as char));
+
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keys;
+    use super::*;
+
+    #[test]
+    fn test_same_address_as_xelis_common() {
+        for network in [Network::Mainnet, Network::Testnet] {
+            let mut encoder = AddressEncoder::new(network);
+            for _ in 0..64 {
+                let keypair = KeyPair::new();
+                // `Address::to_string`, as done before the encoder
+                let expected = keypair.get_public_key().to_address(network.is_mainnet()).to_string();
+                assert_eq!(encoder.encode(&keypair, None), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_integrated_address_as_xelis_common() {
+        // The address type bytes are cached, switch between the payment IDs
+        let mut encoder = AddressEncoder::new(Network::Mainnet);
+        for payment_id in [Some(0), Some(42), None, Some(u64::MAX), Some(42), None] {
+            let keypair = KeyPair::new();
+            let expected = keys::derive_address(&keypair, Network::Mainnet, payment_id);
+            assert_eq!(encoder.encode(&keypair, payment_id), expected);
+            assert_eq!(encoder.encode_key(keypair.get_public_key().compress().as_bytes(), payment_id), expected);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use xelis_common::crypto::bech32::CHARSET;
+use crate::config::Placement;
+
+// Probability for a single generated address to match the content
+// Each bech32 character is one of the 32 symbols of the charset
+pub fn match_probability(content_len: usize, placement: Placement, address_len: usize) -> f64 {
+    let single = (CHARSET.len() as f64).powi(-(content_len as i32));
+    match placement {
+        Placement::Prefix | Placement::Suffix => single,
+        Placement::Anywhere => {
+            // Every position is an independent chance to match
+            let positions = address_len.saturating_sub(content_len) + 1;
+            1f64 - (1f64 - single).powi(positions as i32)
+        }
+    }
+}
+
+// Number of contents within `distance` substitutions of a content, itself included
+// Used for `--fuzzy`: insertions and deletions are ignored, so it's slightly underestimated
+pub fn neighbours(content_len: usize, distance: usize) -> f64 {
+    let alternatives = CHARSET.len() as f64 - 1f64;
+    let mut combinations = 1f64;
+    let mut total = 1f64;
+    for i in 1..=distance.min(content_len) {
+        // Ways to choose the i substituted characters
+        combinations = combinations * (content_len - i + 1) as f64 / i as f64;
+        total += combinations * alternatives.powi(i as i32);
+    }
+
+    total
+}
+
+// Probability for a single address to match any of the contents
+pub fn any_probability(probabilities: impl IntoIterator<Item = f64>) -> f64 {
+    1f64 - probabilities.into_iter().map(|p| 1f64 - p).product::<f64>()
+}
+
+// Average number of keys to generate before finding a match
+pub fn expected_attempts(probability: f64) -> f64 {
+    1f64 / probability
+}
+
+// Number of keys to generate to have `chance` (0..1) of finding a match
+pub fn attempts_for_chance(probability: f64, chance: f64) -> f64 {
+    (1f64 - chance).ln() / (-probability).ln_1p()
+}
+
+// Probability to have found at least one match after `attempts` keys
+pub fn found_probability(probability: f64, attempts: f64) -> f64 {
+    1f64 - ((-probability).ln_1p() * attempts).exp()
+}
+
+// Remaining time above which the ETA isn't shown precisely
+const MAX_ETA: f64 = 100f64 * 365f64 * 24f64 * 3600f64;
+
+// Expected time to the next match at the current rate
+// Attempts are independent: the expected remaining attempts
+// don't decrease while searching, only the rate changes the ETA
+pub fn format_eta(probability: f64, rate: f64) -> String {
+    if rate <= 0f64 {
+        return "?".to_string();
+    }
+
+    let seconds = expected_attempts(probability) / rate;
+    if seconds > MAX_ETA {
+        ">100y".to_string()
+    } else {
+        format_duration(seconds)
+    }
+}
+
+// Format a duration in seconds using the biggest units
+pub fn format_duration(seconds: f64) -> String {
+    const UNITS: [(&str, f64); 5] = [
+        ("y", 365f64 * 24f64 * 3600f64),
+        ("d", 24f64 * 3600f64),
+        ("h", 3600f64),
+        ("m", 60f64),
+        ("s", 1f64),
+    ];
+
+    if !seconds.is_finite() {
+        return "forever".to_string();
+    }
+
+    let mut remaining = seconds.max(0f64).round();
+    let mut parts = Vec::new();
+    for (unit, size) in UNITS {
+        let value = (remaining / size).floor();
+        if value >= 1f64 {
+            parts.push(format!("{}{}", value, unit));
+            remaining -= value * size;
+        }
+
+        // Two units are precise enough
+        if parts.len() == 2 {
+            break;
+        }
+    }
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc
+    },
+    thread,
+    time::{Duration, Instant}
+};
+use log::{debug, error, warn};
+use serde_json::Value;
+use xelis_common::tokio;
+use crate::output::OutputRecord;
+
+// Command run for each match, see `--exec`
+// The match is written as JSON on its stdin, like a line of the output file, and its main fields
+// are in VANITY_* environment variables. A failing command is logged, the search continues.
+
+// Commands running at once, the next matches are not given to the command while they run
+const MAX_RUNNING: usize = 16;
+
+// Interval at which a running command is checked
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Fields of the record only given with `--exec-include-secrets`
+const SECRET_FIELDS: [&str; 3] = ["private_key", "tweak", "seeds"];
+
+pub struct Hook {
+    program: String,
+    args: Vec<String>,
+    secrets: bool,
+    timeout: Duration,
+    running: AtomicUsize,
+}
+
+impl Hook {
+    pub fn new(command: &str, secrets: bool, timeout: Duration) -> Result<Self, String> {
+        let mut words = split_command(command)?.into_iter();
+        let program = words.next().ok_or_else(|| "--exec command can't be empty".to_string())?;
+
+        Ok(Self {
+            program,
+            args: words.collect(),
+            secrets,
+            timeout,
+            running: AtomicUsize::new(0),
+        })
+    }
+
+    // Run the command for a match on a blocking thread, without waiting for it
+    pub fn spawn(self: &Arc<Self>, record: &OutputRecord) {
+        let mut value = match serde_json::to_value(record) {
+            Ok(value) => value,
+            Err(e) => {
+                error!("Couldn't serialize the match for --exec: {}", e);
+                return;
+            }
+        };
+        if let (false, Value::Object(fields)) = (self.secrets, &mut value) {
+            for field in SECRET_FIELDS {
+                fields.remove(field);
+            }
+        }
+
+        let mut env = vec![
+            ("VANITY_PATTERN", record.pattern.to_string()),
+            ("VANITY_PLACEMENT", record.placement.clone()),
+            ("VANITY_ATTEMPTS", record.attempts.to_string()),
+            ("VANITY_NETWORK", record.network.clone()),
+        ];
+        if let Some(address) = record.address {
+            env.push(("VANITY_ADDRESS", address.to_string()));
+        }
+        if let Some(index) = record.index {
+            env.push(("VANITY_INDEX", index.to_string()));
+        }
+        if let Some(private_key) = record.private_key.as_ref().filter(|_| self.secrets) {
+            env.push(("VANITY_PRIVATE_KEY", private_key.clone()));
+        }
+
+        if self.running.fetch_add(1, Ordering::Relaxed) >= MAX_RUNNING {
+            self.running.fetch_sub(1, Ordering::Relaxed);
+            warn!("{} --exec commands are already running, not running it for {}", MAX_RUNNING, record.address.unwrap_or(record.pattern));
+            return;
+        }
+
+        let hook = Arc::clone(self);
+        tokio::task::spawn_blocking(move || {
+            hook.execute(value.to_string(), env);
+            hook.running.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+
+    // Run the command until it exits or its timeout, its output is discarded
+    fn execute(&self, input: String, env: Vec<(&str, String)>) {
+        let child = Command::new(&self.program)
+            .args(&self.args)
+            .envs(env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(child) => child,
+            Err(e) => {
+                error!("Couldn't run the --exec command {}: {}", self.program, e);
+                return;
+            }
+        };
+
+        // The command may exit without reading it
+        if let Some(mut stdin) = child.stdin.take() {
+            if let Err(e) = stdin.write_all(input.as_bytes()).and_then(|_| stdin.write_all(b"\n")) {
+                debug!("Couldn't write the match to the --exec command: {}", e);
+            }
+        }
+
+        let started = Instant::now();
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) if status.success() => {
+                    debug!("--exec command exited in {:.1}s", started.elapsed().as_secs_f64());
+                    return;
+                },
+                Ok(Some(status)) => {
+                    warn!("--exec command failed: {}", status);
+                    return;
+                },
+                Ok(None) if started.elapsed() >= self.timeout => {
+                    warn!("--exec command killed after {}s", self.timeout.as_secs());
+                    if let Err(e) = child.kill().and_then(|_| child.wait().map(|_| ())) {
+                        error!("Couldn't kill the --exec command: {}", e);
+                    }
+                    return;
+                },
+                Ok(None) => thread::sleep(POLL_INTERVAL),
+                Err(e) => {
+                    error!("Couldn't wait for the --exec command: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// Split a command in words like a POSIX shell, without expanding anything
+// Single quotes keep everything, double quotes only let a backslash escape $ ` " \ and a newline,
+// and a backslash outside of quotes escapes the next character
+pub fn split_command(command: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    // A word can be empty, like ''
+    let mut in_word = false;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated single quote in --exec".to_string()),
+                    }
+                }
+            },
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('$' | '`' | '"' | '\\')) => word.push(c),
+                            Some('\n') => {},
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            },
+                            None => return Err("unterminated double quote in --exec".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("unterminated double quote in --exec".to_string()),
+                    }
+                }
+            },
+            '\\' => match chars.next() {
+                // A line continuation
+                Some('\n') => {},
+                Some(c) => {
+                    in_word = true;
+                    word.push(c);
+                },
+                None => return Err("trailing backslash in --exec".to_string()),
+            },
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            },
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(command: &str) -> Vec<String> {
+        split_command(command).unwrap()
+    }
+
+    #[test]
+    fn test_split_plain_words() {
+        assert_eq!(words("notify-send  found\t-u low "), ["notify-send", "found", "-u", "low"]);
+        assert!(words("   ").is_empty());
+    }
+
+    #[test]
+    fn test_split_quotes() {
+        assert_eq!(words("echo 'a b' \"c d\""), ["echo", "a b", "c d"]);
+        // Nothing is escaped nor expanded in single quotes
+        assert_eq!(words(r#"echo '$HOME \" \\'"#), ["echo", r#"$HOME \" \\"#]);
+        assert_eq!(words(r#"echo "\$HOME \" \\ \n""#), ["echo", r#"$HOME " \ \n"#]);
+        assert_eq!(words("echo a'b c'\"d\"e"), ["echo", "ab cde"]);
+    }
+
+    #[test]
+    fn test_split_escapes() {
+        assert_eq!(words(r"echo a\ b \'c\'"), ["echo", "a b", "'c'"]);
+        assert_eq!(words("echo a\\\nb"), ["echo", "ab"]);
+    }
+
+    #[test]
+    fn test_split_empty_words() {
+        assert_eq!(words("cmd '' \"\" x"), ["cmd", "", "", "x"]);
+    }
+
+    #[test]
+    fn test_split_errors() {
+        assert_eq!(split_command("echo 'a").unwrap_err(), "unterminated single quote in --exec");
+        assert_eq!(split_command("echo \"a").unwrap_err(), "unterminated double quote in --exec");
+        assert_eq!(split_command("echo \"a\\").unwrap_err(), "unterminated double quote in --exec");
+        assert_eq!(split_command("echo a\\").unwrap_err(), "trailing backslash in --exec");
+    }
+
+    #[test]
+    fn test_empty_command_is_refused() {
+        assert!(Hook::new("''", false, Duration::from_secs(1)).is_ok());
+        assert!(Hook::new("  ", false, Duration::from_secs(1)).is_err());
+    }
+}
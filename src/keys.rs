@@ -0,0 +1,343 @@
+use std::sync::OnceLock;
+use xelis_common::{
+    api::{DataElement, DataValue},
+    crypto::{
+        bech32::SEPARATOR,
+        Address,
+        AddressType,
+        KeyPair,
+        PrivateKey
+    },
+    serializer::Serializer
+};
+use rand::{
+    rngs::{OsRng, SmallRng, StdRng},
+    RngCore,
+    SeedableRng
+};
+use xelis_wallet::mnemonics;
+use crate::{
+    config::Network,
+    derivation::{self, Derivation},
+    matcher::KEY_DATA_LEN
+};
+
+// Size of a private key in bytes
+const PRIVATE_KEY_SIZE: usize = 32;
+
+// Random generator used for the keys
+enum KeyRng {
+    // Secure random generator of the OS
+    Secure(OsRng),
+    // Fast non-cryptographic generator
+    // Its keys are predictable and must never be used to receive funds
+    Fast(SmallRng),
+    // Generator seeded by `--rng-seed`, the same seed always gives the same keys
+    // Anyone knowing the seed can generate them again, only for tests
+    Seeded(StdRng),
+    // Keys derived from a master seed, `step` indexes apart
+    Derived {
+        master: [u8; PRIVATE_KEY_SIZE],
+        next: u64,
+        step: u64,
+        end: Option<u64>,
+    },
+}
+
+// Generate the keys of the workers by batches
+// The entropy of a whole batch is read from the random generator at once
+pub struct KeyGenerator {
+    rng: KeyRng,
+    // Reused buffer holding the entropy of a batch
+    entropy: Vec<u8>,
+}
+
+impl KeyGenerator {
+    pub fn new(fast: bool, batch_size: usize) -> Self {
+        let rng = if fast {
+            KeyRng::Fast(SmallRng::from_entropy())
+        } else {
+            KeyRng::Secure(OsRng)
+        };
+
+        Self {
+            rng,
+            entropy: vec![0u8; batch_size * PRIVATE_KEY_SIZE],
+        }
+    }
+
+    // Generate the keys of the worker `thread` from the seed and its index
+    // A worker restarted after a panic gets a new stream instead of replaying its keys
+    pub fn seeded(seed: u64, thread: usize, restart: usize, batch_size: usize) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes[..8].copy_from_slice(&seed.to_le_bytes());
+        bytes[8..16].copy_from_slice(&(thread as u64).to_le_bytes());
+        bytes[16..24].copy_from_slice(&(restart as u64).to_le_bytes());
+
+        Self {
+            rng: KeyRng::Seeded(StdRng::from_seed(bytes)),
+            entropy: vec![0u8; batch_size * PRIVATE_KEY_SIZE],
+        }
+    }
+
+    // Derive the keys of the worker `thread` out of `threads` from the master seed
+    pub fn derived(derivation: &Derivation, thread: usize, threads: usize, batch_size: usize) -> Self {
+        Self {
+            rng: KeyRng::Derived {
+                master: derivation.master,
+                next: derivation.start + thread as u64,
+                step: threads as u64,
+                end: derivation.end,
+            },
+            entropy: vec![0u8; batch_size * PRIVATE_KEY_SIZE],
+        }
+    }
+
+    // Continue a derivation from `next`, the index of a key of this worker
+    pub fn resume(&mut self, index: u64) {
+        if let KeyRng::Derived { next, .. } = &mut self.rng {
+            *next = index;
+        }
+    }
+
+    // Index of the next derived key, None if the keys are random
+    pub fn next_index(&self) -> Option<u64> {
+        match &self.rng {
+            KeyRng::Derived { next, .. } => Some(*next),
+            _ => None,
+        }
+    }
+
+    // Replace the content of `keys` by a new batch of keypairs
+    // Derived batches are shorter once the end index is reached
+    pub fn generate_batch(&mut self, keys: &mut Vec<KeyPair>) {
+        let mut len = self.entropy.len();
+        match &mut self.rng {
+            KeyRng::Secure(rng) => rng.fill_bytes(&mut self.entropy),
+            KeyRng::Fast(rng) => rng.fill_bytes(&mut self.entropy),
+            KeyRng::Seeded(rng) => rng.fill_bytes(&mut self.entropy),
+            KeyRng::Derived { master, next, step, end } => {
+                len = 0;
+                for chunk in self.entropy.chunks_exact_mut(PRIVATE_KEY_SIZE) {
+                    if end.is_some_and(|end| *next >= end) {
+                        break;
+                    }
+
+                    chunk.copy_from_slice(&derivation::derive_entropy(master, *next));
+                    *next += *step;
+                    len += PRIVATE_KEY_SIZE;
+                }
+            }
+        }
+
+        keys.clear();
+        keys.extend(self.entropy[..len].chunks_exact(PRIVATE_KEY_SIZE).map(|chunk| {
+            let mut bytes = [0u8; PRIVATE_KEY_SIZE];
+            bytes.copy_from_slice(chunk);
+            keypair_from_entropy(bytes)
+        }));
+    }
+}
+
+// Build a keypair from 32 random bytes
+pub fn keypair_from_entropy(mut bytes: [u8; PRIVATE_KEY_SIZE]) -> KeyPair {
+    // Clear the 4 highest bits so the value is always below the group order,
+    // which makes it a canonical scalar
+    bytes[PRIVATE_KEY_SIZE - 1] &= 0x0f;
+    let private_key = PrivateKey::from_bytes(&bytes)
+        .expect("private key from canonical bytes");
+
+    KeyPair::from_private_key(private_key)
+}
+
+// Payment ID embedded in integrated addresses
+#[derive(Clone, Copy)]
+pub enum PaymentId {
+    // Same payment ID for every address
+    Fixed(u64),
+    // New random payment ID for every address
+    Random,
+}
+
+impl PaymentId {
+    pub fn generate(&self) -> u64 {
+        match self {
+            PaymentId::Fixed(value) => *value,
+            PaymentId::Random => rand::random(),
+        }
+    }
+}
+
+// Derive the address of a keypair on the network as a string
+// With a payment ID, an integrated address is built
+pub fn derive_address(keypair: &KeyPair, network: Network, payment_id: Option<u64>) -> String {
+    match payment_id {
+        Some(id) => {
+            let data = DataElement::Value(DataValue::U64(id));
+            Address::new(network.is_mainnet(), AddressType::Data(data), keypair.get_public_key().compress())
+                .to_string()
+        },
+        None => keypair.get_public_key()
+            .to_address(network.is_mainnet())
+            .to_string()
+    }
+}
+
+// Human-readable part and length of the addresses of a network, learned from a generated address
+// so the limits of the contents follow the selected network instead of the mainnet ones
+pub struct AddressShape {
+    pub hrp: String,
+    pub len: usize,
+}
+
+impl AddressShape {
+    pub fn sample(network: Network, payment_id: Option<u64>) -> Self {
+        let address = derive_address(&KeyPair::new(), network, payment_id);
+        let hrp = address.split_once(SEPARATOR).map_or("", |(hrp, _)| hrp).to_string();
+
+        Self {
+            hrp,
+            len: address.len(),
+        }
+    }
+
+    // Shape of the normal addresses of the network, learned once for the whole process
+    pub fn of(network: Network) -> &'static Self {
+        static MAINNET: OnceLock<AddressShape> = OnceLock::new();
+        static TESTNET: OnceLock<AddressShape> = OnceLock::new();
+        let shape = match network {
+            Network::Mainnet => &MAINNET,
+            Network::Testnet => &TESTNET,
+        };
+
+        shape.get_or_init(|| Self::sample(network, None))
+    }
+
+    // Offset of the first data character, right after the separator
+    pub fn data_start(&self) -> usize {
+        self.hrp.len() + SEPARATOR.len_utf8()
+    }
+
+    // Offset of the first character after the public key
+    pub fn key_end(&self) -> usize {
+        self.data_start() + KEY_DATA_LEN
+    }
+
+    // Data characters a content can be found in, up to `suffix_end` with `--before-checksum`
+    pub fn data_len(&self, suffix_end: Option<usize>) -> usize {
+        suffix_end.unwrap_or(self.len) - self.data_start()
+    }
+}
+
+// Parse a private key from its hex representation
+pub fn parse_private_key(hex: &str) -> Result<PrivateKey, String> {
+    let hex = hex.trim();
+    if let Some(position) = hex.chars().position(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex character at position {}", position));
+    }
+
+    if hex.len() != PRIVATE_KEY_SIZE * 2 {
+        return Err(format!("expected {} hex characters, got {}", PRIVATE_KEY_SIZE * 2, hex.len()));
+    }
+
+    PrivateKey::from_hex(hex)
+        .map_err(|e| format!("invalid private key: {}", e))
+}
+
+// Parse a private key from its mnemonic seed
+// Every word is checked against the wordlists to report precisely the unknown ones
+// Without a language index, the one knowing the most of the words is used
+pub fn parse_seed(seed: &str, language: Option<usize>) -> Result<PrivateKey, String> {
+    let words: Vec<&str> = seed.split_whitespace().collect();
+    if words.is_empty() {
+        return Err("seed is empty".to_string());
+    }
+
+    let language = match language {
+        Some(index) => mnemonics::LANGUAGES.get(index)
+            .ok_or_else(|| format!("unknown language index {}", index))?,
+        None => mnemonics::LANGUAGES.iter()
+            .max_by_key(|language| words.iter().filter(|word| language.get_words().contains(word)).count())
+            .ok_or_else(|| "no language available".to_string())?,
+    };
+
+    if let Some((index, word)) = words.iter().enumerate().find(|(_, word)| !language.get_words().contains(word)) {
+        return Err(format!("unknown word '{}' at position {} (language: {})", word, index + 1, language.get_name()));
+    }
+
+    mnemonics::words_to_key(&words)
+        .map_err(|e| format!("invalid seed: {}", e))
+}
+
+// Re-derive the address from the seed words and compare it with the expected one
+// This ensures the seed written down by the user restores the found address
+pub fn verify_seed(words: &[&str], expected: &str, network: Network, payment_id: Option<u64>) -> Result<(), String> {
+    let private_key = mnemonics::words_to_key(words)
+        .map_err(|e| format!("couldn't convert the seed back to a key: {}", e))?;
+
+    let address = derive_address(&KeyPair::from_private_key(private_key), network, payment_id);
+    if address != expected {
+        return Err(format!("seed restores {} instead of {}", address, expected));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::matcher::{self, CHECKSUM_LEN};
+    use super::*;
+
+    #[test]
+    fn test_address_shape_of_each_network() {
+        for network in [Network::Mainnet, Network::Testnet] {
+            let shape = AddressShape::sample(network, None);
+            let address = derive_address(&KeyPair::new(), network, None);
+            assert_eq!(shape.hrp, network.prefix());
+            assert_eq!(shape.len, address.len());
+            assert_eq!(&address[..shape.data_start()], format!("{}{}", network.prefix(), SEPARATOR));
+            assert_eq!(shape.data_len(None), address.len() - network.prefix().len() - 1);
+            // The address type and the checksum follow the key
+            assert_eq!(shape.key_end() + 2 + CHECKSUM_LEN, shape.len);
+            assert_eq!(shape.data_len(Some(shape.key_end())), KEY_DATA_LEN);
+        }
+    }
+
+    #[test]
+    fn test_matcher_uses_the_learned_shape() {
+        for network in [Network::Mainnet, Network::Testnet] {
+            let shape = AddressShape::of(network);
+            assert_eq!(matcher::data_start(network), shape.data_start());
+            assert_eq!(matcher::key_end(network), shape.key_end());
+        }
+        assert_ne!(AddressShape::of(Network::Mainnet).hrp, AddressShape::of(Network::Testnet).hrp);
+    }
+
+    #[test]
+    fn test_networks_only_change_the_prefix_and_checksum() {
+        for _ in 0..16 {
+            let keypair = KeyPair::new();
+            let mainnet = derive_address(&keypair, Network::Mainnet, None);
+            let testnet = derive_address(&keypair, Network::Testnet, None);
+            let (mainnet_hrp, mainnet_data) = mainnet.split_once(SEPARATOR).unwrap();
+            let (testnet_hrp, testnet_data) = testnet.split_once(SEPARATOR).unwrap();
+            assert_eq!(mainnet_hrp, Network::Mainnet.prefix());
+            assert_eq!(testnet_hrp, Network::Testnet.prefix());
+
+            // Same key and address type, the checksum covers the prefix
+            let checksum = mainnet_data.len() - CHECKSUM_LEN;
+            assert_eq!(mainnet_data.len(), testnet_data.len());
+            assert_eq!(mainnet_data[..checksum], testnet_data[..checksum]);
+            assert_ne!(mainnet_data[checksum..], testnet_data[checksum..]);
+        }
+    }
+
+    #[test]
+    fn test_integrated_address_shape_is_longer() {
+        for network in [Network::Mainnet, Network::Testnet] {
+            let integrated = AddressShape::sample(network, Some(42));
+            assert_eq!(integrated.hrp, network.prefix());
+            assert!(integrated.len > AddressShape::of(network).len);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use xelis_wallet::mnemonics;
+
+// Common aliases for the seed languages
+// The target is compared against the normalized language name
+const ALIASES: [(&str, &str); 15] = [
+    ("en", "english"),
+    ("fr", "french"),
+    ("it", "italian"),
+    ("es", "spanish"),
+    ("pt", "portuguese"),
+    ("ja", "japanese"),
+    ("jp", "japanese"),
+    ("zh", "chinesesimplified"),
+    ("zhsimplified", "chinesesimplified"),
+    ("chinese", "chinesesimplified"),
+    ("nl", "dutch"),
+    ("ru", "russian"),
+    ("eo", "esperanto"),
+    ("de", "german"),
+    ("jbo", "lojban"),
+];
+
+// Native names of the languages, keyed by their normalized name
+const NATIVE_NAMES: [(&str, &str); 12] = [
+    ("english", "English"),
+    ("french", "Français"),
+    ("italian", "Italiano"),
+    ("spanish", "Español"),
+    ("portuguese", "Português"),
+    ("japanese", "日本語"),
+    ("chinesesimplified", "简体中文"),
+    ("dutch", "Nederlands"),
+    ("russian", "Русский"),
+    ("esperanto", "Esperanto"),
+    ("german", "Deutsch"),
+    ("lojban", "Lojban"),
+];
+
+// Number of sample words shown per language
+const SAMPLE_WORDS: usize = 3;
+
+// A seed language available in the wallet mnemonics
+pub struct LanguageInfo {
+    pub index: usize,
+    pub name: &'static str,
+    pub native_name: &'static str,
+    pub samples: Vec<&'static str>,
+}
+
+// Keep only the lowercase alphanumeric characters
+// so "Chinese (Simplified)" and "chinese-simplified" are equal
+fn normalize(value: &str) -> String {
+    value.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// All the available languages, ordered by index
+pub fn languages() -> Vec<LanguageInfo> {
+    mnemonics::LANGUAGES.iter()
+        .enumerate()
+        .map(|(index, language)| {
+            let name = language.get_name();
+            let normalized = normalize(name);
+            let native_name = NATIVE_NAMES.iter()
+                .find(|(key, _)| *key == normalized)
+                .map(|(_, native)| *native)
+                .unwrap_or(name);
+
+            LanguageInfo {
+                index,
+                name,
+                native_name,
+                samples: language.get_words().iter().take(SAMPLE_WORDS).copied().collect(),
+            }
+        })
+        .collect()
+}
+
+// Resolve a language from its index, name or alias
+// Used as a clap value parser so invalid values are rejected at startup
+pub fn parse_language(value: &str) -> Result<usize, String> {
+    let languages = languages();
+    if let Ok(index) = value.parse::<usize>() {
+        if index < languages.len() {
+            return Ok(index);
+        }
+    } else {
+        let normalized = normalize(value);
+        let target = ALIASES.iter()
+            .find(|(alias, _)| *alias == normalized)
+            .map(|(_, target)| *target)
+            .unwrap_or(normalized.as_str());
+
+        if let Some(language) = languages.iter().find(|language| normalize(language.name) == target) {
+            return Ok(language.index);
+        }
+    }
+
+    let valid = languages.iter()
+        .map(|language| format!("{} ({})", language.index, language.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Err(format!("unknown language '{}', valid values are: {}", value, valid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_languages_start_at_index_0() {
+        let languages = languages();
+        assert!(!languages.is_empty());
+        assert_eq!(languages[0].index, 0);
+        assert!(languages.iter().enumerate().all(|(index, language)| language.index == index));
+        assert!(languages.iter().all(|language| language.samples.len() == SAMPLE_WORDS));
+    }
+
+    #[test]
+    fn test_parse_language_uses_the_same_list() {
+        for language in languages() {
+            assert_eq!(parse_language(&language.index.to_string()), Ok(language.index));
+            assert_eq!(parse_language(language.name), Ok(language.index));
+        }
+        assert_eq!(parse_language("EN"), parse_language("English"));
+        assert!(parse_language(&languages().len().to_string()).is_err());
+        assert!(parse_language("klingon").unwrap_err().contains("0 (English)"));
+    }
+}
@@ -0,0 +1,29 @@
+// Engine of the XELIS vanity address generator
+// Library consumers can run the threaded key generation with their own acceptance
+// criteria, see `searcher::AddressMatcher::Custom` and `SearchContext::with_matcher`.
+// The command line interface, the API, the coordinator and the workers live in the binary.
+
+pub mod best;
+pub mod config;
+pub mod derivation;
+pub mod encoder;
+pub mod keys;
+pub mod matcher;
+pub mod pretty;
+pub mod quota;
+pub mod searcher;
+pub mod split;
+pub mod stats;
+
+// Only shared with the binary, not part of the library interface
+#[doc(hidden)]
+pub mod affinity;
+#[doc(hidden)]
+pub mod estimate;
+#[doc(hidden)]
+pub mod language;
+#[doc(hidden)]
+pub mod lifetime;
+
+mod config_file;
+mod priority;
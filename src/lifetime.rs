@@ -0,0 +1,110 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::Ordering,
+    time::Instant
+};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use crate::{
+    estimate,
+    stats::{self, TOTAL_ATTEMPTS, TOTAL_MATCHES}
+};
+
+// Name of the state file in the log directory, without `--state-file`
+pub const DEFAULT_STATE_FILE: &str = "xelis-vanity-state.json";
+
+// Work done by this machine over all its searches, see `--state-file`
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
+pub struct Totals {
+    pub runs: u64,
+    pub attempts: u64,
+    // Seconds spent searching
+    pub runtime: f64,
+    pub matches: u64,
+    // Highest rate of a tick ever measured
+    pub best_rate: f64,
+}
+
+impl Totals {
+    // Keys, duration, matches and best rate, as shown by the `stats` command and at the end of a search
+    pub fn summary(&self) -> String {
+        format!(
+            "{} keys in {}, {} matches, best rate {}",
+            stats::format_count(self.attempts),
+            estimate::format_duration(self.runtime),
+            self.matches,
+            stats::format_rate(self.best_rate)
+        )
+    }
+}
+
+// Totals of the previous searches, and the start of this one
+pub struct Lifetime {
+    path: PathBuf,
+    previous: Totals,
+    started: Instant,
+}
+
+impl Lifetime {
+    // A missing file starts from zero, so does an unreadable or invalid one with a warning
+    // The file is only replaced at the end of the search
+    pub fn load(path: &Path) -> Self {
+        let previous = match fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Invalid state file {}, the lifetime statistics start from zero: {}", path.display(), e);
+                Totals::default()
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Totals::default(),
+            Err(e) => {
+                warn!("Couldn't read the state file {}, the lifetime statistics start from zero: {}", path.display(), e);
+                Totals::default()
+            }
+        };
+
+        Self {
+            path: path.to_path_buf(),
+            previous,
+            started: Instant::now(),
+        }
+    }
+
+    // Totals of this search alone, from the process-wide counters
+    pub fn session(&self, peak_rate: f64) -> Totals {
+        Totals {
+            runs: 1,
+            attempts: TOTAL_ATTEMPTS.load(Ordering::Relaxed),
+            runtime: self.started.elapsed().as_secs_f64(),
+            matches: TOTAL_MATCHES.load(Ordering::Relaxed),
+            best_rate: peak_rate,
+        }
+    }
+
+    // Totals of the previous searches and this one
+    pub fn total(&self, session: &Totals) -> Totals {
+        Totals {
+            runs: self.previous.runs + session.runs,
+            attempts: self.previous.attempts + session.attempts,
+            runtime: self.previous.runtime + session.runtime,
+            matches: self.previous.matches + session.matches,
+            best_rate: self.previous.best_rate.max(session.best_rate),
+        }
+    }
+
+    // Save the totals, writing a temporary file first so a crash never leaves a partial file
+    pub fn save(&self, totals: &Totals) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(totals)
+            .map_err(|e| format!("couldn't serialize the lifetime statistics: {}", e))?;
+
+        let mut tmp = self.path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        // The log directory is missing with `--disable-file-logging`
+        let parent = self.path.parent().filter(|parent| !parent.as_os_str().is_empty());
+        parent.map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&tmp, content))
+            .and_then(|_| fs::rename(&tmp, &self.path))
+            .map_err(|e| format!("couldn't save the lifetime statistics to {}: {}", self.path.display(), e))
+    }
+}
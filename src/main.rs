@@ -1,121 +1,370 @@
+mod api;
+mod commands;
+mod control;
+mod coordinator;
+mod daemon;
+mod desired;
+mod dictionary;
+mod difficulty;
+mod hook;
+mod output;
+mod progress;
+mod protocol;
+mod qr;
+mod report;
+mod reporter;
+mod snapshot;
+mod systemd;
+mod watchdog;
+mod worker;
+
 use std::{
-    str::FromStr,
-    sync::atomic::{AtomicUsize, Ordering},
+    env,
+    future,
+    io::{self, IsTerminal},
+    hint::black_box,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+        Mutex
+    },
     thread,
     time::{Duration, Instant}
 };
-use clap::Parser;
-use lazy_static::lazy_static;
 use log::{error, info, warn};
 use xelis_common::{
     async_handler,
-    config::{PREFIX_ADDRESS, VERSION},
     crypto::{
-        bech32::{
-            SEPARATOR,
-            CHARSET,
-        },
+        bech32::CHARSET,
         KeyPair,
+        PrivateKey,
     },
     prompt::{
+        command::CommandManager,
         Color,
         LogLevel,
         Prompt,
         PromptError,
         ShareablePrompt
     },
-    serializer::Serializer,
-    tokio::{self, sync::Mutex},
-    utils::format_hashrate,
+    time::get_current_time_in_millis,
+    tokio::{
+        self,
+        net::TcpListener,
+        sync::{mpsc, Notify}
+    },
 };
 use xelis_wallet::mnemonics;
+use xelis_vanity::{
+    affinity,
+    best,
+    config,
+    derivation,
+    encoder,
+    estimate,
+    keys,
+    language,
+    lifetime,
+    matcher,
+    pretty,
+    quota,
+    searcher,
+    split,
+    stats
+};
+use config::{
+    BenchArgs,
+    CombineArgs,
+    Command,
+    CommonArgs,
+    Config,
+    CoordinatorArgs,
+    DeriveArgs,
+    EtaArgs,
+    EstimateArgs,
+    Network,
+    OutputField,
+    Placement,
+    SearchArgs,
+    SearchMode,
+    ThreadCount,
+    VerifyArgs,
+    WorkerArgs,
+    DEFAULT_OUTPUT_FIELDS,
+    STDIN_CONTENT
+};
 
-#[derive(clap::ValueEnum, Clone, Copy, Debug)]
-pub enum Placement {
-    Prefix,
-    Suffix,
-    Anywhere,
-}
+use api::ApiState;
+use best::BestMatches;
+use control::{ControlSocket, Controller};
+use coordinator::Coordinator;
+use daemon::PidFile;
+use derivation::{Derivation, Progress, MASTER_SIZE};
+use desired::DesiredWord;
+use difficulty::DifficultyCache;
+use encoder::AddressEncoder;
+use hook::Hook;
+use keys::{AddressShape, KeyGenerator, PaymentId};
+use lifetime::Lifetime;
+use matcher::{Allowlist, KeyFilter, PatternSet, CHECKSUM_LEN, KEY_DATA_LEN};
+use output::{OutputFile, OutputLock};
+use pretty::{CharSubset, RunRecord};
+use progress::ProgressSink;
+use quota::Quotas;
+use report::RunReport;
+use reporter::Reporter;
+use searcher::{AddressMatcher, Sampler, SearchContext, SearchControl, Searcher};
+use snapshot::Snapshot;
+use stats::{MatchStats, RATE_HISTORY, RATE_TREND, TOTAL_ATTEMPTS};
+use worker::WorkerOptions;
+
+// Above this number, the contents are not listed in the logs
+const MAX_LOGGED_CONTENTS: usize = 10;
+
+// Interval between two status lines when the prompt is not interactive
+const STATUS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+// No machine is expected to be slower, used to skip the benchmark of the quick searches
+const MIN_EXPECTED_RATE: f64 = 1000f64;
+
+// Seconds in a year, for `--astronomical-threshold`
+const YEAR: f64 = 365f64 * 24f64 * 3600f64;
+
+// Duration of the benchmark measuring the rate for `--eta-threshold`
+const ETA_CHECK_DURATION: Duration = Duration::from_secs(1);
+
+// Frames of the spinner in the prompt, one per refresh
+const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+// Bars of the hashrate sparkline in the prompt, each one averaging a part of the last hour
+const PROMPT_SPARKLINE_WIDTH: usize = 20;
+
+// Matches logged per second by the coordinator, the others are only saved
+const COORDINATOR_MAX_LOGGED_RATE: u32 = 10;
+
+// Interval between two saves of the derivation offset
+const OFFSET_SAVE_INTERVAL: Duration = Duration::from_secs(30);
 
-impl FromStr for Placement {
-    type Err = &'static str;
+// Time given to the workers to reach their rate before the warmup measures it
+const WARMUP_DELAY: Duration = Duration::from_secs(5);
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "prefix" => Ok(Placement::Prefix),
-            "suffix" => Ok(Placement::Suffix),
-            "anywhere" => Ok(Placement::Anywhere),
-            _ => Err("Unknown placement")
+// Duration of each rate measured during the warmup, with all the threads then one per physical core
+const WARMUP_WINDOW: Duration = Duration::from_secs(5);
+
+fn main() {
+    let config = Config::parse_args();
+    if let Command::Search(args) = &config.command {
+        // Validating only never detaches, it's how the daemon checks its arguments
+        if args.daemon && !args.validate_only {
+            if let Err(e) = daemonize(args) {
+                eprintln!("Error: {}", e);
+                process::exit(Outcome::Error.code());
+            }
         }
     }
+
+    let outcome = run(config);
+    process::exit(outcome.code());
+}
+
+// Detach the search from the terminal, before the runtime and the logger are started
+// The same arguments are checked first with `--validate-only` by a child process,
+// so a mistake is shown in the terminal instead of only in the log file of the daemon
+fn daemonize(args: &SearchArgs) -> Result<(), String> {
+    let executable = env::current_exe()
+        .map_err(|e| format!("couldn't find the executable to validate the arguments: {}", e))?;
+    let status = process::Command::new(executable)
+        .args(env::args_os().skip(1))
+        .arg("--validate-only")
+        .status()
+        .map_err(|e| format!("couldn't validate the arguments: {}", e))?;
+    if !status.success() {
+        return Err("invalid configuration, the search was not started".to_string());
+    }
+
+    eprintln!("Running in the background, logging to {}{}", args.common.log_dir(), args.common.log_file());
+    daemon::detach()
+}
+
+// Result of a subcommand, mapped to the exit code of the process
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Success,
+    // Invalid configuration or unexpected failure
+    Error,
+    // The search stopped before finding the requested addresses
+    NotFound,
+    // With `--dry-run`, the search would take longer than `--eta-threshold`
+    TooLong,
 }
 
-impl ToString for Placement {
-    fn to_string(&self) -> String {
+impl Outcome {
+    fn code(&self) -> i32 {
         match self {
-            Placement::Prefix => "prefix".to_string(),
-            Placement::Suffix => "suffix".to_string(),
-            Placement::Anywhere => "anywhere".to_string(),
-        }
-    }
-}
-
-#[derive(Parser)]
-#[clap(version = VERSION, about = "XELIS is an innovative cryptocurrency built from scratch with BlockDAG, Homomorphic Encryption, Zero-Knowledge Proofs, and Smart Contracts.")]
-#[command(styles = xelis_common::get_cli_styles())]
-pub struct Config {
-    /// The content for the address to search for
-    #[clap(short, long)]
-    pub content: String,
-    /// Language index for the seed
-    #[clap(short, long, default_value_t = 0)]
-    pub language: usize,
-    /// Numbers of threads to use (at least 1, max: 65535)
-    /// By default, this will try to detect the number of threads available on your CPU.
-    #[clap(short, long)]
-    pub num_threads: Option<usize>,
-    /// Placement of the prefix in the address
-    #[clap(short, long, default_value_t = Placement::Prefix)]
-    pub placement: Placement,
-    /// Disable the usage of colors in log
-    #[clap(long)]
-    disable_log_color: bool,
-    /// Disable terminal interactive mode
-    /// You will not be able to write CLI commands in it or to have an updated prompt
-    #[clap(long)]
-    disable_interactive_mode: bool,
-}
-
-static RATE_COUNTER: AtomicUsize = AtomicUsize::new(0);
-lazy_static! {
-    static ref RATE_LAST_TIME: Mutex<Instant> = Mutex::new(Instant::now());
+            Outcome::Success => 0,
+            Outcome::Error => 1,
+            Outcome::NotFound => 2,
+            Outcome::TooLong => 3,
+        }
+    }
+}
+
+// Problems found by the validation of a search
+// A search stops at the first one, `--validate-only` goes on with the next checks and lists them all
+struct Problems {
+    collect: bool,
+    found: Vec<String>,
+}
+
+impl Problems {
+    fn new(collect: bool) -> Self {
+        Self {
+            collect,
+            found: Vec::new(),
+        }
+    }
+
+    // Record a problem, returns true if the validation must stop now
+    fn add(&mut self, problem: impl ToString) -> bool {
+        if self.collect {
+            self.found.push(problem.to_string());
+            return false;
+        }
+
+        error!("{}", problem.to_string());
+        true
+    }
+
+    // A problem the next checks depend on, the validation ends with it
+    fn fail(&mut self, problem: impl ToString) -> Outcome {
+        self.add(problem);
+        self.finish().unwrap_or(Outcome::Error)
+    }
+
+    // List the problems found, None if there is none
+    fn finish(&mut self) -> Option<Outcome> {
+        if self.found.is_empty() {
+            return None;
+        }
+
+        error!("The configuration has {} problem(s):", self.found.len());
+        for problem in self.found.drain(..) {
+            error!("- {}", problem);
+        }
+        Some(Outcome::Error)
+    }
 }
 
 #[tokio::main]
-async fn main() {
-    let config = Config::parse();
-    let prompt = match Prompt::new(LogLevel::Info, "logs/", "logs.log", true, false, config.disable_log_color, !config.disable_interactive_mode, Vec::new(), LogLevel::Info) {
+async fn run(config: Config) -> Outcome {
+    let command = config.command;
+    let common = command.common();
+    let prompt = match Prompt::new(common.log_level, &common.log_dir(), common.log_file(), !common.file_logging(), false, common.disable_log_color, command.is_interactive(), Vec::new(), common.file_log_level.unwrap_or(common.log_level)) {
         Ok(value) => value,
         Err(e) => {
             error!("Couldn't initialize prompt: {}", e);
-            return;
+            return Outcome::Error;
         }
     };
+    stats::set_rate_unit(common.rate_unit);
+    warn_inline_master();
 
-    // Check if the content is empty
-    if config.content.is_empty() {
-        error!("Prefix can't be empty");
-        return;
+    if matches!(command, Command::Search(_) | Command::Coordinator(_)) && !common.disable_interactive_mode && !io::stdout().is_terminal() {
+        info!("Output is not a terminal, interactive mode is disabled: the status is logged every {}s", STATUS_LOG_INTERVAL.as_secs());
     }
 
-    // Check if the content contains invalid characters
-    for c in config.content.chars() {
-        if !CHARSET.chars().any(|v| v == c) {
-            error!("Invalid character in prefix: {}", c);
-            return;
+    if command.common().fast_rng {
+        warn!("**************************************************************");
+        warn!("* --fast-rng is enabled: keys are generated by an INSECURE    *");
+        warn!("* random generator. NEVER use them to receive any funds.      *");
+        warn!("**************************************************************");
+    }
+
+    match command {
+        Command::Search(args) => search(prompt, args).await,
+        Command::Bench(args) => bench(args),
+        Command::Estimate(args) => estimate(args),
+        Command::Verify(args) => if verify(args) {
+            Outcome::Success
+        } else {
+            Outcome::Error
+        },
+        Command::Derive(args) => if derive(args) {
+            Outcome::Success
+        } else {
+            Outcome::Error
+        },
+        Command::Combine(args) => if combine(args) {
+            Outcome::Success
+        } else {
+            Outcome::Error
+        },
+        Command::Languages(_) => {
+            list_languages();
+            Outcome::Success
+        },
+        Command::Worker(args) => if worker(args).await {
+            Outcome::Success
+        } else {
+            Outcome::Error
+        },
+        Command::Coordinator(args) => coordinator(prompt, args).await,
+    }
+}
+
+// Verify that the content can be found in an address
+fn validate_content(content: &str, network: Network) -> bool {
+    match matcher::check_content(content, network) {
+        Ok(()) => true,
+        Err(e) => {
+            error!("{}", e);
+            false
+        }
+    }
+}
+
+// A content after the address prefix (`xel:dog`) is always a prefix, even with another `--placement`
+fn warn_address_prefix(value: &str, global: &[Placement], network: Network) {
+    if matcher::has_address_prefix(value, network) && !global.contains(&Placement::Prefix) {
+        warn!("'{}' starts after the address prefix, it's searched as a prefix instead of '{}'", value, format_placements(global));
+    }
+}
+
+// Placements of each content, `prefix:dog` overrides `--placement` for this content
+// The contents are replaced by their value without placement, and validated
+// Returns the problems of all the contents, not only the first one
+fn parse_placements(contents: &mut [String], global: &[Placement], network: Network) -> Result<Vec<Vec<Placement>>, Vec<String>> {
+    let mut placements = Vec::with_capacity(contents.len());
+    let mut problems = Vec::new();
+    for value in contents.iter_mut() {
+        warn_address_prefix(value, global, network);
+        match matcher::parse_content_placement(value, network) {
+            Ok((placement, content)) => {
+                placements.push(placement.map_or_else(|| global.to_vec(), |placement| vec![placement]));
+                *value = content.to_string();
+            },
+            Err(e) => {
+                problems.push(e);
+                continue;
+            }
         }
+
+        if let Err(e) = matcher::check_content(value, network) {
+            problems.push(e);
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(problems);
     }
 
+    Ok(placements)
+}
+
+// Resolve the number of threads to use from the config
+fn resolve_threads(common: &CommonArgs) -> Result<usize, String> {
     let detected_threads = match thread::available_parallelism() {
         Ok(value) => value.get(),
         Err(e) => {
@@ -124,79 +373,2028 @@ async fn main() {
         }
     };
 
-    let threads = match config.num_threads {
-        Some(value) => value,
+    let threads = match common.num_threads {
+        Some(value) => value.resolve(detected_threads),
         None => detected_threads
     };
 
     if threads < 1 {
-        error!("Number of threads must be at least 1");
-        return;
+        return Err(match common.num_threads {
+            Some(ThreadCount::Percent(percent)) => format!("{}% of the {} detected threads is less than 1 thread", percent, detected_threads),
+            _ => "Number of threads must be at least 1".to_string(),
+        });
     }
 
     info!("Total threads to use: {} (detected: {})", threads, detected_threads);
-    info!("Searching for address with content: {} at placement '{}'", config.content, config.placement.to_string());
-
-    let prefix = match config.placement {
-        Placement::Prefix => format!("{}{}{}", PREFIX_ADDRESS, SEPARATOR, config.content),
-        _ => config.content.clone(),
-    };
+    Ok(threads)
+}
 
-    for i in 0..threads {
-        let prefix = prefix.clone();
-        // TODO: abort threads when one of them found the address
-        thread::spawn(move || search_for(prefix, config.placement, config.language, i));
+// Resolve the core of each worker thread if the pinning is requested
+// Returns Err if a requested core doesn't exist, Ok(None) to run unpinned
+fn resolve_pinning(common: &CommonArgs, threads: usize) -> Result<Option<Vec<usize>>, String> {
+    if !common.pinning() {
+        return Ok(None);
     }
 
-    if let Err(e) = run_prompt(prompt).await {
-        error!("Error while running prompt: {}", e);
+    match affinity::resolve_cores(threads, &common.cpu_list) {
+        Ok(Some(cores)) => {
+            let mapping = cores.iter()
+                .enumerate()
+                .map(|(thread, core)| format!("#{} -> {}", thread, core))
+                .collect::<Vec<_>>()
+                .join(", ");
+            info!("Pinning the worker threads to the cores: {}", mapping);
+            Ok(Some(cores))
+        },
+        Ok(None) => {
+            warn!("Thread pinning is not supported on this platform, running unpinned");
+            Ok(None)
+        },
+        Err(e) => Err(format!("Couldn't pin the threads: {}", e)),
     }
 }
 
-fn search_for(content: String, placement: Placement, language: usize, thread: usize) {
-    loop {
-        let keypair = KeyPair::new();
-        let address = keypair.get_public_key()
-            .to_address(true)
-            .to_string();
-
-        let valid = match placement {
-            Placement::Prefix => address.starts_with(&content),
-            Placement::Suffix => address.ends_with(&content),
-            Placement::Anywhere => address.contains(&content),
-        };
+// Threads of the workers and the cores they are pinned to
+fn resolve_workers(common: &CommonArgs) -> Result<(usize, Option<Vec<usize>>), String> {
+    let threads = resolve_threads(common)?;
+    Ok((threads, resolve_pinning(common, threads)?))
+}
+
+// Resolve where a suffix must end in the address
+// Returns None to search in the whole address
+// The prefixes are not affected by it
+fn resolve_suffix_end(before_checksum: bool, placements: &[Placement], network: Network, integrated: bool) -> Result<Option<usize>, &'static str> {
+    if !before_checksum {
+        return Ok(None);
+    }
+
+    if !placements.contains(&Placement::Suffix) {
+        return Err("--before-checksum can only be used with the suffix placement");
+    }
+
+    // The checksum would be excluded from the anywhere search too
+    if placements.contains(&Placement::Anywhere) {
+        return Err("--before-checksum can't be used with the anywhere placement");
+    }
+
+    // The payment ID is encoded after the public key
+    if integrated {
+        return Err("--before-checksum can't be used with integrated addresses");
+    }
+
+    Ok(Some(matcher::key_end(network)))
+}
 
-        if valid {
-            info!("Thread #{} found: {}", thread, address);
-            info!("Private key: {}", keypair.get_private_key().to_hex());
-            info!("Seed: {}", mnemonics::key_to_words(keypair.get_private_key(), language).unwrap().join(" "));
+// Each content must fit in the data characters of the address at its placements
+// With `--fuzzy`, a near miss can be a few characters longer than the address part
+// Returns true if the validation must stop now, see `Problems::add`
+fn check_lengths(problems: &mut Problems, contents: &[String], placements: &[Vec<Placement>], shape: &AddressShape, suffix_end: Option<usize>, network: Network, fuzzy: usize) -> bool {
+    for (content, placements) in contents.iter().zip(placements) {
+        let max = placements.iter()
+            .map(|placement| shape.data_len(suffix_end.filter(|_| *placement == Placement::Suffix)))
+            .max()
+            .unwrap_or(0);
+        if content.len() > max + fuzzy && problems.add(format!("'{}' is {} characters, longer than the {} data characters of a {} address", content, content.len(), max, network)) {
+            return true;
         }
+    }
+
+    false
+}
 
-        RATE_COUNTER.fetch_add(1, Ordering::Relaxed);
+// Some characters are never at some positions of a public key
+// Each content that can never be found is logged, the search is refused if none can
+// Returns true if the validation must stop now, see `Problems::add`
+fn check_reachable(problems: &mut Problems, contents: &[String], placements: &[Vec<Placement>], suffix_end: Option<usize>) -> bool {
+    let mut impossible = 0;
+    for (content, placement) in contents.iter().zip(placements).flat_map(|(content, placements)| placements.iter().map(move |placement| (content, *placement))) {
+        if let Some((index, c)) = matcher::unreachable(content, placement, suffix_end) {
+            warn!("Content '{}' can never be found as a {}: no public key has '{}' as data character {}", content, placement, c, index + 1);
+            impossible += 1;
+        }
     }
+
+    impossible > 0 && impossible == placements.iter().map(Vec::len).sum::<usize>() && problems.add("No content can ever be found, the search would never end")
 }
 
-async fn run_prompt(prompt: ShareablePrompt) -> Result<(), PromptError> {
-    let closure = |_: &_, _: _| async {
-        let rate = {
-            let mut last_time = RATE_LAST_TIME.lock().await;
-            let counter = RATE_COUNTER.swap(0, Ordering::Relaxed);
+async fn search(prompt: ShareablePrompt, mut args: SearchArgs) -> Outcome {
+    let mut problems = Problems::new(args.validate_only);
 
-            let hashrate = 1000f64 / (last_time.elapsed().as_millis() as f64 / counter as f64);
-            *last_time = Instant::now();
+    // Checked by the validation before detaching
+    if args.daemon {
+        if let Err(e) = args.validate_daemon() {
+            if problems.add(e) {
+                return Outcome::Error;
+            }
+        }
+    }
 
-            prompt.colorize_string(Color::Green, &format!("{}", format_hashrate(hashrate)))
+    // Read completely before starting, this is not a stream of contents
+    if args.reads_stdin() {
+        let contents = match dictionary::read_contents(io::stdin().lock()) {
+            Ok(contents) => contents,
+            Err(e) => return problems.fail(e),
         };
 
-        Ok(
-            format!(
-                "{} | {} {} ",
-                prompt.colorize_str(Color::Blue, "XELIS Vanity"),
-                rate,
-                prompt.colorize_str(Color::BrightBlack, ">>")
-            )
-        )
+        if contents.is_empty() {
+            return problems.fail("Prefix can't be empty: no content was read from stdin");
+        }
+
+        info!("Read {} contents from stdin, interactive mode is disabled", contents.len());
+        args.content.retain(|content| content != STDIN_CONTENT);
+        args.content.extend(contents);
+    }
+
+    // The next checks need the placement of every content
+    let mut placements = match parse_placements(&mut args.content, &args.placement, args.common.network) {
+        Ok(placements) => placements,
+        Err(found) => {
+            for problem in found {
+                if problems.add(problem) {
+                    return Outcome::Error;
+                }
+            }
+            return problems.finish().unwrap_or(Outcome::Error);
+        }
+    };
+
+    let desired = args.desired.as_deref().map(DesiredWord::new);
+    if let Some(desired) = &desired {
+        // Converted by the substitutions, but checked like any other content
+        let problem = if desired.content.is_empty() {
+            Some(format!("Desired word '{}' has no character available in addresses", desired.word))
+        } else {
+            matcher::check_content(&desired.content, args.common.network).err()
+        };
+
+        match problem {
+            Some(problem) => if problems.add(problem) {
+                return Outcome::Error;
+            },
+            None => {
+                info!("Desired word '{}' is searched as '{}' (substitutions: {})", desired.word, desired.content, desired.describe_substitutions());
+                args.content.push(desired.content.clone());
+            }
+        }
+    }
+
+    if let Some(path) = &args.dictionary {
+        match dictionary::load_dictionary(path, args.min_word_len, args.common.network) {
+            Ok(words) => args.content.extend(words),
+            Err(e) => if problems.add(e) {
+                return Outcome::Error;
+            }
+        }
+    }
+
+    // The desired word and the dictionary are searched at the global placements
+    placements.resize(args.content.len(), args.placement.clone());
+    let mut used_placements = placements.concat();
+    used_placements.sort_by_key(|placement| *placement as u8);
+    used_placements.dedup();
+
+    // The languages are already validated by the parser,
+    // but convert a key once so any issue shows up now instead of on the first match
+    let languages = args.seed_languages();
+    for (index, name) in languages.iter() {
+        if let Err(e) = mnemonics::key_to_words(KeyPair::new().get_private_key(), *index) {
+            if problems.add(format!("Couldn't convert a key to a seed in {}: {}", name, e)) {
+                return Outcome::Error;
+            }
+        }
+    }
+
+    let suffix_end = match resolve_suffix_end(args.before_checksum, &used_placements, args.common.network, args.integrated) {
+        Ok(value) => value,
+        Err(e) => return problems.fail(e),
+    };
+
+    let payment_id = args.payment_id();
+    // Workers are encoding the addresses themselves, make sure they are identical to the wallet ones
+    let keypair = KeyPair::new();
+    let id = payment_id.map(|v| v.generate());
+    let expected = keys::derive_address(&keypair, args.common.network, id);
+    if AddressEncoder::new(args.common.network).encode(&keypair, id) != expected && problems.add("Address encoding self-check failed, please report this issue") {
+        return Outcome::Error;
+    }
+
+    // The limits of the contents are the ones of the addresses of this network
+    let shape = AddressShape::sample(args.common.network, id);
+    if shape.hrp != args.common.network.prefix() && problems.add(format!("The {} addresses start with '{}' instead of '{}', please report this issue", args.common.network, shape.hrp, args.common.network.prefix())) {
+        return Outcome::Error;
+    }
+
+    if check_lengths(&mut problems, &args.content, &placements, &shape, suffix_end, args.common.network, args.fuzzy) {
+        return Outcome::Error;
+    }
+
+    // With a fixed payment ID, only the checksum is changing at the end of the address
+    if let Some(PaymentId::Fixed(_)) = payment_id {
+        if args.content.iter().zip(&placements).any(|(content, placements)| placements.contains(&Placement::Suffix) && content.len() > CHECKSUM_LEN) && problems.add(format!("With a fixed payment ID, a suffix can't be longer than the checksum ({} characters)", CHECKSUM_LEN)) {
+            return Outcome::Error;
+        }
+    }
+
+    // The palindrome is only made of data characters, at the start or the end of the searched area
+    if args.mode == Some(SearchMode::Palindrome) {
+        if args.placement.contains(&Placement::Anywhere) && problems.add("--mode palindrome can only be used with the prefix and suffix placements") {
+            return Outcome::Error;
+        }
+
+        let max = shape.data_len(suffix_end);
+        if args.length as usize > max && problems.add(format!("A palindrome can't be longer than the {} data characters of the address", max)) {
+            return Outcome::Error;
+        }
+    }
+
+    if args.mode == Some(SearchMode::DigitsSuffix) {
+        let max = shape.data_len(suffix_end);
+        if args.min_length as usize > max && problems.add(format!("The digits can't be more than the {} data characters of the address", max)) {
+            return Outcome::Error;
+        }
+    }
+
+    // Characters scored by the charset score mode, only the ones of the charset are accepted
+    let subset = match args.chars.as_deref().filter(|_| args.mode == Some(SearchMode::CharsetScore)).map(CharSubset::new).transpose() {
+        Ok(subset) => subset,
+        Err(e) => {
+            if problems.add(e) {
+                return Outcome::Error;
+            }
+            None
+        }
+    };
+
+    if let Some(target) = args.target_score.filter(|_| subset.is_some()) {
+        let max = shape.data_len(suffix_end);
+        if target as usize > max && problems.add(format!("The target score can't be more than the {} data characters of the address", max)) {
+            return Outcome::Error;
+        }
+    }
+
+    // Characters of the whole data part, the contents must be made of them too
+    let only = match args.only.as_deref().map(Allowlist::new).transpose() {
+        Ok(only) => only,
+        Err(e) => {
+            if problems.add(e) {
+                return Outcome::Error;
+            }
+            None
+        }
+    };
+    if let Some(only) = &only {
+        if let Some((content, c)) = args.content.iter().find_map(|content| only.missing(content).map(|c| (content, c))) {
+            if problems.add(format!("Content '{}' contains '{}' which is not in --only, it can never be found", content, c)) {
+                return Outcome::Error;
+            }
+        }
+    }
+
+    // The index is enough to derive a key again, and a split-key search only has the tweak
+    let recoverable = args.derive_from.is_some() || args.new_master || args.split_key.is_some();
+    if !recoverable && !args.output_fields.iter().any(|field| matches!(field, OutputField::PrivateKey | OutputField::Seed)) && problems.add("--output-fields must include private-key or seed, the keys found would be lost") {
+        return Outcome::Error;
+    }
+
+    // A QR code is the address itself
+    if (args.qr || args.qr_png) && !args.output_fields.contains(&OutputField::Address) && problems.add("--qr and --qr-png need address in --output-fields") {
+        return Outcome::Error;
+    }
+
+    // An edit of `--fuzzy` could replace an unreachable character
+    if args.mode.is_none() && args.fuzzy == 0 && check_reachable(&mut problems, &args.content, &placements, suffix_end) {
+        return Outcome::Error;
+    }
+
+    // Every address would be a near miss of a content this short
+    if args.fuzzy > 0 && args.content.iter().any(|content| content.len() <= args.fuzzy) && problems.add("--fuzzy must be lower than the length of each content") {
+        return Outcome::Error;
+    }
+
+    let (threads, cores) = match resolve_workers(&args.common) {
+        Ok(resolved) => resolved,
+        Err(e) => return problems.fail(e),
+    };
+
+    // Only the secure generator can be used for keys that are saved
+    let insecure = if args.common.fast_rng {
+        Some("--fast-rng")
+    } else if args.rng_seed.is_some() {
+        Some("--rng-seed")
+    } else {
+        None
+    };
+    if let Some(option) = insecure.filter(|_| args.common.output.is_some()) {
+        if problems.add(format!("{} can't be used with --output, the keys would be insecure", option)) {
+            return Outcome::Error;
+        }
+    }
+
+    // The tweak only gives the address if the public key is the inverse of the private key times the generator
+    if args.split_key.is_some() {
+        if !split::self_check() && problems.add("Split-key self-check failed, please report this issue") {
+            return Outcome::Error;
+        }
+
+        info!("Searching for tweaks of the split public key: only the tweaks are reported, the private key stays with its owner");
+    }
+
+    if let Some(seed) = args.rng_seed {
+        warn!("**************************************************************");
+        warn!("* --rng-seed is enabled: keys are generated from a KNOWN seed *");
+        warn!("* for tests only. NEVER use them to receive any funds.        *");
+        warn!("**************************************************************");
+        info!("Each worker generates the keys from the seed {} and its index", seed);
+    }
+
+    let master = resolve_master(args.derive_from, args.new_master);
+
+    // Resolve the derivation, resuming from the saved offset if any
+    let derivation = match master {
+        Some(master) => {
+            // Range of this machine, the whole range without a shard
+            let (first, end) = match args.shard {
+                Some(shard) => match shard.range(args.start_offset, args.end_offset) {
+                    Ok((first, end)) => {
+                        info!("Shard {}: indexes from {}{}", shard, first, end.map(|end| format!(" to {}", end)).unwrap_or_default());
+                        (first, end)
+                    },
+                    Err(e) => return problems.fail(e),
+                },
+                None => (args.start_offset, args.end_offset),
+            };
+
+            let start = match &args.offset_file {
+                Some(path) => match derivation::load_offset(path) {
+                    Ok(Some(offset)) => {
+                        info!("Resuming from the offset {} saved in {}", offset, path.display());
+                        offset
+                    },
+                    Ok(None) => first,
+                    Err(e) => return problems.fail(e),
+                },
+                None => first,
+            };
+
+            if end.is_some_and(|end| start >= end) && problems.add(format!("Start offset {} must be below the end offset", start)) {
+                return Outcome::Error;
+            }
+
+            // The file of another shard would search its indexes twice
+            if start < first && problems.add(format!("Offset {} is below the first index {} of the shard, the offset file may belong to another shard", start, first)) {
+                return Outcome::Error;
+            }
+
+            info!("Deriving the keys from the master seed, from index {}{}", start, end.map(|end| format!(" to {}", end)).unwrap_or_default());
+            Some(Derivation { master, start, end })
+        },
+        None => None
+    };
+    let progress = derivation.map(|derivation| Arc::new(Progress::new(derivation.start, threads)));
+
+    let len = shape.len;
+    let run_record = match (args.mode, &subset) {
+        (Some(SearchMode::LongestRun), _) => Some(Arc::new(RunRecord::new(args.min_run as usize, shape.data_len(None)))),
+        (Some(SearchMode::CharsetScore), Some(subset)) => {
+            let (subset, placements) = (subset.clone(), args.placement.clone());
+            let data_len = shape.data_len(suffix_end);
+            Some(Arc::new(RunRecord::with_probability(args.min_run as usize, move |score| subset.probability(score, &placements, data_len))))
+        },
+        _ => None,
+    };
+
+    // Probability of each content at each of its placements
+    let content_probabilities = args.content.iter().zip(&placements).flat_map(|(content, placements)| placements.iter().map(|placement| {
+        let allowed = only.as_ref().map_or(1f64, |only| only.probability(KEY_DATA_LEN.saturating_sub(content.len())));
+        let allowed = if unreachable(content, *placement).is_some() { 0f64 } else { allowed };
+        let probability = (estimate::match_probability(content.len(), *placement, len) * estimate::neighbours(content.len(), args.fuzzy)).min(1f64) * allowed;
+        (content.as_str(), *placement, probability)
+    })).collect::<Vec<_>>();
+
+    // In a pretty address mode, the probability to find the next record
+    let probability = match (&run_record, args.mode) {
+        (Some(record), _) => record.next_probability(),
+        (None, Some(SearchMode::Palindrome)) => estimate::any_probability(args.placement.iter().map(|_| pretty::palindrome_probability(args.length as usize))),
+        (None, Some(SearchMode::DigitsSuffix)) => pretty::digits_probability(args.min_length as usize),
+        _ => estimate::any_probability(content_probabilities.iter().map(|(_, _, probability)| *probability)),
+    };
+
+    // Difficulty measured by the previous runs of the same search
+    let stats_key = difficulty_key(&args, &placements);
+    let mut stats_cache = match args.stats_file.as_deref().map(DifficultyCache::load).transpose() {
+        Ok(cache) => cache,
+        Err(e) => {
+            if problems.add(e) {
+                return Outcome::Error;
+            }
+            None
+        }
+    };
+    let measured = stats_key.as_ref().and_then(|key| stats_cache.as_ref()?.get(key)).copied();
+    let probability = match measured.and_then(|measured| measured.probability()) {
+        Some(measured) => {
+            info!("Using the probability measured by the previous runs: 1 in {:.0} keys (theoretical: 1 in {:.0})", estimate::expected_attempts(measured), estimate::expected_attempts(probability));
+            measured
+        },
+        None => probability,
+    };
+
+    let configuration = configuration(&args, &placements, threads, cores.is_some(), derivation.as_ref(), probability);
+    if args.quiet && !args.dry_run && !args.validate_only {
+        info!("Searching for {} {} with content: {} at placement '{}'", args.common.network, if payment_id.is_some() { "integrated address" } else { "address" }, describe_contents(&args), format_content_placements(&args.placement, &placements));
+    } else {
+        log_configuration(&configuration);
+    }
+
+    let known_rate = measured.and_then(|measured| measured.rate(threads));
+    if !args.dry_run && !args.validate_only && !check_eta(&prompt, &args.eta, args.is_interactive(), probability, known_rate, || measure_rate(threads, ETA_CHECK_DURATION, &args.common, cores.clone())).await {
+        return Outcome::Error;
+    }
+
+    let mut patterns = match PatternSet::new(&args.content, &placements, args.common.network) {
+        Ok(patterns) => patterns,
+        Err(e) => return problems.fail(e),
+    };
+    patterns.set_raw(args.raw_match);
+
+    // The filter must accept the key of an address starting or ending with the content
+    // It only knows the exact contents, the near misses are only found on the address
+    let filter = if args.mode.is_some() {
+        None
+    } else if args.fuzzy > 0 {
+        info!("Near misses up to {} edits from a content are reported too, the public key filter is disabled", args.fuzzy);
+        None
+    } else {
+        KeyFilter::new(&args.content, &placements, suffix_end)
+    };
+    // Checked with each length and placement of the contents, on the characters of the sample address
+    if filter.is_some() {
+        let area = matcher::match_area(&expected, suffix_end);
+        let start = matcher::build_pattern("", Placement::Prefix, args.common.network).len();
+        let key = keypair.get_public_key().compress();
+        let failed = args.content.iter()
+            .zip(&placements)
+            .flat_map(|(content, placements)| placements.iter().map(|placement| (content.len(), *placement)))
+            .find(|(len, placement)| {
+                let sample = match placement {
+                    Placement::Prefix => &area[start..start + len],
+                    _ => &area[area.len() - len..],
+                };
+
+                !KeyFilter::new(&[sample.to_string()], &[vec![*placement]], suffix_end)
+                    .is_some_and(|f| f.is_matching(key.as_bytes()))
+            });
+        if let Some((len, placement)) = failed {
+            if problems.add(format!("Public key filter self-check failed for a {} of {} characters, please report this issue", placement, len)) {
+                return Outcome::Error;
+            }
+        }
+    }
+
+    // Everything is validated, only the estimate is left
+    // The matches are logged at the info level, their keys would only be in the output file
+    if matches!(args.common.log_level, LogLevel::Off | LogLevel::Error | LogLevel::Warn) && args.common.output.is_none() && problems.add("The matches are not logged below the info level, use --output to keep their keys") {
+        return Outcome::Error;
+    }
+
+    // Checked before the search so a quoting mistake isn't found at the first match
+    let hook = match args.exec.as_deref().map(|command| Hook::new(command, args.exec_include_secrets, Duration::from_secs(args.exec_timeout))).transpose() {
+        Ok(hook) => hook.map(Arc::new),
+        Err(e) => {
+            if problems.add(e) {
+                return Outcome::Error;
+            }
+            None
+        }
+    };
+
+    // Only validating creates the output file if needed, without locking nor writing it
+    if args.validate_only {
+        if let Some(Err(e)) = args.common.output.as_deref().map(output::check_writable) {
+            problems.add(e);
+        }
+
+        if let Some(outcome) = problems.finish() {
+            return outcome;
+        }
+
+        return validate_only(&content_probabilities, args.mode.is_none(), probability, known_rate);
+    }
+
+    // A dry run never touches the output file
+    if args.dry_run {
+        return dry_run(&args, threads, cores, probability, known_rate);
+    }
+
+    // The lock is kept until the end of the search
+    let Ok((_lock, output)) = open_output(args.common.output.as_deref()) else {
+        return Outcome::Error;
+    };
+
+    // Removed when dropped at the end of the search
+    let _pid_file = match args.pid_file.as_deref().map(PidFile::create).transpose() {
+        Ok(pid_file) => pid_file,
+        Err(e) => {
+            error!("{}", e);
+            return Outcome::Error;
+        }
+    };
+
+    // Totals of the previous searches, the counters of this one are added at the end
+    let lifetime = args.state_file().map(|path| Arc::new(Lifetime::load(&path)));
+
+    // Without a count, the search runs until it's stopped and one address is enough to succeed
+    // With a quota per content, each worker claims its match so exactly all the quotas are reported
+    let quotas = args.count_per_pattern.map(|quota| Arc::new(Quotas::new(args.content.len(), quota)));
+    let count = match args.count_per_pattern {
+        Some(quota) => Some(quota as usize * args.content.len()),
+        None => args.count.map(|count| count as usize),
+    };
+    let required = count.unwrap_or(1);
+    let best = Arc::new(BestMatches::new(&args.content));
+    let interactive = args.is_interactive();
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let sampler = (args.verbose > 0).then(|| Arc::new(Sampler::default()));
+    let context = SearchContext {
+        matcher: match (&run_record, subset, args.mode) {
+            (Some(record), Some(subset), _) => AddressMatcher::CharsetScore { record: Arc::clone(record), subset, placements: args.placement.clone() },
+            (Some(record), None, _) => AddressMatcher::LongestRun(Arc::clone(record)),
+            (None, _, Some(SearchMode::Palindrome)) => AddressMatcher::Palindrome { len: args.length as usize, placements: args.placement.clone() },
+            (None, _, Some(SearchMode::DigitsSuffix)) => AddressMatcher::DigitsSuffix { min_len: args.min_length as usize },
+            _ => AddressMatcher::Patterns(patterns),
+        },
+        suffix_end,
+        network: args.common.network,
+        payment_id,
+        filter,
+        only,
+        best: Arc::clone(&best),
+        quotas,
+        fuzzy: args.fuzzy,
+        fast_rng: args.common.fast_rng,
+        rng_seed: args.rng_seed,
+        derivation,
+        split_key: args.split_key,
+        progress: progress.clone(),
+        batch_size: args.common.batch_size as usize,
+        // Working all the time doesn't need any throttle
+        throttle: args.throttle.filter(|percent| *percent < 100),
+        sender,
+        on_progress: None,
+        sampler: sampler.clone(),
+    };
+
+    let mut searcher = match Searcher::new(threads, cores, args.low_priority) {
+        Ok(searcher) => searcher,
+        Err(e) => {
+            error!("Couldn't create the worker threads: {}", e);
+            return Outcome::Error;
+        }
+    };
+    let control = searcher.control();
+
+    // The API and the control socket share the counters of the status line, only the results are kept for them
+    let api_state = (args.api_bind.is_some() || args.control_socket.is_some())
+        .then(|| Arc::new(ApiState::new(threads, Arc::clone(&control), args.content.clone(), args.api_token.clone(), args.api_expose_secrets)));
+    let api = match (&args.api_bind, &api_state) {
+        (Some(bind), Some(state)) => match TcpListener::bind(bind).await {
+            Ok(listener) => {
+                info!("Status API listening on {}", bind);
+                Some((listener, Arc::clone(state)))
+            },
+            Err(e) => {
+                error!("Couldn't listen on {}: {}", bind, e);
+                return Outcome::Error;
+            }
+        },
+        _ => None,
+    };
+
+    let reporter = Reporter {
+        prompt: prompt.clone(),
+        colors: !args.common.disable_log_color,
+        network: args.common.network,
+        languages,
+        verify: args.verify,
+        insecure,
+        output,
+        qr: args.qr,
+        qr_dir: args.common.output.as_deref().filter(|_| args.qr_png).map(qr::output_dir),
+        desired,
+        mode: args.mode,
+        count,
+        found: Arc::new(Notify::new()),
+        max_logged_rate: args.max_logged_rate,
+        api: api_state.clone(),
+        verbose: args.verbose_output,
+        shard: args.shard,
+        fields: args.output_fields.clone(),
+        compact: args.compact,
+        hook,
+    };
+
+    let command_manager = CommandManager::new(prompt.clone());
+    let status_best = Arc::clone(&best);
+    let snapshot = Arc::new(Snapshot::new(searcher.workers(), Arc::clone(&best), lifetime.clone()));
+    if let Err(e) = commands::register_commands(&command_manager, best, Arc::clone(&control), Arc::clone(&snapshot)) {
+        error!("Couldn't register commands: {}", e);
+        return Outcome::Error;
+    }
+
+    let progress_sink = match args.progress_json.as_deref().map(ProgressSink::open).transpose() {
+        Ok(sink) => sink.map(|sink| Arc::new(Mutex::new(sink))),
+        Err(e) => {
+            error!("{}", e);
+            return Outcome::Error;
+        }
+    };
+
+    // Bound last so the socket file is always removed at the end of the search
+    let stop_requested = Arc::new(Notify::new());
+    let control_socket = match (&args.control_socket, &api_state) {
+        (Some(path), Some(state)) => match ControlSocket::bind(path) {
+            Ok(socket) => {
+                info!("Control socket listening on {}", path.display());
+                let controller = Controller {
+                    search: Arc::clone(&control),
+                    state: Arc::clone(state),
+                    stop: Arc::clone(&stop_requested),
+                };
+                Some((socket, Arc::new(controller)))
+            },
+            Err(e) => {
+                error!("{}", e);
+                return Outcome::Error;
+            }
+        },
+        _ => None,
+    };
+
+    let found = Arc::clone(&reporter.found);
+    let reporter = tokio::spawn(reporter.run(receiver));
+    let workers = searcher.workers();
+    let watchdog = tokio::spawn(watchdog::run(Arc::clone(&workers)));
+    let progress_task = progress_sink.clone().map(|sink| tokio::spawn(progress::run(sink, probability)));
+    let milestones = args.milestone.map(|every| tokio::spawn(progress::run_milestones(every, probability, progress_sink.clone())));
+    let api_server = api.map(|(listener, state)| tokio::spawn(api::serve(listener, state)));
+    let control_server = control_socket.map(|(socket, controller)| tokio::spawn(socket.serve(controller)));
+    let notifier = systemd::Notifier::new(args.systemd).map(Arc::new);
+    let notifier_task = notifier.clone().map(|notifier| tokio::spawn(systemd::run(notifier, Arc::clone(&workers))));
+    let stats_dumper = tokio::spawn(daemon::dump_stats_on_signal(snapshot));
+    let sampler_task = sampler.map(|sampler| tokio::spawn(log_samples(sampler, args.verbose, args.common.network, suffix_end)));
+    let started = Instant::now();
+    let started_at = get_current_time_in_millis();
+    searcher.start(context);
+    if let Some(notifier) = &notifier {
+        notifier.ready();
+    }
+    // An explicit number of threads is kept as is
+    let warmup = args.common.num_threads.is_none()
+        .then(|| tokio::spawn(warmup_threads(Arc::clone(&control), threads)));
+
+    let saver = match (&progress, &args.offset_file) {
+        (Some(progress), Some(path)) => Some(tokio::spawn(save_offset_periodically(Arc::clone(progress), path.clone()))),
+        _ => None
     };
 
-    prompt.start(Duration::from_secs(1), Box::new(async_handler!(closure)), None).await
-}
\ No newline at end of file
+    // Reason saved in the report
+    let stop_reason = tokio::select! {
+        res = run_prompt(prompt, &command_manager, probability, run_record.clone(), !args.quiet), if interactive => {
+            if let Err(e) = res {
+                error!("Error while running prompt: {}", e);
+            }
+            "exit"
+        },
+        _ = log_status(probability, Arc::clone(&status_best), run_record.clone()), if !interactive => "exit",
+        _ = tokio::signal::ctrl_c() => {
+            info!("Received Ctrl-C, stopping");
+            "ctrl-c"
+        },
+        _ = daemon::terminated() => {
+            info!("Received SIGTERM, stopping");
+            "sigterm"
+        },
+        _ = wait_finished(&searcher) => {
+            info!("All the workers stopped, the derivation reached its end offset");
+            "finished"
+        },
+        _ = found.notified() => {
+            info!("Found the {} requested addresses", required);
+            "found"
+        },
+        _ = stop_requested.notified() => "control-socket",
+        _ = wait_timeout(args.timeout) => {
+            info!("Timeout reached");
+            "timeout"
+        },
+        _ = wait_attempts(args.max_attempts) => {
+            info!("Maximum attempts reached");
+            "max-attempts"
+        },
+        _ = wait_score(run_record.clone(), args.target_score) => {
+            info!("Target score reached");
+            "target-score"
+        },
+    };
+
+    info!("Waiting for the workers to stop...");
+    if let Some(notifier) = &notifier {
+        notifier.stopping();
+    }
+    watchdog.abort();
+    stats_dumper.abort();
+    for task in [progress_task, milestones, api_server, control_server, notifier_task, sampler_task, warmup].into_iter().flatten() {
+        task.abort();
+    }
+    if let Some(path) = &args.control_socket {
+        control::remove(path);
+    }
+    searcher.stop();
+
+    let total = TOTAL_ATTEMPTS.load(Ordering::Relaxed);
+    let elapsed = started.elapsed().as_secs_f64();
+    let restarts = workers.iter().map(|worker| worker.restarts.load(Ordering::Relaxed)).sum::<u64>();
+    if restarts > 0 {
+        warn!("Workers were restarted {} times after a panic, see the errors above", restarts);
+    }
+    if let Some(record) = &run_record {
+        match record.address() {
+            Some(address) if args.mode == Some(SearchMode::CharsetScore) => info!("Best score: {} in {}", record.len(), address),
+            Some(address) => info!("Longest run: {} characters in {}", record.len(), address),
+            None => info!("No run of at least {} characters was found", args.min_run),
+        }
+    }
+
+    if let Some(progress) = progress {
+        if let Some(saver) = saver {
+            saver.abort();
+        }
+
+        let offset = progress.offset();
+        match (args.shard, derivation.and_then(|derivation| derivation.end)) {
+            (Some(_), Some(end)) => info!("Stopped at the derivation offset {}, use --start-offset {} --end-offset {} without --shard to resume", offset, offset, end),
+            _ => info!("Stopped at the derivation offset {}, use --start-offset {} to resume", offset, offset),
+        }
+        if let Some(path) = &args.offset_file {
+            if let Err(e) = derivation::save_offset(path, offset) {
+                error!("{}", e);
+            }
+        }
+    }
+
+    // All the senders are dropped with the workers, report the last results
+    match reporter.await {
+        Ok(stats) => {
+            log_summary(&args, stop_reason, total, elapsed, probability, &stats);
+
+            if let Some(path) = &args.report {
+                let report = RunReport {
+                    started: started_at,
+                    stopped: get_current_time_in_millis(),
+                    elapsed,
+                    stop_reason,
+                    attempts: total,
+                    average_rate: total as f64 / elapsed,
+                    peak_rate: RATE_HISTORY.lock().await.peak(),
+                    matches: stats.count(),
+                    addresses: stats.addresses(),
+                    threads: workers.iter().map(|worker| worker.attempts.load(Ordering::Relaxed)).collect(),
+                    restarts: workers.iter().map(|worker| worker.restarts.load(Ordering::Relaxed)).collect(),
+                    configuration: configuration.into_iter().collect(),
+                };
+
+                match report.write(path) {
+                    Ok(()) => info!("Report written to {}", path.display()),
+                    Err(e) => error!("{}", e),
+                }
+            }
+
+            if let (Some(cache), Some(key)) = (stats_cache.as_mut(), stats_key) {
+                cache.add(key, total, stats.count() as u64, total as f64 / elapsed, threads);
+                if let Err(e) = cache.save() {
+                    error!("{}", e);
+                }
+            }
+
+            if let Some(lifetime) = &lifetime {
+                let session = lifetime.session(RATE_HISTORY.lock().await.peak());
+                let totals = lifetime.total(&session);
+                info!("Session: {}", session.summary());
+                info!("Lifetime: {} over {} searches", totals.summary(), totals.runs);
+                if let Err(e) = lifetime.save(&totals) {
+                    error!("{}", e);
+                }
+            }
+
+            if stats.count() >= required {
+                Outcome::Success
+            } else {
+                info!("Found {} of the {} requested addresses", stats.count(), required);
+                Outcome::NotFound
+            }
+        },
+        Err(e) => {
+            error!("Reporter task failed: {}", e);
+            Outcome::Error
+        }
+    }
+}
+
+// Summary of the search once stopped, whatever stopped it
+// The addresses are listed without their keys, which are only in the detailed logs and the output file
+fn log_summary(args: &SearchArgs, stop_reason: &str, total: u64, elapsed: f64, probability: f64, stats: &MatchStats) {
+    info!("Search summary (stopped by {}):", stop_reason);
+    info!("  Tried {} keys ({}) in {} at an average of {}", total, stats::format_count(total), estimate::format_duration(elapsed), stats::format_rate(total as f64 / elapsed));
+    // The probability of a pretty address mode is the one of the next record, not of the ones found
+    match args.mode {
+        None => info!("  Found {} addresses, {:.2} expected for the keys tried", stats.count(), total as f64 * probability),
+        Some(_) => info!("  Found {} addresses", stats.count()),
+    }
+    if stats.count() > 0 {
+        info!("  Keys per match: mean {:.0}, min {}, max {} (expected: {:.0})", stats.mean(), stats.min(), stats.max(), estimate::expected_attempts(probability));
+    }
+
+    let found = stats.contents().collect::<Vec<_>>();
+    match args.count_per_pattern {
+        Some(quota) => {
+            info!("  Found {} of the {} contents:", found.iter().filter(|(_, count, _)| *count as u64 >= quota).count(), args.content.len());
+            for (content, count, attempts) in found {
+                info!("    {}: {}/{} found, last after {} keys", content, count, quota, attempts);
+            }
+        },
+        None if !found.is_empty() => {
+            info!("  Matches by content:");
+            for (content, count, attempts) in found {
+                info!("    {}: {} found, last after {} keys", content, count, attempts);
+            }
+        },
+        None => {},
+    }
+
+    if !stats.addresses().is_empty() {
+        info!("  Addresses:");
+        for address in stats.addresses() {
+            info!("    {}", address);
+        }
+        if stats.count() > stats.addresses().len() {
+            info!("    and {} more, see the logs or the output file", stats.count() - stats.addresses().len());
+        }
+    }
+}
+
+// Lock and open the output file if any
+fn open_output(path: Option<&Path>) -> Result<(Option<OutputLock>, Option<Arc<OutputFile>>), ()> {
+    let Some(path) = path else {
+        return Ok((None, None));
+    };
+
+    let lock = match OutputLock::acquire(path) {
+        Ok(lock) => lock,
+        Err(e) => {
+            error!("Couldn't lock the output file: {}", e);
+            return Err(());
+        }
+    };
+
+    match OutputFile::open(path) {
+        Ok(file) => Ok((Some(lock), Some(Arc::new(file)))),
+        Err(e) => {
+            error!("{}", e);
+            Err(())
+        }
+    }
+}
+
+// A master seed written on the command line can be read by the other users in the process list
+fn warn_inline_master() {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--derive-from") {
+            Some("") => args.next(),
+            Some(value) => value.strip_prefix('=').map(str::to_string),
+            None => continue,
+        };
+
+        if value.is_some_and(|value| derivation::is_inline_master(&value)) {
+            warn!("The master seed given to --derive-from is visible in the process list, give a file holding it or env:<variable> instead");
+        }
+    }
+}
+
+// A new master seed is printed before searching, it's needed to derive the keys again
+fn resolve_master(derive_from: Option<[u8; MASTER_SIZE]>, new_master: bool) -> Option<[u8; MASTER_SIZE]> {
+    match (derive_from, new_master) {
+        (None, true) => {
+            let master = derivation::generate_master();
+            warn!("Generated the master seed {}", derivation::format_master(&master));
+            warn!("Keep it as secret as a private key, the keys found can be derived again from it and their index");
+            Some(master)
+        },
+        (master, _) => master,
+    }
+}
+
+// Contents to show in the logs
+// A dictionary can contain thousands of words, only their number is shown then
+fn format_contents(contents: &[String]) -> String {
+    if contents.len() > MAX_LOGGED_CONTENTS {
+        format!("{} contents", contents.len())
+    } else {
+        contents.join(", ")
+    }
+}
+
+// Contents to show in the logs, or the pretty address searched
+fn describe_contents(args: &SearchArgs) -> String {
+    match args.mode {
+        Some(SearchMode::LongestRun) => format!("longest run of a repeated character (at least {})", args.min_run),
+        Some(SearchMode::Palindrome) => format!("palindrome of {} characters", args.length),
+        Some(SearchMode::DigitsSuffix) => format!("at least {} digits at the end ({})", args.min_length, pretty::charset_digits().into_iter().collect::<String>()),
+        Some(SearchMode::CharsetScore) => format!("longest run of the characters '{}' (at least {}{})", args.chars.as_deref().unwrap_or_default(), args.min_run, args.target_score.map(|target| format!(", target {}", target)).unwrap_or_default()),
+        None => format_contents(&args.content),
+    }
+}
+
+fn format_placements(placements: &[Placement]) -> String {
+    placements.iter()
+        .map(|placement| placement.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Key of the search in the statistics file: network, placements and contents
+// The records are not saved, their difficulty changes with each one
+fn difficulty_key(args: &SearchArgs, placements: &[Vec<Placement>]) -> Option<String> {
+    let pattern = match args.mode {
+        Some(SearchMode::LongestRun | SearchMode::CharsetScore) => return None,
+        Some(SearchMode::Palindrome) => format!("palindrome:{}:{}", format_placements(&args.placement), args.length),
+        Some(SearchMode::DigitsSuffix) => format!("digits-suffix:{}", args.min_length),
+        None => args.content.iter()
+            .zip(placements)
+            .map(|(content, placements)| format!("{}:{}", format_placements(placements).replace(", ", "+"), content))
+            .collect::<Vec<_>>()
+            .join(","),
+    };
+
+    let mut key = format!("{} {}", args.common.network, pattern);
+    if args.before_checksum {
+        key.push_str(" before-checksum");
+    }
+    if args.fuzzy > 0 {
+        key.push_str(&format!(" fuzzy:{}", args.fuzzy));
+    }
+    if let Some(only) = &args.only {
+        key.push_str(&format!(" only:{}", only));
+    }
+
+    Some(key)
+}
+
+// Check the time to have a 50% chance to find an address against `--eta-threshold`
+// The rate saved by a previous run is used when known, instead of measuring it
+// Returns false if the search must not start
+async fn check_eta(prompt: &ShareablePrompt, eta: &EtaArgs, interactive: bool, probability: f64, known_rate: Option<f64>, measure: impl FnOnce() -> f64) -> bool {
+    let threshold = eta.eta_threshold as f64 * 3600f64;
+    let astronomical = eta.astronomical_threshold as f64 * YEAR;
+    let attempts = estimate::attempts_for_chance(probability, 0.5);
+    // Even at the slowest rate, the search is below both thresholds
+    if attempts / MIN_EXPECTED_RATE <= threshold.min(astronomical) {
+        return true;
+    }
+
+    let rate = match known_rate {
+        Some(rate) => rate,
+        None => {
+            info!("Measuring the rate to estimate the search duration...");
+            measure()
+        }
+    };
+    let seconds = attempts / rate;
+    match classify_eta(seconds, threshold, astronomical) {
+        Eta::Short => return true,
+        Eta::Astronomical => return confirm_astronomical(prompt, eta, interactive, seconds, rate).await,
+        Eta::Long => {}
+    }
+
+    warn!("**************************************************************");
+    warn!("* This search is expected to take a VERY long time            *");
+    warn!("**************************************************************");
+    warn!("50% chance to find an address after {} at {} (threshold: {})", estimate::format_duration(seconds), stats::format_rate(rate), estimate::format_duration(threshold));
+
+    if eta.yes {
+        return true;
+    }
+
+    // Nobody is there to confirm it, the warning stays in the logs
+    if !interactive {
+        warn!("Starting anyway as the search is not interactive");
+        return true;
+    }
+
+    error!("Use a shorter content, another placement or more threads, or --yes to start it anyway");
+    false
+}
+
+// Time to have a 50% chance to find an address, against the thresholds in seconds
+#[derive(Debug, PartialEq)]
+enum Eta {
+    Short,
+    // Above `--eta-threshold`
+    Long,
+    // Above `--astronomical-threshold`
+    Astronomical,
+}
+
+// The astronomical threshold is checked first, so an `--eta-threshold` above it never skips the confirmation
+fn classify_eta(seconds: f64, threshold: f64, astronomical: f64) -> Eta {
+    if seconds > astronomical {
+        Eta::Astronomical
+    } else if seconds <= threshold {
+        Eta::Short
+    } else {
+        Eta::Long
+    }
+}
+
+// Above `--astronomical-threshold`, the search is only started once confirmed
+// It's always logged, even with `--yes-i-know`
+async fn confirm_astronomical(prompt: &ShareablePrompt, eta: &EtaArgs, interactive: bool, seconds: f64, rate: f64) -> bool {
+    warn!("**************************************************************");
+    warn!("* This search is expected to take MORE THAN {:>3} YEARS        *", eta.astronomical_threshold);
+    warn!("**************************************************************");
+    warn!("50% chance to find an address after {} at {}", estimate::format_duration(seconds), stats::format_rate(rate));
+
+    if eta.yes_i_know {
+        warn!("Starting anyway with --yes-i-know");
+        return true;
+    }
+
+    if !interactive {
+        error!("Nobody can confirm it, use a shorter content or --yes-i-know to start it anyway");
+        return false;
+    }
+
+    match prompt.read_input("Type 'yes' to start it anyway: ", false).await {
+        Ok(answer) if answer.trim() == "yes" => {
+            warn!("Starting as confirmed");
+            true
+        },
+        Ok(_) => {
+            error!("Search cancelled");
+            false
+        },
+        Err(e) => {
+            error!("Couldn't read the confirmation: {}", e);
+            false
+        }
+    }
+}
+
+// Measure the rate and estimate the search duration without searching
+// The search is achievable if it has a 50% chance to find an address within `--eta-threshold`
+fn dry_run(args: &SearchArgs, threads: usize, cores: Option<Vec<usize>>, probability: f64, known_rate: Option<f64>) -> Outcome {
+    let rate = match known_rate {
+        Some(rate) => rate,
+        None => {
+            info!("Measuring the rate of this machine...");
+            measure_rate(threads, Duration::from_secs(3), &args.common, cores)
+        }
+    };
+
+    info!("Estimation at {}:", stats::format_rate(rate));
+    info!("Expected attempts: {:.0} ({})", estimate::expected_attempts(probability), estimate::format_duration(estimate::expected_attempts(probability) / rate));
+    for chance in [0.5, 0.9, 0.99] {
+        let attempts = estimate::attempts_for_chance(probability, chance);
+        info!("{}% chance: {:.0} attempts ({})", chance * 100f64, attempts, estimate::format_duration(attempts / rate));
+    }
+
+    if args.before_checksum {
+        info!("Checksum characters are as random as the others: the difficulty is the same with or without --before-checksum");
+    }
+
+    let threshold = args.eta.eta_threshold as f64 * 3600f64;
+    let seconds = estimate::attempts_for_chance(probability, 0.5) / rate;
+    if seconds > threshold {
+        warn!("Dry run: the configuration is valid but a 50% chance takes longer than the threshold of {}, the search would need --yes", estimate::format_duration(threshold));
+        return Outcome::TooLong;
+    }
+
+    info!("Dry run: the configuration is valid, no search was started");
+    Outcome::Success
+}
+
+// Estimate without measuring the rate: no key is generated and no thread is started
+// The durations are only given with the rate saved by `--stats-file`
+fn validate_only(content_probabilities: &[(&str, Placement, f64)], contents: bool, probability: f64, known_rate: Option<f64>) -> Outcome {
+    let duration = |attempts: f64| known_rate.map(|rate| format!(" ({} at {})", estimate::format_duration(attempts / rate), stats::format_rate(rate))).unwrap_or_default();
+
+    if contents {
+        for (content, placement, probability) in content_probabilities {
+            let attempts = estimate::expected_attempts(*probability);
+            info!("'{}' ({}): {:.0} expected attempts{}", content, placement, attempts, duration(attempts));
+        }
+    }
+
+    let attempts = estimate::expected_attempts(probability);
+    info!("Expected attempts for the first match: {:.0}{}", attempts, duration(attempts));
+    info!("Validation: the configuration is valid, no key was generated");
+    Outcome::Success
+}
+
+// Global placements, with the number of contents having their own
+fn format_content_placements(global: &[Placement], placements: &[Vec<Placement>]) -> String {
+    let own = placements.iter().filter(|placements| placements.as_slice() != global).count();
+    if own == 0 {
+        return format_placements(global);
+    }
+
+    format!("{} ({} contents with their own placement)", format_placements(global), own)
+}
+
+// Summary of the effective configuration, logged before starting the search and saved in the report
+fn configuration(args: &SearchArgs, placements: &[Vec<Placement>], threads: usize, pinned: bool, derivation: Option<&Derivation>, probability: f64) -> Vec<(&'static str, String)> {
+    let placement = match (args.before_checksum, args.raw_match) {
+        (true, _) => format!("{} (suffix before checksum)", format_content_placements(&args.placement, placements)),
+        (false, true) => format!("{} (raw, anywhere includes the network prefix)", format_content_placements(&args.placement, placements)),
+        (false, false) => format_content_placements(&args.placement, placements),
+    };
+    let placement = match &args.only {
+        Some(only) => format!("{} (data only made of '{}')", placement, only),
+        None => placement,
+    };
+
+    let address_type = match args.payment_id() {
+        Some(PaymentId::Fixed(id)) => format!("integrated (payment ID: {})", id),
+        Some(PaymentId::Random) => "integrated (random payment IDs)".to_string(),
+        None => "normal".to_string(),
+    };
+
+    let keys = match (derivation, args.rng_seed) {
+        _ if args.split_key.is_some() => "tweaks of the split public key".to_string(),
+        (Some(derivation), _) => match args.shard {
+            Some(shard) => format!("derived from the master seed, shard {} from index {}", shard, derivation.start),
+            None => format!("derived from the master seed, from index {}", derivation.start),
+        },
+        (None, Some(seed)) => format!("INSECURE generator seeded with {}", seed),
+        (None, None) if args.common.fast_rng => "INSECURE fast generator".to_string(),
+        (None, None) => "secure random generator".to_string(),
+    };
+
+    let languages = args.seed_languages()
+        .iter()
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let output = match &args.common.output {
+        Some(path) => path.display().to_string(),
+        None => "none".to_string(),
+    };
+
+    let log_file = if args.common.file_logging() {
+        format!("{}{}", args.common.log_dir(), args.common.log_file())
+    } else {
+        "disabled".to_string()
+    };
+
+    let config_file = match &args.common.config {
+        Some(path) => path.display().to_string(),
+        None => "none".to_string(),
+    };
+
+    let threads = format!(
+        "{}{}{}",
+        threads,
+        if pinned { " (pinned)" } else { "" },
+        args.throttle.map(|percent| format!(" (throttled at {}%)", percent)).unwrap_or_default()
+    );
+
+    vec![
+        ("configuration file", config_file),
+        ("content", describe_contents(args)),
+        ("placement", placement),
+        ("network", args.common.network.to_string()),
+        ("address type", address_type),
+        ("threads", threads),
+        ("keys", keys),
+        ("seed languages", languages),
+        ("output file", output),
+        ("log file", log_file),
+        ("expected attempts", format!("{:.0} (50% chance: {:.0})", estimate::expected_attempts(probability), estimate::attempts_for_chance(probability, 0.5))),
+    ]
+}
+
+fn log_configuration(configuration: &[(&str, String)]) {
+    let lines = configuration.iter()
+        .map(|(key, value)| format!("\n  {}: {}", key, value))
+        .collect::<String>();
+
+    info!("Configuration:{}", lines);
+}
+
+fn bench(args: BenchArgs) -> Outcome {
+    if args.compare_matchers {
+        bench_matchers(Duration::from_secs(args.duration));
+        return Outcome::Success;
+    }
+
+    let (threads, cores) = match resolve_workers(&args.common) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            error!("{}", e);
+            return Outcome::Error;
+        }
+    };
+
+    info!("Running benchmark for {}s with batches of {} keys...", args.duration, args.common.batch_size);
+    let rate = measure_rate(threads, Duration::from_secs(args.duration), &args.common, cores);
+    info!("Rate: {} ({} per thread)", stats::format_rate(rate), stats::format_rate(rate / threads as f64));
+
+    // An explicit number of threads is kept as is
+    if args.common.num_threads.is_none() {
+        check_logical_threads(&args, threads, rate);
+    }
+
+    Outcome::Success
+}
+
+// On some CPUs, the logical threads beyond the physical cores are lowering the rate
+// Measure the rate with one thread per physical core and suggest it if it's faster
+fn check_logical_threads(args: &BenchArgs, threads: usize, rate: f64) {
+    let physical = num_cpus::get_physical();
+    if physical == 0 || physical >= threads {
+        return;
+    }
+
+    let cores = match resolve_pinning(&args.common, physical) {
+        Ok(cores) => cores,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+
+    info!("Running benchmark for {}s with one thread per physical core ({} threads)...", args.duration, physical);
+    let physical_rate = measure_rate(physical, Duration::from_secs(args.duration), &args.common, cores);
+    info!("Rate: {} ({} per thread)", stats::format_rate(physical_rate), stats::format_rate(physical_rate / physical as f64));
+
+    if physical_rate > rate {
+        warn_logical_threads(threads, physical, rate, physical_rate);
+    } else {
+        info!("The logical threads are faster than the physical cores alone, keep the default number of threads");
+    }
+}
+
+fn warn_logical_threads(threads: usize, physical: usize, rate: f64, physical_rate: f64) {
+    warn!("The {} logical threads are slower than the {} physical cores on this CPU ({} vs {}), use --num-threads {} for a better rate", threads, physical, stats::format_rate(rate), stats::format_rate(physical_rate), physical);
+}
+
+// Same check as `bench` during the first seconds of the search: measure the rate with all the threads,
+// then with one working thread per physical core, and restore all the threads
+// The measure is dropped if the search is paused or its threads are changed meanwhile,
+// a derived search refuses to change its threads so it stops after the first measure
+async fn warmup_threads(control: Arc<SearchControl>, threads: usize) {
+    let physical = num_cpus::get_physical();
+    if physical == 0 || physical >= threads {
+        return;
+    }
+
+    tokio::time::sleep(WARMUP_DELAY).await;
+    let rate = measure_search_rate(WARMUP_WINDOW).await;
+    if control.is_paused() || control.active_threads() != threads || control.set_active_threads(physical).is_err() {
+        return;
+    }
+
+    info!("Measuring the rate with one thread per physical core ({} threads) for {}s", physical, WARMUP_WINDOW.as_secs());
+    let physical_rate = measure_search_rate(WARMUP_WINDOW).await;
+    if control.active_threads() != physical {
+        return;
+    }
+    if let Err(e) = control.set_active_threads(threads) {
+        warn!("Couldn't restore the {} threads after the warmup: {}", threads, e);
+    }
+
+    if !control.is_paused() && physical_rate > rate {
+        warn_logical_threads(threads, physical, rate, physical_rate);
+    }
+}
+
+// Rate of the running search during the duration
+async fn measure_search_rate(duration: Duration) -> f64 {
+    let start = Instant::now();
+    let before = TOTAL_ATTEMPTS.load(Ordering::Relaxed);
+    tokio::time::sleep(duration).await;
+    TOTAL_ATTEMPTS.load(Ordering::Relaxed).saturating_sub(before) as f64 / start.elapsed().as_secs_f64()
+}
+
+fn estimate(mut args: EstimateArgs) -> Outcome {
+    warn_address_prefix(&args.content, &args.placement, args.common.network);
+    match matcher::parse_content_placement(&args.content, args.common.network) {
+        Ok((placement, content)) => {
+            if let Some(placement) = placement {
+                args.placement = vec![placement];
+            }
+            args.content = content.to_string();
+        },
+        Err(e) => {
+            error!("{}", e);
+            return Outcome::Error;
+        }
+    }
+
+    if !validate_content(&args.content, args.common.network) {
+        return Outcome::Error;
+    }
+
+    if let Err(e) = resolve_suffix_end(args.before_checksum, &args.placement, args.common.network, args.integrated) {
+        error!("{}", e);
+        return Outcome::Error;
+    }
+
+    let payment_id = if args.integrated { Some(PaymentId::Random.generate()) } else { None };
+    let len = AddressShape::sample(args.common.network, payment_id).len;
+    let probability = estimate::any_probability(args.placement.iter().map(|placement| estimate::match_probability(args.content.len(), *placement, len)));
+    let rate = match args.rate {
+        Some(rate) if rate > 0f64 => rate,
+        Some(_) => {
+            error!("Rate must be greater than 0");
+            return Outcome::Error;
+        },
+        None => {
+            let (threads, cores) = match resolve_workers(&args.common) {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    error!("{}", e);
+                    return Outcome::Error;
+                }
+            };
+
+            info!("Measuring the rate of this machine...");
+            measure_rate(threads, Duration::from_secs(3), &args.common, cores)
+        }
+    };
+
+    info!("Estimation for content '{}' at placement '{}' at {}:", args.content, format_placements(&args.placement), stats::format_rate(rate));
+    info!("Expected attempts: {:.0} ({})", estimate::expected_attempts(probability), estimate::format_duration(estimate::expected_attempts(probability) / rate));
+    for chance in [0.5, 0.9, 0.99] {
+        let attempts = estimate::attempts_for_chance(probability, chance);
+        info!("{}% chance: {:.0} attempts ({})", chance * 100f64, attempts, estimate::format_duration(attempts / rate));
+    }
+
+    if args.placement.contains(&Placement::Suffix) {
+        info!("Checksum characters are as random as the others: the difficulty is the same with or without --before-checksum");
+    }
+
+    Outcome::Success
+}
+
+// Compare the naive scan of each pattern with the automaton, for the anywhere placement
+// `duration` is shared between all the measures
+fn bench_matchers(duration: Duration) {
+    const COUNTS: [usize; 3] = [1, 10, 100];
+    const ADDRESSES: usize = 1024;
+    const CONTENT_LEN: usize = 5;
+    const SCORED_CHARS: &str = "xels";
+
+    let mut encoder = AddressEncoder::new(Network::Mainnet);
+    let addresses: Vec<String> = (0..ADDRESSES).map(|_| encoder.encode(&KeyPair::new(), None).to_string()).collect();
+    let duration = duration / (COUNTS.len() as u32 * 2 + 1);
+    let charset = CHARSET.as_bytes();
+
+    info!("Comparing the matchers on {} addresses...", ADDRESSES);
+    for count in COUNTS {
+        let contents: Vec<String> = (0..count)
+            .map(|_| (0..CONTENT_LEN).map(|_| charset[rand::random::<usize>() % charset.len()] as char).collect())
+            .collect();
+
+        let patterns = match PatternSet::new(&contents, &vec![vec![Placement::Anywhere]; count], Network::Mainnet) {
+            Ok(patterns) => patterns,
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        };
+
+        let naive = measure_matches(duration, &addresses, |address| contents.iter().any(|content| address.contains(content.as_str())));
+        let automaton = measure_matches(duration, &addresses, |address| patterns.find(address).is_some());
+        info!("{} patterns: naive {} / automaton {}", count, stats::format_rate(naive), stats::format_rate(automaton));
+    }
+
+    // The charset score runs on every address too
+    let subset = match CharSubset::new(SCORED_CHARS) {
+        Ok(subset) => subset,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        }
+    };
+
+    let start = matcher::data_start(Network::Mainnet);
+    let score = measure_matches(duration, &addresses, |address| !subset.score(&address.as_bytes()[start..], Placement::Anywhere).is_empty());
+    info!("Charset score of '{}': {}", SCORED_CHARS, stats::format_rate(score));
+}
+
+// Run the matcher on the addresses during the duration
+// Returns the number of addresses checked per second
+fn measure_matches(duration: Duration, addresses: &[String], matcher: impl Fn(&str) -> bool) -> f64 {
+    let start = Instant::now();
+    let mut checked = 0;
+    while start.elapsed() < duration {
+        for address in addresses {
+            black_box(matcher(black_box(address)));
+        }
+        checked += addresses.len();
+    }
+
+    checked as f64 / start.elapsed().as_secs_f64()
+}
+
+// Load the private key given in hex or as a seed
+fn load_private_key(private_key: Option<&str>, seed: Option<&str>, language: Option<usize>) -> Option<PrivateKey> {
+    let private_key = match (private_key, seed) {
+        (Some(hex), _) => keys::parse_private_key(hex),
+        (None, Some(seed)) => keys::parse_seed(seed, language),
+        (None, None) => Err("a private key or a seed is required".to_string()),
+    };
+
+    match private_key {
+        Ok(value) => Some(value),
+        Err(e) => {
+            error!("Couldn't load the key: {}", e);
+            None
+        }
+    }
+}
+
+fn verify(args: VerifyArgs) -> bool {
+    let keypair = match (args.derive_from, args.index) {
+        (Some(master), Some(index)) => {
+            let keypair = keys::keypair_from_entropy(derivation::derive_entropy(&master, index));
+            info!("Private key at index {}: {}", index, keypair.get_private_key().to_hex());
+            keypair
+        },
+        _ => {
+            let Some(private_key) = load_private_key(args.private_key.as_deref(), args.seed.as_deref(), None) else {
+                return false;
+            };
+
+            KeyPair::from_private_key(private_key)
+        }
+    };
+
+    let address = keys::derive_address(&keypair, args.common.network, args.payment_id);
+    info!("Address: {}", address);
+
+    let Some(value) = args.content.as_deref() else {
+        return true;
+    };
+
+    warn_address_prefix(value, &[args.placement], args.common.network);
+    let (placement, content) = match matcher::parse_content_placement(value, args.common.network) {
+        Ok((placement, content)) => (placement.unwrap_or(args.placement), content),
+        Err(e) => {
+            error!("{}", e);
+            return false;
+        }
+    };
+
+    if !validate_content(content, args.common.network) {
+        return false;
+    }
+
+    let suffix_end = match resolve_suffix_end(args.before_checksum, &[placement], args.common.network, args.payment_id.is_some()) {
+        Ok(value) => value,
+        Err(e) => {
+            error!("{}", e);
+            return false;
+        }
+    };
+
+    let pattern = matcher::build_pattern(content, placement, args.common.network);
+    if matcher::is_matching(matcher::match_area(&address, suffix_end), &pattern, placement, args.raw_match) {
+        info!("Address contains '{}' at placement '{}'", content, placement);
+        true
+    } else {
+        error!("Address doesn't contain '{}' at placement '{}'", content, placement);
+        false
+    }
+}
+
+fn derive(args: DeriveArgs) -> bool {
+    let Some(private_key) = load_private_key(args.private_key.as_deref(), args.seed.as_deref(), args.language) else {
+        return false;
+    };
+
+    let keypair = KeyPair::from_private_key(private_key);
+    info!("Address: {}", keys::derive_address(&keypair, args.common.network, args.payment_id));
+    true
+}
+
+// Combine the private key with the tweak of a split-key search
+// The private key is only printed once the combined key derives the expected address
+fn combine(args: CombineArgs) -> bool {
+    let Some(private_key) = load_private_key(args.private_key.as_deref(), args.seed.as_deref(), None) else {
+        return false;
+    };
+
+    let combined = match split::combine(&private_key, &args.tweak) {
+        Ok(combined) => combined,
+        Err(e) => {
+            error!("Couldn't combine the keys: {}", e);
+            return false;
+        }
+    };
+
+    let keypair = KeyPair::from_private_key(combined);
+    let address = keys::derive_address(&keypair, args.common.network, args.payment_id);
+    info!("Address: {}", address);
+    if let Some(expected) = &args.address {
+        if &address != expected {
+            error!("The combined key derives {} instead of {}, check the private key, the tweak, the network and the payment ID", address, expected);
+            return false;
+        }
+
+        info!("The combined key derives the expected address");
+    }
+
+    info!("Private key: {}", keypair.get_private_key().to_hex());
+    let words = match mnemonics::key_to_words(keypair.get_private_key(), args.language) {
+        Ok(words) => words,
+        Err(e) => {
+            error!("Couldn't convert the private key to a seed: {}", e);
+            return false;
+        }
+    };
+
+    if let Err(e) = keys::verify_seed(&words, &address, args.common.network, args.payment_id) {
+        error!("Seed verification failed: {}, keep the private key above instead", e);
+        return false;
+    }
+
+    info!("Seed: {}", words.join(" "));
+    true
+}
+
+// Search the jobs of a coordinator until it stops the search
+async fn worker(args: WorkerArgs) -> bool {
+    // The keys are sent to the coordinator, they must be secure
+    if args.common.fast_rng {
+        error!("--fast-rng can't be used by a worker");
+        return false;
+    }
+
+    let (threads, cores) = match resolve_workers(&args.common) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            error!("{}", e);
+            return false;
+        }
+    };
+
+    let options = WorkerOptions {
+        address: args.connect,
+        name: args.name,
+        threads,
+        cores,
+        low_priority: args.low_priority,
+        batch_size: args.common.batch_size as usize,
+        coordinator_key: args.coordinator_key,
+    };
+
+    if let Err(e) = worker::run(options).await {
+        error!("Worker stopped: {}", e);
+        return false;
+    }
+
+    true
+}
+
+// Hand the search to the workers connecting to `--bind` until the requested addresses are found
+async fn coordinator(prompt: ShareablePrompt, mut args: CoordinatorArgs) -> Outcome {
+    let network = args.common.network;
+    let placements = match parse_placements(&mut args.content, &args.placement, network) {
+        Ok(placements) => placements,
+        Err(problems) => {
+            problems.iter().for_each(|problem| error!("{}", problem));
+            return Outcome::Error;
+        }
+    };
+
+    // The same checks as a search, the workers would never find a content refused by them
+    let mut problems = Problems::new(false);
+    let shape = AddressShape::sample(network, None);
+    if check_lengths(&mut problems, &args.content, &placements, &shape, None, network, 0) || check_reachable(&mut problems, &args.content, &placements, None) {
+        return Outcome::Error;
+    }
+
+    let probability = estimate::any_probability(args.content.iter().zip(&placements).flat_map(|(content, placements)| {
+        placements.iter().map(|placement| estimate::match_probability(content.len(), *placement, shape.len))
+    }));
+
+    // The workers are not connected yet, the duration is estimated at the rate of this machine
+    let (threads, cores) = match resolve_workers(&args.common) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            error!("{}", e);
+            return Outcome::Error;
+        }
+    };
+    if !check_eta(&prompt, &args.eta, args.is_interactive(), probability, None, || measure_rate(threads, ETA_CHECK_DURATION, &args.common, cores)).await {
+        return Outcome::Error;
+    }
+
+    let patterns = match PatternSet::new(&args.content, &placements, network) {
+        Ok(patterns) => patterns,
+        Err(e) => {
+            error!("{}", e);
+            return Outcome::Error;
+        }
+    };
+
+    // The lock is kept until the end of the search
+    let Ok((_lock, output)) = open_output(args.common.output.as_deref()) else {
+        return Outcome::Error;
+    };
+
+    let derivation = resolve_master(args.derive_from, args.new_master).map(|master| {
+        info!("Deriving the keys from the master seed, from index {} in ranges of {} indexes per worker", args.start_offset, args.range_size);
+        Derivation { master, start: args.start_offset, end: None }
+    });
+
+    let listener = match TcpListener::bind(&args.bind).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Couldn't listen on {}: {}", args.bind, e);
+            return Outcome::Error;
+        }
+    };
+
+    let count = args.count.map(|count| count as usize);
+    let required = count.unwrap_or(1);
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let coordinator = Arc::new(Coordinator::new(network, patterns, placements.clone(), derivation, args.range_size, sender));
+    info!("Searching for {} address with content: {} at placement '{}'", network, format_contents(&args.content), format_content_placements(&args.placement, &placements));
+    info!("Waiting for the workers on {}, the keys are sealed to the public key {}", args.bind, coordinator.public_key());
+
+    let reporter = Reporter {
+        prompt: prompt.clone(),
+        colors: !args.common.disable_log_color,
+        network,
+        languages: vec![(args.language, language::languages()[args.language].name)],
+        verify: args.verify,
+        insecure: None,
+        output,
+        qr: false,
+        qr_dir: None,
+        desired: None,
+        mode: None,
+        count,
+        found: Arc::new(Notify::new()),
+        max_logged_rate: COORDINATOR_MAX_LOGGED_RATE,
+        api: None,
+        verbose: false,
+        shard: None,
+        fields: DEFAULT_OUTPUT_FIELDS.to_vec(),
+        compact: false,
+        hook: None,
+    };
+
+    let command_manager = CommandManager::new(prompt.clone());
+    if let Err(e) = commands::register_coordinator_commands(&command_manager, Arc::clone(&coordinator)) {
+        error!("Couldn't register commands: {}", e);
+        return Outcome::Error;
+    }
+
+    let interactive = args.is_interactive();
+    let found = Arc::clone(&reporter.found);
+    let reporter = tokio::spawn(reporter.run(receiver));
+    let server = tokio::spawn(Arc::clone(&coordinator).listen(listener));
+
+    tokio::select! {
+        res = run_coordinator_prompt(prompt, &command_manager, probability, Arc::clone(&coordinator)), if interactive => {
+            if let Err(e) = res {
+                error!("Error while running prompt: {}", e);
+            }
+        },
+        _ = log_coordinator_status(probability, Arc::clone(&coordinator)), if !interactive => {},
+        _ = tokio::signal::ctrl_c() => info!("Received Ctrl-C, stopping"),
+        _ = found.notified() => info!("Found the {} requested addresses", required),
+    }
+
+    info!("Stopping the workers...");
+    server.abort();
+    coordinator.stop().await;
+    coordinator.close();
+
+    let total = TOTAL_ATTEMPTS.load(Ordering::Relaxed);
+    info!("The workers tried {} keys ({})", total, stats::format_count(total));
+
+    match reporter.await {
+        Ok(stats) if stats.count() >= required => Outcome::Success,
+        Ok(stats) => {
+            info!("Found {} of the {} requested addresses", stats.count(), required);
+            Outcome::NotFound
+        },
+        Err(e) => {
+            error!("Reporter task failed: {}", e);
+            Outcome::Error
+        }
+    }
+}
+
+fn list_languages() {
+    info!("{:<6} {:<24} {:<16} {}", "Index", "Name", "Native name", "Sample words");
+    for language in language::languages() {
+        info!("{:<6} {:<24} {:<16} {}", language.index, language.name, language.native_name, language.samples.join(", "));
+    }
+}
+
+// Generate keys on all threads during the requested duration
+// Returns the average rate in keys per second
+fn measure_rate(threads: usize, duration: Duration, common: &CommonArgs, cores: Option<Vec<usize>>) -> f64 {
+    let stop = Arc::new(AtomicBool::new(false));
+    let start = Instant::now();
+    let handles: Vec<_> = (0..threads).map(|i| {
+            let stop = Arc::clone(&stop);
+            let (fast_rng, batch_size) = (common.fast_rng, common.batch_size as usize);
+            let core = cores.as_ref().map(|cores| cores[i]);
+            thread::spawn(move || {
+                if let Some(core) = core {
+                    searcher::pin_worker(i, core);
+                }
+                bench_for(&stop, fast_rng, batch_size)
+            })
+        })
+        .collect();
+
+    thread::sleep(duration);
+    stop.store(true, Ordering::Relaxed);
+
+    let attempts: usize = handles.into_iter()
+        .map(|handle| handle.join().unwrap_or(0))
+        .sum();
+
+    attempts as f64 / start.elapsed().as_secs_f64()
+}
+
+// Same work as `search_for` without any matching
+fn bench_for(stop: &AtomicBool, fast_rng: bool, batch_size: usize) -> usize {
+    let mut generator = KeyGenerator::new(fast_rng, batch_size);
+    let mut encoder = AddressEncoder::new(Network::Mainnet);
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut attempts = 0;
+    while !stop.load(Ordering::Relaxed) {
+        generator.generate_batch(&mut batch);
+        for keypair in batch.drain(..) {
+            black_box(encoder.encode(&keypair, None));
+        }
+
+        attempts += batch_size;
+    }
+
+    attempts
+}
+
+// Save the derivation offset regularly so the search can be resumed after a crash
+async fn save_offset_periodically(progress: Arc<Progress>, path: PathBuf) {
+    loop {
+        tokio::time::sleep(OFFSET_SAVE_INTERVAL).await;
+        if let Err(e) = derivation::save_offset(&path, progress.offset()) {
+            warn!("{}", e);
+        }
+    }
+}
+
+// Resolves once the timeout in seconds is elapsed, never without timeout
+async fn wait_timeout(timeout: Option<u64>) {
+    match timeout {
+        Some(seconds) => tokio::time::sleep(Duration::from_secs(seconds)).await,
+        None => future::pending().await,
+    }
+}
+
+// Resolves once the workers generated the maximum of keys, never without maximum
+async fn wait_attempts(max_attempts: Option<u64>) {
+    let Some(max) = max_attempts else {
+        return future::pending().await;
+    };
+
+    while TOTAL_ATTEMPTS.load(Ordering::Relaxed) < max {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+// Resolves once the record reaches the target score
+async fn wait_score(record: Option<Arc<RunRecord>>, target: Option<u64>) {
+    let (Some(record), Some(target)) = (record, target) else {
+        return future::pending().await;
+    };
+
+    while (record.len() as u64) < target {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+// Resolves once all the workers exited by themselves
+async fn wait_finished(searcher: &Searcher) {
+    while !searcher.is_finished() {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+// `probability` is the chance of a single key to match, used for the ETA
+// Log the status periodically, replacing the prompt status line when it's not interactive
+async fn log_status(probability: f64, best: Arc<BestMatches>, record: Option<Arc<RunRecord>>) {
+    let mut interval = tokio::time::interval(STATUS_LOG_INTERVAL);
+    // The first tick completes immediately
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        let (hashrate, average, peak) = stats::sample_rate().await;
+        let probability = record.as_ref().map_or(probability, |record| record.next_probability());
+        let best = match &record {
+            Some(record) => format!(" | best run: {}", record.len()),
+            None => best.snapshot()
+                .first()
+                .map(|(content, len, _)| format!(" | best: {} {}/{}", content, len, content.len()))
+                .unwrap_or_default(),
+        };
+
+        info!(
+            "Rate: {} (avg: {}, peak: {}) | {} tried | ETA: {}{}",
+            stats::format_rate(hashrate),
+            stats::format_rate(average),
+            stats::format_rate(peak),
+            stats::format_count(TOTAL_ATTEMPTS.load(Ordering::Relaxed)),
+            estimate::format_eta(probability, average),
+            best
+        );
+    }
+}
+
+// Log an address generated by the workers at each tick, see `-v`
+// With `-vv`, also the parts of the address compared for each placement
+async fn log_samples(sampler: Arc<Sampler>, verbose: u8, network: Network, suffix_end: Option<usize>) {
+    let mut interval = tokio::time::interval(STATUS_LOG_INTERVAL);
+    loop {
+        interval.tick().await;
+        let Some(address) = sampler.take() else {
+            continue;
+        };
+
+        info!("Sample address: {}", address);
+        if verbose > 1 {
+            let start = matcher::data_start(network);
+            info!("Prefix compared to the start of {}", &address[start..]);
+            match suffix_end {
+                Some(end) => info!("Suffix compared to the end of {}, before the checksum {}", &address[start..end], &address[end..]),
+                None => info!("Suffix compared to the end of {}, checksum included", &address[start..]),
+            }
+        }
+    }
+}
+
+// Connected workers and their combined rate, as reported by them
+fn coordinator_status(coordinator: &Coordinator, probability: f64, rate: String) -> String {
+    format!(
+        "{} workers | {} | {} tried | ETA: {}",
+        coordinator.peers().len(),
+        rate,
+        stats::format_count(TOTAL_ATTEMPTS.load(Ordering::Relaxed)),
+        estimate::format_eta(probability, coordinator.rate())
+    )
+}
+
+// Log the status of the workers periodically when the prompt is not interactive
+async fn log_coordinator_status(probability: f64, coordinator: Arc<Coordinator>) {
+    let mut interval = tokio::time::interval(STATUS_LOG_INTERVAL);
+    // The first tick completes immediately
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        info!("{}", coordinator_status(&coordinator, probability, stats::format_rate(coordinator.rate())));
+    }
+}
+
+async fn run_coordinator_prompt(prompt: ShareablePrompt, command_manager: &CommandManager, probability: f64, coordinator: Arc<Coordinator>) -> Result<(), PromptError> {
+    let closure = |_: &_, _: _| async {
+        let rate = prompt.colorize_string(Color::Green, &format!("{}", stats::format_rate(coordinator.rate())));
+        Ok(
+            format!(
+                "{} | {} {} ",
+                prompt.colorize_str(Color::Blue, "XELIS Vanity"),
+                coordinator_status(&coordinator, probability, rate),
+                prompt.colorize_str(Color::BrightBlack, ">>")
+            )
+        )
+    };
+
+    prompt.start(Duration::from_secs(1), Box::new(async_handler!(closure)), Some(command_manager)).await
+}
+
+// With a run record, the ETA is the one of the next record
+// With `spinner`, a frame of the spinner is shown at each refresh so a stalled process is visible
+async fn run_prompt(prompt: ShareablePrompt, command_manager: &CommandManager, probability: f64, record: Option<Arc<RunRecord>>, spinner: bool) -> Result<(), PromptError> {
+    let frame = AtomicUsize::new(0);
+    let closure = |_: &_, _: _| async {
+        let spinner = if spinner {
+            format!(" {}", SPINNER_FRAMES[frame.fetch_add(1, Ordering::Relaxed) % SPINNER_FRAMES.len()])
+        } else {
+            String::new()
+        };
+        let rate = {
+            let (hashrate, average, peak) = stats::sample_rate().await;
+            let probability = record.as_ref().map_or(probability, |record| record.next_probability());
+            format!(
+                "{} {} (avg: {}, peak: {}) | {} tried | ETA: {}{}",
+                prompt.colorize_string(Color::Green, &format!("{}", stats::format_rate(hashrate))),
+                RATE_TREND.lock().await.sparkline(PROMPT_SPARKLINE_WIDTH),
+                stats::format_rate(average),
+                stats::format_rate(peak),
+                stats::format_count(TOTAL_ATTEMPTS.load(Ordering::Relaxed)),
+                estimate::format_eta(probability, average),
+                record.as_ref().map(|record| format!(" | best run: {}", record.len())).unwrap_or_default()
+            )
+        };
+
+        Ok(
+            format!(
+                "{}{} | {} {} ",
+                prompt.colorize_str(Color::Blue, "XELIS Vanity"),
+                spinner,
+                rate,
+                prompt.colorize_str(Color::BrightBlack, ">>")
+            )
+        )
+    };
+
+    prompt.start(Duration::from_secs(1), Box::new(async_handler!(closure)), Some(command_manager)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_problems_stop_at_the_first_one() {
+        let mut problems = Problems::new(false);
+        assert!(problems.add("first"));
+        assert!(problems.finish().is_none());
+        assert!(matches!(problems.fail("second"), Outcome::Error));
+    }
+
+    #[test]
+    fn test_problems_are_collected() {
+        let mut problems = Problems::new(true);
+        assert!(!problems.add("first"));
+        assert!(!problems.add(format!("{}", "second")));
+        assert_eq!(problems.found, ["first", "second"]);
+        assert!(matches!(problems.finish(), Some(Outcome::Error)));
+        assert!(problems.finish().is_none());
+    }
+
+    #[test]
+    fn test_parse_placements_reports_every_content() {
+        let mut contents = vec!["cafe".to_string(), "big".to_string(), "nowhere:dog".to_string(), "suffix:d0g".to_string(), "".to_string()];
+        let problems = parse_placements(&mut contents, &[Placement::Prefix], Network::Mainnet).unwrap_err();
+        assert_eq!(problems.len(), 3);
+        assert!(problems[0].starts_with("Invalid character in prefix"));
+        assert!(problems[1].contains("'nowhere:dog'"));
+        assert_eq!(problems[2], "Prefix can't be empty");
+
+        let mut contents = vec!["cafe".to_string(), "suffix:d0g".to_string()];
+        let placements = parse_placements(&mut contents, &[Placement::Prefix, Placement::Anywhere], Network::Mainnet).unwrap();
+        assert_eq!(placements, [vec![Placement::Prefix, Placement::Anywhere], vec![Placement::Suffix]]);
+        assert_eq!(contents, ["cafe", "d0g"]);
+    }
+
+    #[test]
+    fn test_astronomical_threshold_is_checked_first() {
+        let (hour, year) = (3600f64, YEAR);
+        assert_eq!(classify_eta(hour, 24f64 * hour, 10f64 * year), Eta::Short);
+        assert_eq!(classify_eta(48f64 * hour, 24f64 * hour, 10f64 * year), Eta::Long);
+        assert_eq!(classify_eta(20f64 * year, 24f64 * hour, 10f64 * year), Eta::Astronomical);
+        // An ETA threshold above the astronomical one doesn't skip the confirmation
+        assert_eq!(classify_eta(20f64 * year, 100f64 * year, 10f64 * year), Eta::Astronomical);
+    }
+
+    #[test]
+    fn test_coordinator_checks_refuse_impossible_contents() {
+        let shape = AddressShape::sample(Network::Mainnet, None);
+        let mut problems = Problems::new(true);
+        let contents = ["cafe".to_string(), "q".repeat(shape.len)];
+        assert!(!check_lengths(&mut problems, &contents, &[vec![Placement::Prefix], vec![Placement::Prefix]], &shape, None, Network::Mainnet, 0));
+        assert_eq!(problems.found.len(), 1);
+        assert!(problems.found[0].starts_with(&format!("'{}'", contents[1])));
+
+        // 'y' is never the second data character
+        let mut problems = Problems::new(true);
+        check_reachable(&mut problems, &["ay".to_string(), "cafe".to_string()], &[vec![Placement::Prefix], vec![Placement::Prefix]], None);
+        assert!(problems.found.is_empty());
+        check_reachable(&mut problems, &["ay".to_string()], &[vec![Placement::Prefix]], None);
+        assert_eq!(problems.found, ["No content can ever be found, the search would never end"]);
+        assert!(check_reachable(&mut Problems::new(false), &["ay".to_string()], &[vec![Placement::Prefix]], None));
+    }
+}
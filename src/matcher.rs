@@ -0,0 +1,763 @@
+use std::ops::Range;
+use aho_corasick::AhoCorasick;
+use xelis_common::crypto::bech32::{CHARSET, SEPARATOR};
+use crate::{
+    config::{Network, Placement},
+    keys::AddressShape
+};
+
+// Number of characters of the bech32 checksum at the end of an address
+pub const CHECKSUM_LEN: usize = 6;
+
+// Number of data characters fully determined by the public key
+// The 32 bytes key is encoded first, followed by the address type
+pub const KEY_DATA_LEN: usize = 32 * 8 / 5;
+
+// Offset of the first data character in an address, right after the separator
+pub fn data_start(network: Network) -> usize {
+    AddressShape::of(network).data_start()
+}
+
+// Offset of the first character after the public key in an address
+// For a normal address, only the address type (2 characters) and the checksum (6, not 8) are after it
+pub fn key_end(network: Network) -> usize {
+    AddressShape::of(network).key_end()
+}
+
+// Part of the address to search in
+// With `--before-checksum`, a suffix is searched at the end of the public key characters
+pub fn match_area(address: &str, suffix_end: Option<usize>) -> &str {
+    match suffix_end {
+        Some(end) => &address[..end],
+        None => address,
+    }
+}
+
+// Separator of the placement in a content, like `prefix:dog`
+pub const PLACEMENT_SEPARATOR: char = ':';
+
+// Split a content from its placement (`suffix:dog`), the placement is None if not given
+// The separator isn't in the charset, so it can't be part of a content
+// A content written after the address prefix (`xel:dog`) or only its separator (`:dog`)
+// is a prefix starting at the first data character, the address prefix is never added twice
+pub fn parse_content_placement(value: &str, network: Network) -> Result<(Option<Placement>, &str), String> {
+    // Checked with the address separator, even if it's the same character as the placement one
+    let address_prefix = |network: Network| value.strip_prefix(network.prefix()).and_then(|rest| rest.strip_prefix(SEPARATOR));
+    if let Some(content) = address_prefix(network) {
+        return Ok((Some(Placement::Prefix), content));
+    }
+
+    if address_prefix(network.other()).is_some() {
+        return Err(format!("'{}' starts with the {} address prefix, use --network {}", value, network.other(), network.other()));
+    }
+
+    if let Some(content) = value.strip_prefix(SEPARATOR) {
+        return Ok((Some(Placement::Prefix), content));
+    }
+
+    let Some((placement, content)) = value.split_once(PLACEMENT_SEPARATOR) else {
+        return Ok((None, value));
+    };
+
+    placement.parse()
+        .map(|placement| (Some(placement), content))
+        .map_err(|e: String| format!("{} in '{}'", e, value))
+}
+
+// Check that a content can be found in an address: not empty and only made of the charset
+// Every content goes through it, whether it's from `--content`, a dictionary or `--desired`
+pub fn check_content(content: &str, network: Network) -> Result<(), String> {
+    // Check if the content is empty
+    if content.is_empty() {
+        return Err("Prefix can't be empty".to_string());
+    }
+
+    // Check if the content contains invalid characters
+    for c in content.chars() {
+        // Left by a content like `xel::dog` or `prefix:xel:dog`
+        if c == SEPARATOR {
+            return Err(format!("Invalid character in prefix: {} (write the content alone like 'dog', with its placement like 'suffix:dog' or after the address prefix like '{}{}dog')", c, network.prefix(), SEPARATOR));
+        }
+
+        if !CHARSET.chars().any(|v| v == c) {
+            return Err(format!("Invalid character in prefix: {}", c));
+        }
+    }
+
+    Ok(())
+}
+
+// Check if a content is written after the address prefix (`xel:dog`) or its separator (`:dog`)
+// It's always a prefix, whatever `--placement` is
+pub fn has_address_prefix(value: &str, network: Network) -> bool {
+    value.starts_with(SEPARATOR) || value.strip_prefix(network.prefix()).is_some_and(|rest| rest.starts_with(SEPARATOR))
+}
+
+// Build the string to compare against the full address
+// A prefix is searched right after the network prefix and separator
+pub fn build_pattern(content: &str, placement: Placement, network: Network) -> String {
+    match placement {
+        Placement::Prefix => format!("{}{}{}", network.prefix(), SEPARATOR, content),
+        _ => content.to_string(),
+    }
+}
+
+// Offset of the canonical form of an address: its data part, after the separator
+// The separator is never in the network prefix nor in the data, so no network is needed
+pub fn canonical_start(address: &str) -> usize {
+    address.find(SEPARATOR).map_or(0, |i| i + SEPARATOR.len_utf8())
+}
+
+// Check if the address contains the pattern at the requested placement
+// With `raw`, a content anywhere can also be found in the network prefix, see `--raw-match`
+pub fn is_matching(address: &str, pattern: &str, placement: Placement, raw: bool) -> bool {
+    find_match(address, pattern, placement, raw).is_some()
+}
+
+// Position of the pattern in the address if it's at the requested placement
+pub fn find_match(address: &str, pattern: &str, placement: Placement, raw: bool) -> Option<Range<usize>> {
+    let start = match placement {
+        Placement::Prefix => address.starts_with(pattern).then_some(0)?,
+        Placement::Suffix => address.ends_with(pattern).then(|| address.len() - pattern.len())?,
+        Placement::Anywhere if raw => address.find(pattern)?,
+        Placement::Anywhere => {
+            let offset = canonical_start(address);
+            offset + address[offset..].find(pattern)?
+        },
+    };
+
+    Some(start..start + pattern.len())
+}
+
+// Levenshtein distance between `a` and `b`, None as soon as it's above `max`
+// It runs for each key with `--fuzzy`: the rows are only as long as `b`, a content,
+// and the computation stops once a whole row is above `max` as the distance can only grow
+pub fn bounded_distance(a: &[u8], b: &[u8], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0usize; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        let mut row_min = i;
+        for j in 1..=b.len() {
+            let substitution = previous[j - 1] + (a[i - 1] != b[j - 1]) as usize;
+            current[j] = substitution.min(previous[j] + 1).min(current[j - 1] + 1);
+            row_min = row_min.min(current[j]);
+        }
+
+        if row_min > max {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|distance| *distance <= max)
+}
+
+// A content found in an address
+pub struct PatternMatch {
+    // Index of the content
+    pub index: usize,
+    pub placement: Placement,
+    // Position of the pattern in the address
+    // For a near miss, only the characters compared with the content
+    pub range: Range<usize>,
+    // Edits between the content and the address, 0 for an exact match
+    pub distance: usize,
+}
+
+// A content to search at a placement
+struct Pattern {
+    // Index of the content
+    index: usize,
+    placement: Placement,
+    // String compared against the address, see `build_pattern`
+    value: String,
+}
+
+// Contents searched in the addresses at each placement, built once and shared by all the workers
+pub struct PatternSet {
+    // Contents requested by the user
+    contents: Vec<String>,
+    // Patterns compared one by one, for the prefix and suffix placements
+    patterns: Vec<Pattern>,
+    // Scan all the anywhere patterns at once, with the index of their content
+    automaton: Option<(AhoCorasick, Vec<usize>)>,
+    // Search the contents anywhere in the whole address instead of its data part, see `--raw-match`
+    raw: bool,
+}
+
+impl PatternSet {
+    // `placements` are the placements of each content, in the same order
+    pub fn new(contents: &[String], placements: &[Vec<Placement>], network: Network) -> Result<Self, String> {
+        let mut patterns = Vec::new();
+        let mut anywhere = Vec::new();
+        for (index, (content, placements)) in contents.iter().zip(placements).enumerate() {
+            for placement in placements {
+                let value = build_pattern(content, *placement, network);
+                match placement {
+                    Placement::Anywhere => anywhere.push((index, value)),
+                    _ => patterns.push(Pattern { index, placement: *placement, value }),
+                }
+            }
+        }
+
+        let automaton = if anywhere.is_empty() {
+            None
+        } else {
+            let automaton = AhoCorasick::new(anywhere.iter().map(|(_, value)| value))
+                .map_err(|e| format!("couldn't build the patterns automaton: {}", e))?;
+            Some((automaton, anywhere.into_iter().map(|(index, _)| index).collect()))
+        };
+
+        Ok(Self {
+            contents: contents.to_vec(),
+            patterns,
+            automaton,
+            raw: false,
+        })
+    }
+
+    // Also find the contents anywhere in the network prefix and the separator,
+    // the behavior before the canonical form
+    pub fn set_raw(&mut self, raw: bool) {
+        self.raw = raw;
+    }
+
+    // Offset of the area where the contents anywhere are searched
+    fn anywhere_start(&self, address: &str) -> usize {
+        if self.raw {
+            0
+        } else {
+            canonical_start(address)
+        }
+    }
+
+    pub fn contents(&self) -> &[String] {
+        &self.contents
+    }
+
+    // Index of the content with the most characters matched at its placement and their number
+    // Only available for prefixes and suffixes, the first compared character is checked before counting
+    pub fn partial(&self, address: &str) -> Option<(usize, usize)> {
+        let address = address.as_bytes();
+        self.patterns.iter()
+            .filter_map(|pattern| {
+                let value = pattern.value.as_bytes();
+                let len = match pattern.placement {
+                    Placement::Prefix => {
+                        // The pattern starts with the network prefix
+                        let start = value.len() - self.contents[pattern.index].len();
+                        if address.get(start) != value.get(start) {
+                            return None;
+                        }
+                        address[start..].iter().zip(&value[start..]).take_while(|(a, b)| a == b).count()
+                    },
+                    Placement::Suffix => {
+                        if address.last() != value.last() {
+                            return None;
+                        }
+                        address.iter().rev().zip(value.iter().rev()).take_while(|(a, b)| a == b).count()
+                    },
+                    Placement::Anywhere => return None,
+                };
+
+                Some((pattern.index, len))
+            })
+            .max_by_key(|(_, len)| *len)
+    }
+
+    // First content found in the address, the prefixes and suffixes are checked first
+    pub fn find(&self, address: &str) -> Option<PatternMatch> {
+        self.find_where(address, |_| true)
+    }
+
+    // First content accepted by `active` found in the address
+    // Used to skip the contents that don't need to be searched anymore
+    pub fn find_where(&self, address: &str, active: impl Fn(usize) -> bool) -> Option<PatternMatch> {
+        let found = self.patterns.iter()
+            .filter(|pattern| active(pattern.index))
+            .find_map(|pattern| {
+                find_match(address, &pattern.value, pattern.placement, self.raw)
+                    .map(|range| PatternMatch { index: pattern.index, placement: pattern.placement, range, distance: 0 })
+            });
+
+        if found.is_some() {
+            return found;
+        }
+
+        // The leftmost match may be an inactive content, look at the overlapping ones too
+        let (automaton, indexes) = self.automaton.as_ref()?;
+        let start = self.anywhere_start(address);
+        automaton.find_overlapping_iter(&address[start..])
+            .find(|found| active(indexes[found.pattern().as_usize()]))
+            .map(|found| PatternMatch {
+                index: indexes[found.pattern().as_usize()],
+                placement: Placement::Anywhere,
+                range: start + found.start()..start + found.end(),
+                distance: 0,
+            })
+    }
+
+    // Closest content accepted by `active` within `max` edits of the address, see `--fuzzy`
+    // A prefix or a suffix is compared with the slice of the same length at its placement,
+    // a content anywhere with the best window of the address, whose length is up to `max` characters away
+    // Only called once `find_where` failed, so the exact matches are never reported as near misses
+    pub fn find_fuzzy(&self, address: &str, max: usize, active: impl Fn(usize) -> bool) -> Option<PatternMatch> {
+        let bytes = address.as_bytes();
+        let mut best: Option<PatternMatch> = None;
+        for pattern in self.patterns.iter().filter(|pattern| active(pattern.index)) {
+            let len = self.contents[pattern.index].len();
+            let range = match pattern.placement {
+                // Skip the network prefix and the separator
+                Placement::Prefix => {
+                    let start = pattern.value.len() - len;
+                    start..start + len
+                },
+                _ => bytes.len().saturating_sub(len)..bytes.len(),
+            };
+
+            let Some(window) = bytes.get(range.clone()) else {
+                continue;
+            };
+
+            // Only a closer content replaces the best one
+            let limit = best.as_ref().map_or(max, |best| best.distance - 1);
+            if let Some(distance) = bounded_distance(window, self.contents[pattern.index].as_bytes(), limit) {
+                best = Some(PatternMatch { index: pattern.index, placement: pattern.placement, range, distance });
+            }
+
+            if best.as_ref().is_some_and(|best| best.distance <= 1) {
+                return best;
+            }
+        }
+
+        let indexes = self.automaton.as_ref().map(|(_, indexes)| indexes.as_slice()).unwrap_or_default();
+        let first = self.anywhere_start(address);
+        for index in indexes.iter().copied().filter(|index| active(*index)) {
+            let content = self.contents[index].as_bytes();
+            for window_len in content.len().saturating_sub(max).max(1)..=content.len() + max {
+                for start in first..=bytes.len().saturating_sub(window_len) {
+                    let limit = best.as_ref().map_or(max, |best| best.distance - 1);
+                    let range = start..start + window_len;
+                    if let Some(distance) = bytes.get(range.clone()).and_then(|window| bounded_distance(window, content, limit)) {
+                        best = Some(PatternMatch { index, placement: Placement::Anywhere, range, distance });
+                    }
+
+                    if best.as_ref().is_some_and(|best| best.distance <= 1) {
+                        return best;
+                    }
+                }
+            }
+        }
+
+        best
+    }
+}
+
+// Characters the whole data part of an address must be made of, see `--only`
+pub struct Allowlist {
+    table: [bool; 128],
+    len: usize,
+}
+
+impl Allowlist {
+    pub fn new(chars: &str) -> Result<Self, String> {
+        let mut table = [false; 128];
+        let mut len = 0;
+        for c in chars.chars() {
+            if !CHARSET.contains(c) {
+                return Err(format!("Invalid character '{}' in --only, only '{}' can appear in addresses", c, CHARSET));
+            }
+
+            if !table[c as usize] {
+                table[c as usize] = true;
+                len += 1;
+            }
+        }
+
+        if len == 0 {
+            return Err("--only can't be empty".to_string());
+        }
+
+        // Every address would be accepted
+        if len == CHARSET.len() {
+            return Err("--only can't contain the whole charset".to_string());
+        }
+
+        Ok(Self { table, len })
+    }
+
+    // Characters of the content not in the list, it could never be found
+    pub fn missing(&self, content: &str) -> Option<char> {
+        content.chars().find(|c| !self.table.get(*c as usize).copied().unwrap_or(false))
+    }
+
+    // Scan the characters of the public key, most addresses are rejected by their first characters
+    // The address type and the checksum are not checked: the type is fixed, so a list without
+    // its characters could never match, and the checksum can't be chosen
+    pub fn is_matching(&self, address: &str) -> bool {
+        let start = canonical_start(address);
+        let end = (start + KEY_DATA_LEN).min(address.len());
+        address.as_bytes()[start..end].iter().all(|c| self.table.get(*c as usize).copied().unwrap_or(false))
+    }
+
+    // Probability that the `free` key characters not set by the content are all in the list
+    pub fn probability(&self, free: usize) -> f64 {
+        (self.len as f64 / CHARSET.len() as f64).powi(free as i32)
+    }
+}
+
+// Cheap check of the content directly against the 5-bit groups of a public key
+// Each data character of an address is one group, so the content can be compared
+// before encoding the address and computing its checksum
+pub struct KeyFilter {
+    // Index of the content, its placement, the index of the first group to compare and the expected groups
+    patterns: Vec<(usize, Placement, usize, Vec<u8>)>,
+}
+
+impl KeyFilter {
+    // Returns None when a content isn't fully determined by the public key at one of the placements:
+    // anywhere in the address, or a suffix including the checksum
+    pub fn new(contents: &[String], placements: &[Vec<Placement>], suffix_end: Option<usize>) -> Option<Self> {
+        let mut patterns = Vec::new();
+        for (index, (content, placements)) in contents.iter().zip(placements).enumerate() {
+            for placement in placements {
+                let groups = content.chars()
+                    .map(|c| CHARSET.find(c).map(|v| v as u8))
+                    .collect::<Option<Vec<_>>>()?;
+
+                if groups.len() > KEY_DATA_LEN {
+                    return None;
+                }
+
+                let start = match (placement, suffix_end) {
+                    (Placement::Prefix, _) => 0,
+                    (Placement::Suffix, Some(_)) => KEY_DATA_LEN - groups.len(),
+                    _ => return None,
+                };
+
+                patterns.push((index, *placement, start, groups));
+            }
+        }
+
+        Some(Self { patterns })
+    }
+
+    // Index of the content with the most groups matched and their number
+    // The first compared group is checked before counting
+    pub fn partial(&self, key: &[u8]) -> Option<(usize, usize)> {
+        self.patterns.iter()
+            .filter_map(|(index, placement, start, groups)| {
+                let len = match placement {
+                    Placement::Prefix => {
+                        if group_at(key, 0) != groups[0] {
+                            return None;
+                        }
+                        groups.iter().enumerate().take_while(|(j, group)| group_at(key, *j) == **group).count()
+                    },
+                    _ => {
+                        // Suffix ending at the last group of the key
+                        let end = start + groups.len();
+                        if group_at(key, end - 1) != groups[groups.len() - 1] {
+                            return None;
+                        }
+                        groups.iter().rev().enumerate().take_while(|(j, group)| group_at(key, end - 1 - j) == **group).count()
+                    }
+                };
+
+                Some((*index, len))
+            })
+            .max_by_key(|(_, len)| *len)
+    }
+
+    // Check the groups of the compressed public key against each content
+    // A key passing it still needs to be checked against the full address
+    pub fn is_matching(&self, key: &[u8]) -> bool {
+        self.patterns.iter().any(|(_, _, start, groups)| {
+            groups.iter()
+                .enumerate()
+                .all(|(i, group)| group_at(key, start + i) == *group)
+        })
+    }
+}
+
+// Bits of a public key that are always 0, numbered from the first bit of the first group
+// A compressed Ristretto point is the canonical little-endian encoding of a non-negative field element:
+// it's even (last bit of the first byte) and below 2^255 (first bit of the last byte)
+const ZERO_KEY_BITS: [usize; 2] = [7, 31 * 8];
+
+// Check if the group at `index` of a public key can have the value
+fn is_reachable(index: usize, value: u8) -> bool {
+    ZERO_KEY_BITS.iter()
+        .filter(|bit| *bit / 5 == index)
+        .all(|bit| value & (1 << (4 - bit % 5)) == 0)
+}
+
+// First character of the content that no public key has at the placement, with its index in the data part
+// Only the characters of the public key are checked: a prefix, or a suffix before the checksum
+// The second data character is never one of 'y98x', 'vdw0', '54kh' nor 'ua7l' for example
+pub fn unreachable(content: &str, placement: Placement, suffix_end: Option<usize>) -> Option<(usize, char)> {
+    let start = match (placement, suffix_end) {
+        (Placement::Prefix, _) => 0,
+        (Placement::Suffix, Some(_)) => KEY_DATA_LEN.checked_sub(content.len())?,
+        _ => return None,
+    };
+
+    content.chars()
+        .enumerate()
+        .map(|(i, c)| (start + i, c))
+        .take_while(|(index, _)| *index < KEY_DATA_LEN)
+        .find(|(index, c)| CHARSET.find(*c).is_some_and(|value| !is_reachable(*index, value as u8)))
+}
+
+// 5-bit group at `index` in the bytes, as encoded by bech32
+fn group_at(bytes: &[u8], index: usize) -> u8 {
+    let bit = index * 5;
+    let byte = bit / 8;
+    // A group overlaps at most two bytes
+    let window = (bytes[byte] as u16) << 8 | bytes.get(byte + 1).copied().unwrap_or(0) as u16;
+    ((window >> (11 - bit % 8)) & 31) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::crypto::KeyPair;
+    use crate::keys;
+    use super::*;
+
+    // Real key and its encoded address
+    fn encoded(seed: u8, network: Network) -> (KeyPair, String) {
+        let keypair = keys::keypair_from_entropy([seed; 32]);
+        let address = keys::derive_address(&keypair, network, None);
+        (keypair, address)
+    }
+
+    #[test]
+    fn test_check_content() {
+        assert!(check_content("cafe", Network::Mainnet).is_ok());
+        assert!(check_content("qpzry9x8gf2tvdw0s3jn54khce6mua7l", Network::Testnet).is_ok());
+        assert_eq!(check_content("", Network::Mainnet), Err("Prefix can't be empty".to_string()));
+        for content in ["big", "Cafe", "d0g1", "ca fe"] {
+            assert!(check_content(content, Network::Mainnet).unwrap_err().starts_with("Invalid character in prefix"), "{}", content);
+        }
+
+        // A separator left by `xel::dog` explains how to write it
+        assert!(check_content("xel:dog", Network::Mainnet).unwrap_err().contains("'xel:dog'"));
+    }
+
+    #[test]
+    fn test_key_filter_agrees_with_the_address() {
+        let network = Network::Mainnet;
+        let samples = (1..40).map(|seed| encoded(seed, network)).collect::<Vec<_>>();
+        for (keypair, address) in &samples {
+            let key = keypair.get_public_key().compress();
+            for (_, other) in &samples {
+                let area = match_area(other, Some(key_end(network)));
+                for len in 1..=3 {
+                    let contents = [
+                        (other[data_start(network)..data_start(network) + len].to_string(), Placement::Prefix),
+                        (area[area.len() - len..].to_string(), Placement::Suffix),
+                    ];
+                    for (content, placement) in contents {
+                        let contents = [content];
+                        let placements = [vec![placement]];
+                        let filter = KeyFilter::new(&contents, &placements, Some(key_end(network))).unwrap();
+                        let patterns = PatternSet::new(&contents, &placements, network).unwrap();
+                        let found = patterns.find(match_area(address, Some(key_end(network)))).is_some();
+                        assert_eq!(filter.is_matching(key.as_bytes()), found, "{} {:?} in {}", contents[0], placement, address);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_key_end_is_before_the_type_and_the_checksum() {
+        for network in [Network::Mainnet, Network::Testnet] {
+            for seed in 1..8 {
+                let (_, address) = encoded(seed, network);
+                assert_eq!(&address[..data_start(network)], format!("{}{}", network.prefix(), SEPARATOR));
+                // 6 checksum characters, the 2 before them encode the address type
+                assert_eq!(address.len() - key_end(network), 2 + CHECKSUM_LEN);
+                assert!(address[key_end(network)..].starts_with(|c| c == 'q' || c == 's'));
+                assert_eq!(address.as_bytes()[key_end(network) + 1], b'q');
+            }
+        }
+    }
+
+    #[test]
+    fn test_suffix_before_the_checksum_is_in_the_key() {
+        for network in [Network::Mainnet, Network::Testnet] {
+            for seed in 1..8 {
+                let (keypair, address) = encoded(seed, network);
+                let key = keypair.get_public_key().compress();
+                let area = match_area(&address, Some(key_end(network)));
+                assert_eq!(area, &address[..address.len() - 2 - CHECKSUM_LEN]);
+
+                let suffix = area[area.len() - 4..].to_string();
+                assert!(KeyFilter::new(&[suffix.clone()], &[vec![Placement::Suffix]], Some(key_end(network))).unwrap().is_matching(key.as_bytes()));
+                assert_eq!(unreachable(&suffix, Placement::Suffix, Some(key_end(network))), None);
+            }
+        }
+    }
+
+    // Address with the public key characters `key`, the address type and a checksum
+    fn address(key: &str) -> String {
+        format!("xel:{}qq9x8gf2", key)
+    }
+
+    #[test]
+    fn test_parse_content_placement() {
+        assert_eq!(parse_content_placement("xel:cafe", Network::Mainnet), Ok((Some(Placement::Prefix), "cafe")));
+        assert_eq!(parse_content_placement(":cafe", Network::Mainnet), Ok((Some(Placement::Prefix), "cafe")));
+        assert_eq!(parse_content_placement("cafe", Network::Mainnet), Ok((None, "cafe")));
+        assert_eq!(parse_content_placement("suffix:cafe", Network::Mainnet), Ok((Some(Placement::Suffix), "cafe")));
+        assert_eq!(parse_content_placement("Anywhere:cafe", Network::Mainnet), Ok((Some(Placement::Anywhere), "cafe")));
+        assert_eq!(parse_content_placement("xet:cafe", Network::Testnet), Ok((Some(Placement::Prefix), "cafe")));
+    }
+
+    #[test]
+    fn test_parse_content_placement_errors() {
+        assert!(parse_content_placement("xet:cafe", Network::Mainnet).unwrap_err().contains("--network testnet"));
+        assert!(parse_content_placement("xel:cafe", Network::Testnet).unwrap_err().contains("--network mainnet"));
+        assert!(parse_content_placement("middle:cafe", Network::Mainnet).unwrap_err().starts_with("unknown placement 'middle'"));
+    }
+
+    #[test]
+    fn test_address_prefix_is_never_added_twice() {
+        for value in ["xel:cafe", ":cafe", "cafe"] {
+            let (placement, content) = parse_content_placement(value, Network::Mainnet).unwrap();
+            assert_eq!(build_pattern(content, placement.unwrap_or(Placement::Prefix), Network::Mainnet), "xel:cafe");
+        }
+    }
+
+    #[test]
+    fn test_prefix_after_the_separator() {
+        let (placement, content) = parse_content_placement("xel:aa", Network::Mainnet).unwrap();
+        assert_eq!((placement, content), (Some(Placement::Prefix), "aa"));
+        assert_eq!(build_pattern(content, Placement::Prefix, Network::Mainnet), "xel:aa");
+        // The second data character is never 'a', so this prefix is refused before searching
+        assert_eq!(unreachable(content, Placement::Prefix, None), Some((1, 'a')));
+
+        // Other placements are not anchored after the separator
+        assert_eq!(build_pattern("aa", Placement::Suffix, Network::Mainnet), "aa");
+        assert_eq!(build_pattern("aa", Placement::Anywhere, Network::Mainnet), "aa");
+        assert!(is_matching("xel:aacafe", "xel:aa", Placement::Prefix, false));
+        assert!(!is_matching("xel:caafe", "xel:aa", Placement::Prefix, false));
+
+        assert!(has_address_prefix("xel:cafe", Network::Mainnet));
+        assert!(has_address_prefix(":cafe", Network::Mainnet));
+        assert!(!has_address_prefix("cafe", Network::Mainnet));
+        assert!(!has_address_prefix("suffix:cafe", Network::Mainnet));
+    }
+
+    #[test]
+    fn test_bounded_distance() {
+        assert_eq!(bounded_distance(b"cafe", b"cafe", 0), Some(0));
+        assert_eq!(bounded_distance(b"cafa", b"cafe", 1), Some(1));
+        assert_eq!(bounded_distance(b"caf", b"cafe", 1), Some(1));
+        assert_eq!(bounded_distance(b"caffe", b"cafe", 1), Some(1));
+        assert_eq!(bounded_distance(b"", b"", 0), Some(0));
+        assert_eq!(bounded_distance(b"", b"ca", 2), Some(2));
+    }
+
+    #[test]
+    fn test_bounded_distance_limit() {
+        // Exactly `max` edits is accepted, one more is not
+        assert_eq!(bounded_distance(b"qqfe", b"cafe", 2), Some(2));
+        assert_eq!(bounded_distance(b"qqfe", b"cafe", 1), None);
+        assert_eq!(bounded_distance(b"ca", b"cafe", 2), Some(2));
+        assert_eq!(bounded_distance(b"c", b"cafe", 2), None);
+        assert_eq!(bounded_distance(b"qqqq", b"cafe", 3), None);
+    }
+
+    #[test]
+    fn test_fuzzy_windows_at_the_edges() {
+        let contents = ["cafe".to_string()];
+        let patterns = PatternSet::new(&contents, &[vec![Placement::Anywhere]], Network::Mainnet).unwrap();
+        let data = |data: &str| format!("xel:{}", data);
+
+        // One character shorter, at the first data character and at the end of the address
+        let found = patterns.find_fuzzy(&data("cafqqqqq"), 1, |_| true).unwrap();
+        assert_eq!((found.range, found.distance), (4..7, 1));
+        let found = patterns.find_fuzzy(&data("qqqqqcaf"), 1, |_| true).unwrap();
+        assert_eq!((found.range, found.distance), (9..12, 1));
+
+        // One character longer, ending with the address
+        let found = patterns.find_fuzzy(&data("qqqqcaxfe"), 1, |_| true).unwrap();
+        assert_eq!((found.range.end, found.distance), (13, 1));
+
+        // The network prefix and the separator are not part of the windows
+        assert!(patterns.find_fuzzy("xel:afqqqqqq", 1, |_| true).is_none());
+        assert!(patterns.find_fuzzy(&data("qqqqqqqq"), 1, |_| true).is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_prefix_and_suffix_windows() {
+        let contents = ["cafe".to_string()];
+        let patterns = PatternSet::new(&contents, &[vec![Placement::Prefix, Placement::Suffix]], Network::Mainnet).unwrap();
+
+        let found = patterns.find_fuzzy("xel:cafqqqqq", 1, |_| true).unwrap();
+        assert_eq!((found.placement, found.range, found.distance), (Placement::Prefix, 4..8, 1));
+        let found = patterns.find_fuzzy("xel:qqqqqcaff", 1, |_| true).unwrap();
+        assert_eq!((found.placement, found.range, found.distance), (Placement::Suffix, 9..13, 1));
+        assert!(patterns.find_fuzzy("xel:qcafeqqq", 1, |_| true).is_none());
+    }
+
+    #[test]
+    fn test_is_reachable() {
+        // Bit 7 of the key is 0: the second group never has its third bit set
+        for c in "y98xvdw054khua7l".chars() {
+            assert!(!is_reachable(1, CHARSET.find(c).unwrap() as u8), "{}", c);
+        }
+        for c in "qpzrgf2ts3jnce6m".chars() {
+            assert!(is_reachable(1, CHARSET.find(c).unwrap() as u8), "{}", c);
+        }
+        // The first group has no fixed bit
+        assert!(CHARSET.chars().all(|c| is_reachable(0, CHARSET.find(c).unwrap() as u8)));
+    }
+
+    #[test]
+    fn test_unreachable_content() {
+        assert_eq!(unreachable("ay", Placement::Prefix, None), Some((1, 'y')));
+        assert_eq!(unreachable("aq", Placement::Prefix, None), None);
+        assert_eq!(unreachable("cafe", Placement::Prefix, None), None);
+        // The checksum can't be checked
+        assert_eq!(unreachable("ay", Placement::Suffix, None), None);
+        assert_eq!(unreachable("ay", Placement::Anywhere, None), None);
+
+        // Real addresses never have an unreachable prefix
+        for seed in 1..32 {
+            let (_, address) = encoded(seed, Network::Mainnet);
+            let prefix = &address[data_start(Network::Mainnet)..data_start(Network::Mainnet) + 8];
+            assert_eq!(unreachable(prefix, Placement::Prefix, None), None);
+        }
+    }
+
+    #[test]
+    fn test_allowlist_ignores_the_type_and_the_checksum() {
+        let only = Allowlist::new("ac").unwrap();
+        assert!(only.is_matching(&address(&"ac".repeat(26)[..KEY_DATA_LEN])));
+        assert!(!only.is_matching(&address(&format!("{}d", &"a".repeat(KEY_DATA_LEN - 1)))));
+    }
+
+    #[test]
+    fn test_allowlist_rejects_invalid_lists() {
+        assert!(Allowlist::new("").is_err());
+        assert!(Allowlist::new("b").is_err());
+        assert!(Allowlist::new(CHARSET).is_err());
+    }
+
+    #[test]
+    fn test_allowlist_missing() {
+        let only = Allowlist::new("ac").unwrap();
+        assert_eq!(only.missing("caca"), None);
+        assert_eq!(only.missing("cad"), Some('d'));
+        assert_eq!(only.missing("caé"), Some('é'));
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex
+};
+use log::warn;
+use serde::Serialize;
+
+// Record of a found address, written as one JSON line in the output file
+#[derive(Serialize)]
+pub struct OutputRecord<'a> {
+    // Address matching the content, on the searched network, unless excluded by `--output-fields`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<&'a str>,
+    pub network: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mainnet_address: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub testnet_address: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_id: Option<u64>,
+    // Derivation index of the key, the key can be derived again from the master seed and it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u64>,
+    // Shard of the machine which found it, like 2/8, see `--shard`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shard: Option<String>,
+    // Private key, or the tweak to combine with it in a split-key search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tweak: Option<String>,
+    // Compressed public key in hex, only with `--output-fields public-key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    // Seed words keyed by language name
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub seeds: BTreeMap<&'a str, String>,
+    // Content matched by the address
+    pub pattern: &'a str,
+    // Placement where the content was found
+    pub placement: String,
+    // Position of the content in the address
+    pub match_offset: usize,
+    // False for a near miss found with `--fuzzy`, `distance` edits away from the content
+    pub exact: bool,
+    pub distance: usize,
+    // Thread which found it and its number of attempts
+    pub thread: usize,
+    pub attempts: u64,
+    // Time of the match in milliseconds since the UNIX epoch
+    pub timestamp: u64,
+}
+
+// Lock file preventing another instance to write in the same output file
+// It contains the PID of the process owning it and is removed on drop
+pub struct OutputLock {
+    path: PathBuf,
+}
+
+impl OutputLock {
+    pub fn acquire(output: &Path) -> Result<Self, String> {
+        let mut path = output.as_os_str().to_owned();
+        path.push(".lock");
+        let path = PathBuf::from(path);
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", process::id())
+                        .map_err(|e| format!("couldn't write lock file {}: {}", path.display(), e))?;
+
+                    return Ok(Self { path });
+                },
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let pid = fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|content| content.trim().parse::<u32>().ok());
+
+                    match pid {
+                        Some(pid) if is_process_alive(pid) => {
+                            return Err(format!("output file is already used by another instance (PID {}), lock file: {}", pid, path.display()));
+                        },
+                        _ => {
+                            warn!("Removing stale lock file {}", path.display());
+                            fs::remove_file(&path)
+                                .map_err(|e| format!("couldn't remove stale lock file {}: {}", path.display(), e))?;
+                        }
+                    }
+                },
+                Err(e) => return Err(format!("couldn't create lock file {}: {}", path.display(), e)),
+            }
+        }
+    }
+}
+
+impl Drop for OutputLock {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            warn!("Couldn't remove lock file {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_process_alive(pid: u32) -> bool {
+    // Signal 0 only checks if the process exists
+    // EPERM means it exists but is owned by another user
+    unsafe { libc::kill(pid as libc::pid_t, 0) == 0 }
+        || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn is_process_alive(_: u32) -> bool {
+    // We can't check it, consider it's still running
+    true
+}
+
+// Check that the output file can be opened for writing, creating it empty if needed
+// Nothing is written, used by `--validate-only`
+pub fn check_writable(path: &Path) -> Result<(), String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map(|_| ())
+        .map_err(|e| format!("couldn't open output file {}: {}", path.display(), e))
+}
+
+// Output file shared by all the workers
+pub struct OutputFile {
+    file: Mutex<File>,
+}
+
+impl OutputFile {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("couldn't open output file {}: {}", path.display(), e))?;
+
+        // A crash during a previous write may have left an incomplete line
+        // Keep it for manual recovery but start the next record on a new line
+        if !ends_with_newline(&mut file).map_err(|e| format!("couldn't read output file {}: {}", path.display(), e))? {
+            warn!("Output file {} ends with an incomplete record, it was probably interrupted during a write", path.display());
+            file.write_all(b"\n")
+                .and_then(|_| file.sync_data())
+                .map_err(|e| format!("couldn't write output file {}: {}", path.display(), e))?;
+        }
+
+        Ok(Self {
+            file: Mutex::new(file)
+        })
+    }
+
+    // Append a record as a JSON line
+    // The line is written at once and synced to the disk before returning,
+    // so a found key is either fully persisted or not at all
+    pub fn write(&self, record: &OutputRecord) -> Result<(), String> {
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| format!("couldn't serialize record: {}", e))?;
+        line.push('\n');
+
+        let mut file = self.file.lock()
+            .map_err(|_| "output file lock is poisoned".to_string())?;
+
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.sync_data())
+            .map_err(|e| format!("couldn't write output file: {}", e))
+    }
+}
+
+// Check if the file is empty or ends with a new line
+fn ends_with_newline(file: &mut File) -> io::Result<bool> {
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok(true);
+    }
+
+    let mut last = [0u8; 1];
+    file.seek(SeekFrom::Start(len - 1))?;
+    file.read_exact(&mut last)?;
+
+    Ok(last[0] == b'\n')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use super::*;
+
+    // Fresh file path in the temporary directory, unique per test and process
+    fn temp_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("xelis-vanity-{}-{}.jsonl", name, process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn record(address: &str) -> OutputRecord {
+        OutputRecord {
+            address: Some(address),
+            network: "mainnet".to_string(),
+            mainnet_address: None,
+            testnet_address: None,
+            payment_id: None,
+            index: None,
+            shard: None,
+            private_key: Some("00".repeat(32)),
+            tweak: None,
+            public_key: None,
+            seeds: BTreeMap::new(),
+            pattern: "cafe",
+            placement: "prefix".to_string(),
+            match_offset: 4,
+            exact: true,
+            distance: 0,
+            thread: 0,
+            attempts: 1,
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_record_after_an_interrupted_write() {
+        let path = temp_path("interrupted");
+        OutputFile::open(&path).unwrap().write(&record("xel:first")).unwrap();
+
+        // The process died in the middle of the second record
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"address":"xel:second","priv"#).unwrap();
+        drop(file);
+
+        OutputFile::open(&path).unwrap().write(&record("xel:third")).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // The partial line is kept apart and every complete record is still readable
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(content.ends_with('\n'));
+        assert_eq!(lines[1], r#"{"address":"xel:second","priv"#);
+        for (line, address) in [(lines[0], "xel:first"), (lines[2], "xel:third")] {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(value["address"], address);
+            assert_eq!(value["private_key"], "00".repeat(32));
+        }
+    }
+
+    #[test]
+    fn test_complete_file_is_not_changed() {
+        let path = temp_path("complete");
+        OutputFile::open(&path).unwrap().write(&record("xel:first")).unwrap();
+        let before = fs::read_to_string(&path).unwrap();
+
+        OutputFile::open(&path).unwrap();
+        let after = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn test_output_lock() {
+        let path = temp_path("lock");
+        let lock = OutputLock::acquire(&path).unwrap();
+        assert!(OutputLock::acquire(&path).unwrap_err().contains(&process::id().to_string()));
+
+        drop(lock);
+        let lock = OutputLock::acquire(&path).unwrap();
+        drop(lock);
+    }
+}
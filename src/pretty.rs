@@ -0,0 +1,269 @@
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex
+    }
+};
+use xelis_common::crypto::bech32::CHARSET;
+use crate::{config::Placement, estimate};
+
+// Longest run of the same character in the data part of an address
+// Returns its offset and its length
+pub fn longest_run(data: &[u8]) -> (usize, usize) {
+    let mut best = (0, 0);
+    let mut start = 0;
+    for i in 1..=data.len() {
+        if i == data.len() || data[i] != data[start] {
+            if i - start > best.1 {
+                best = (start, i - start);
+            }
+            start = i;
+        }
+    }
+
+    best
+}
+
+// Probability for `data_len` characters to contain a run of at least `len` characters
+// Each position can start a run: its first character is free, the next ones must repeat it
+pub fn run_probability(len: usize, data_len: usize) -> f64 {
+    let positions = data_len.saturating_sub(len) + 1;
+    let single = (CHARSET.len() as f64).powi(1 - len as i32);
+    1f64 - (1f64 - single).powi(positions as i32)
+}
+
+// Check if the characters are the same in both directions
+pub fn is_palindrome(window: &[u8]) -> bool {
+    window.iter().eq(window.iter().rev())
+}
+
+// Probability for `len` characters to form a palindrome
+// The first half is free, the second one must mirror it
+pub fn palindrome_probability(len: usize) -> f64 {
+    (CHARSET.len() as f64).powi(-((len / 2) as i32))
+}
+
+// Digits available in addresses, derived from the charset
+pub fn charset_digits() -> Vec<char> {
+    CHARSET.chars().filter(char::is_ascii_digit).collect()
+}
+
+// Number of digits at the end of the data part of an address
+// Addresses only contain charset characters, so any ASCII digit is one of `charset_digits`
+pub fn trailing_digits(data: &[u8]) -> usize {
+    data.iter().rev().take_while(|c| c.is_ascii_digit()).count()
+}
+
+// Probability for the last `len` characters to be digits
+pub fn digits_probability(len: usize) -> f64 {
+    (charset_digits().len() as f64 / CHARSET.len() as f64).powi(len as i32)
+}
+
+// Characters scored by `--mode charset-score`, a subset of the charset
+// Checked with a table indexed by the character as it runs on every address
+#[derive(Clone)]
+pub struct CharSubset {
+    table: [bool; 128],
+    len: usize,
+}
+
+impl CharSubset {
+    // Only the characters of the charset can appear in addresses, duplicates are ignored
+    pub fn new(chars: &str) -> Result<Self, String> {
+        let mut table = [false; 128];
+        let mut len = 0;
+        for c in chars.chars() {
+            if !CHARSET.contains(c) {
+                return Err(format!("Invalid character '{}' in --chars, only '{}' can appear in addresses", c, CHARSET));
+            }
+
+            if !table[c as usize] {
+                table[c as usize] = true;
+                len += 1;
+            }
+        }
+
+        if len == 0 {
+            return Err("--chars can't be empty".to_string());
+        }
+
+        // Every address would have the best score
+        if len == CHARSET.len() {
+            return Err("--chars can't contain the whole charset".to_string());
+        }
+
+        Ok(Self { table, len })
+    }
+
+    pub fn contains(&self, c: u8) -> bool {
+        self.table.get(c as usize).copied().unwrap_or(false)
+    }
+
+    // Run of characters of the subset in the data part at the placement, its length is the score
+    // Anywhere, it's the longest run
+    pub fn score(&self, data: &[u8], placement: Placement) -> Range<usize> {
+        match placement {
+            Placement::Prefix => 0..data.iter().take_while(|c| self.contains(**c)).count(),
+            Placement::Suffix => data.len() - data.iter().rev().take_while(|c| self.contains(**c)).count()..data.len(),
+            Placement::Anywhere => {
+                let mut best = 0..0;
+                let mut start = 0;
+                for (i, c) in data.iter().enumerate() {
+                    if !self.contains(*c) {
+                        start = i + 1;
+                    } else if i + 1 - start > best.len() {
+                        best = start..i + 1;
+                    }
+                }
+
+                best
+            }
+        }
+    }
+
+    // Probability for `data_len` characters to score at least `score` at one of the placements
+    pub fn probability(&self, score: usize, placements: &[Placement], data_len: usize) -> f64 {
+        let single = (self.len as f64 / CHARSET.len() as f64).powi(score as i32);
+        estimate::any_probability(placements.iter().map(|placement| match placement {
+            Placement::Anywhere => {
+                let positions = data_len.saturating_sub(score) + 1;
+                1f64 - (1f64 - single).powi(positions as i32)
+            },
+            _ => single,
+        }))
+    }
+}
+
+// Best run found so far, see `--mode longest-run` and `--mode charset-score`
+// Only an address beating it is reported, so the bar keeps rising
+pub struct RunRecord {
+    len: AtomicUsize,
+    // Only the address is kept, its keys are reported with the match
+    address: Mutex<Option<String>>,
+    // Probability for a single address to have a run of at least the given length
+    probability: Box<dyn Fn(usize) -> f64 + Send + Sync>,
+}
+
+impl RunRecord {
+    // The first record needs a run of at least `min_len` characters
+    pub fn new(min_len: usize, data_len: usize) -> Self {
+        Self::with_probability(min_len, move |len| run_probability(len, data_len))
+    }
+
+    pub fn with_probability(min_len: usize, probability: impl Fn(usize) -> f64 + Send + Sync + 'static) -> Self {
+        Self {
+            len: AtomicUsize::new(min_len.saturating_sub(1)),
+            address: Mutex::new(None),
+            probability: Box::new(probability),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    // Keep the address if its run beats the record
+    // Returns false if another worker found a run at least as long first
+    pub fn raise(&self, len: usize, address: &str) -> bool {
+        if self.len.fetch_max(len, Ordering::Relaxed) >= len {
+            return false;
+        }
+
+        if let Ok(mut best) = self.address.lock() {
+            *best = Some(address.to_string());
+        }
+        true
+    }
+
+    // Address holding the record, None until a first one is found
+    pub fn address(&self) -> Option<String> {
+        self.address.lock().ok().and_then(|address| address.clone())
+    }
+
+    // Probability for a single address to beat the record
+    pub fn next_probability(&self) -> f64 {
+        (self.probability)(self.len() + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_palindrome() {
+        for window in ["", "q", "qq", "qpq", "xelex", "ac9ca", "a0b0b0a", "zzzzzz"] {
+            assert!(is_palindrome(window.as_bytes()), "{}", window);
+        }
+
+        for window in ["qp", "qpp", "xelis", "ac9cb", "a0b0ba"] {
+            assert!(!is_palindrome(window.as_bytes()), "{}", window);
+        }
+    }
+
+    #[test]
+    fn test_palindrome_windows_of_the_data() {
+        let data = b"racecar0lnm2wf5madam";
+        assert!(is_palindrome(&data[..7]));
+        assert!(!is_palindrome(&data[..8]));
+        assert!(is_palindrome(&data[data.len() - 5..]));
+        assert!(!is_palindrome(&data[data.len() - 6..]));
+    }
+
+    #[test]
+    fn test_palindrome_probability() {
+        // The middle character of an odd length is free
+        assert_eq!(palindrome_probability(1), 1f64);
+        assert_eq!(palindrome_probability(2), 1f64 / 32f64);
+        assert_eq!(palindrome_probability(3), palindrome_probability(2));
+        assert_eq!(palindrome_probability(6), 1f64 / 32768f64);
+    }
+
+    #[test]
+    fn test_char_subset_validation() {
+        assert!(CharSubset::new("xels").is_ok());
+        assert!(CharSubset::new("").is_err());
+        // 'b', 'i', 'o' and '1' are not in the charset
+        for chars in ["xelb", "i", "o1", "XELS"] {
+            assert!(CharSubset::new(chars).unwrap_err().contains("Invalid character"), "{}", chars);
+        }
+        assert!(CharSubset::new(CHARSET).is_err());
+
+        let subset = CharSubset::new("xxeels").unwrap();
+        assert!(subset.contains(b'x') && subset.contains(b's'));
+        assert!(!subset.contains(b'q') && !subset.contains(0xff));
+    }
+
+    #[test]
+    fn test_char_subset_score() {
+        let subset = CharSubset::new("xels").unwrap();
+        let data = b"xelqsssexqlxes";
+        assert_eq!(subset.score(data, Placement::Prefix), 0..3);
+        assert_eq!(subset.score(data, Placement::Suffix), 10..14);
+        assert_eq!(subset.score(data, Placement::Anywhere), 4..9);
+
+        // Ties keep the first run
+        assert_eq!(subset.score(b"xeqlsq", Placement::Anywhere), 0..2);
+
+        let none = b"qpzry";
+        assert!(subset.score(none, Placement::Prefix).is_empty());
+        assert!(subset.score(none, Placement::Suffix).is_empty());
+        assert!(subset.score(none, Placement::Anywhere).is_empty());
+
+        let all = b"sexless";
+        for placement in [Placement::Prefix, Placement::Suffix, Placement::Anywhere] {
+            assert_eq!(subset.score(all, placement), 0..all.len());
+        }
+    }
+
+    #[test]
+    fn test_char_subset_probability() {
+        let subset = CharSubset::new("xels").unwrap();
+        let single = (4f64 / 32f64).powi(3);
+        assert_eq!(subset.probability(3, &[Placement::Prefix], 57), single);
+        assert!(subset.probability(3, &[Placement::Prefix, Placement::Suffix], 57) > single);
+        assert!(subset.probability(3, &[Placement::Anywhere], 57) > subset.probability(3, &[Placement::Prefix, Placement::Suffix], 57));
+        assert!(subset.probability(4, &[Placement::Anywhere], 57) < subset.probability(3, &[Placement::Anywhere], 57));
+    }
+}
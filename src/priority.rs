@@ -0,0 +1,46 @@
+// Lowest scheduling priority for the worker threads
+// Only the calling thread is changed, so the prompt and the reporter keep a normal priority
+
+// Returns the priority applied
+#[cfg(target_os = "linux")]
+pub fn lower_current_thread() -> Result<&'static str, String> {
+    // SCHED_IDLE only runs the thread when the CPU is idle
+    let param = libc::sched_param { sched_priority: 0 };
+    if unsafe { libc::sched_setscheduler(0, libc::SCHED_IDLE, &param) } == 0 {
+        return Ok("idle scheduling policy (SCHED_IDLE)");
+    }
+
+    // On Linux, the nice value is per thread
+    let tid = unsafe { libc::gettid() };
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, 19) } == 0 {
+        return Ok("nice level 19");
+    }
+
+    Err(std::io::Error::last_os_error().to_string())
+}
+
+#[cfg(target_os = "macos")]
+pub fn lower_current_thread() -> Result<&'static str, String> {
+    let result = unsafe { libc::pthread_set_qos_class_self_np(libc::qos_class_t::QOS_CLASS_BACKGROUND, 0) };
+    if result != 0 {
+        return Err(std::io::Error::from_raw_os_error(result).to_string());
+    }
+
+    Ok("background QoS class")
+}
+
+#[cfg(windows)]
+pub fn lower_current_thread() -> Result<&'static str, String> {
+    use windows_sys::Win32::System::Threading::{GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_IDLE};
+
+    if unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_IDLE) } == 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+
+    Ok("idle thread priority")
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn lower_current_thread() -> Result<&'static str, String> {
+    Err("not supported on this platform".to_string())
+}
@@ -0,0 +1,165 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    sync::{atomic::Ordering, Arc, Mutex},
+    time::{Duration, Instant}
+};
+use log::{info, warn};
+use serde::Serialize;
+use xelis_common::{
+    time::get_current_time_in_millis,
+    tokio
+};
+use crate::{
+    estimate,
+    stats::{self, TOTAL_ATTEMPTS}
+};
+
+// Interval between two progress records
+const TICK: Duration = Duration::from_secs(1);
+// Interval between two checks of the milestones
+const MILESTONE_TICK: Duration = Duration::from_millis(100);
+
+// Status of the search, written as one JSON line per tick
+// Fields may be added but never removed or renamed
+#[derive(Serialize)]
+struct ProgressRecord {
+    // Always "progress"
+    event: &'static str,
+    // Milliseconds since the UNIX epoch
+    timestamp: u64,
+    // Seconds since the start of the search
+    elapsed: f64,
+    // Keys generated since the start
+    attempts: u64,
+    // Keys per second during the last tick
+    rate: f64,
+    // Probability to have found at least one match after these attempts
+    p_found: f64,
+}
+
+// A multiple of `--milestone` attempts was crossed
+#[derive(Serialize)]
+struct MilestoneRecord {
+    // Always "milestone"
+    event: &'static str,
+    timestamp: u64,
+    elapsed: f64,
+    attempts: u64,
+    // Average keys per second since the start
+    rate: f64,
+    // Attempts divided by the expected attempts to find a match
+    expected_progress: f64,
+}
+
+// Sink shared by the progress and the milestones
+pub type SharedSink = Arc<Mutex<ProgressSink>>;
+
+// Where the progress records are written
+pub enum ProgressSink {
+    Stdout,
+    Stderr,
+    // Records are appended to the file
+    File(File),
+}
+
+impl ProgressSink {
+    // `stdout`, `stderr` or the path of a file
+    pub fn open(target: &str) -> Result<Self, String> {
+        match target {
+            "stdout" | "-" => Ok(ProgressSink::Stdout),
+            "stderr" => Ok(ProgressSink::Stderr),
+            path => OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map(ProgressSink::File)
+                .map_err(|e| format!("couldn't open progress file {}: {}", path, e)),
+        }
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        match self {
+            ProgressSink::Stdout => writeln!(io::stdout().lock(), "{}", line),
+            ProgressSink::Stderr => writeln!(io::stderr().lock(), "{}", line),
+            ProgressSink::File(file) => writeln!(file, "{}", line),
+        }
+    }
+}
+
+// Serialize a record as a line in the sink
+fn write_record(sink: &SharedSink, record: &impl Serialize) {
+    let result = serde_json::to_string(record)
+        .map_err(|e| e.to_string())
+        .and_then(|line| {
+            let mut sink = sink.lock().map_err(|_| "progress sink lock is poisoned".to_string())?;
+            sink.write_line(&line).map_err(|e| e.to_string())
+        });
+
+    if let Err(e) = result {
+        warn!("Couldn't write the progress: {}", e);
+    }
+}
+
+// Log a line each time the attempts cross a multiple of `every`, until the task is aborted
+// Workers are adding their attempts by batches, so only the crossings are detected here
+pub async fn run_milestones(every: u64, probability: f64, sink: Option<SharedSink>) {
+    let start = Instant::now();
+    let mut next = every;
+    let mut interval = tokio::time::interval(MILESTONE_TICK);
+    loop {
+        interval.tick().await;
+        let attempts = TOTAL_ATTEMPTS.load(Ordering::Relaxed);
+        if attempts < next {
+            continue;
+        }
+
+        // Several milestones may be crossed at once, only the last one is logged
+        let milestone = attempts / every * every;
+        next = milestone + every;
+
+        let elapsed = start.elapsed().as_secs_f64();
+        let rate = attempts as f64 / elapsed;
+        let expected_progress = attempts as f64 / estimate::expected_attempts(probability);
+        info!("Milestone: {} attempts in {} (avg: {}, {:.2}x the expected attempts)", milestone, estimate::format_duration(elapsed), stats::format_rate(rate), expected_progress);
+
+        if let Some(sink) = &sink {
+            write_record(sink, &MilestoneRecord {
+                event: "milestone",
+                timestamp: get_current_time_in_millis(),
+                elapsed,
+                attempts,
+                rate,
+                expected_progress,
+            });
+        }
+    }
+}
+
+// Write a progress record every tick until the task is aborted
+// `probability` is the chance of a single key to match
+pub async fn run(sink: SharedSink, probability: f64) {
+    let start = Instant::now();
+    let mut last_attempts = TOTAL_ATTEMPTS.load(Ordering::Relaxed);
+    let mut last_time = Instant::now();
+    let mut interval = tokio::time::interval(TICK);
+    // The first tick completes immediately
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+        let attempts = TOTAL_ATTEMPTS.load(Ordering::Relaxed);
+        let record = ProgressRecord {
+            event: "progress",
+            timestamp: get_current_time_in_millis(),
+            elapsed: start.elapsed().as_secs_f64(),
+            attempts,
+            rate: (attempts - last_attempts) as f64 / last_time.elapsed().as_secs_f64(),
+            p_found: estimate::found_probability(probability, attempts as f64),
+        };
+        last_attempts = attempts;
+        last_time = Instant::now();
+
+        write_record(&sink, &record);
+    }
+}
@@ -0,0 +1,365 @@
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305,
+    Key,
+    Nonce
+};
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT,
+    ristretto::RistrettoPoint,
+    scalar::Scalar
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use xelis_common::{
+    crypto::hash,
+    tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt}
+};
+use crate::{
+    config::{Network, Placement},
+    split
+};
+
+// Distributed search between a coordinator and its workers, see `worker --connect`
+// Each message is a JSON object prefixed by its length as a big endian u32.
+
+// Changed on any incompatible change of the messages
+pub const PROTOCOL_VERSION: u32 = 3;
+
+// Largest message accepted, the jobs and the results are a few kilobytes
+pub const MAX_MESSAGE_SIZE: usize = 1 << 20;
+
+// Size of the nonce of a sealed secret in bytes
+const NONCE_SIZE: usize = 12;
+
+// Search assigned by the coordinator to a worker
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Job {
+    // Changes with each new job, the results refer to it
+    pub id: u64,
+    pub network: Network,
+    pub contents: Vec<String>,
+    // Placements of each content
+    pub placements: Vec<Vec<Placement>>,
+    // Indexes to derive from a master seed, random keys are generated without it
+    pub derivation: Option<JobRange>,
+    // Compressed public key of the coordinator in hex, the secrets are sealed to it
+    pub public_key: String,
+}
+
+impl Job {
+    // Same search, the master seed is sealed again each time a job is sent
+    pub fn is_same(&self, other: &Job) -> bool {
+        let range = |job: &Job| job.derivation.as_ref().map(|range| (range.start, range.end));
+        self.id == other.id
+            && self.network == other.network
+            && self.contents == other.contents
+            && self.placements == other.placements
+            && range(self) == range(other)
+            && self.public_key == other.public_key
+    }
+}
+
+// Counter range of a worker in a derived search
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JobRange {
+    // Master seed in hex, as accepted by `--derive-from`, sealed to the worker
+    // Anyone reading it could derive all the keys found
+    pub master: SealedSecret,
+    // First index to derive and index to stop at (excluded)
+    pub start: u64,
+    pub end: u64,
+}
+
+// Messages sent by a worker
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerMessage {
+    // First message of each connection
+    Hello {
+        version: u32,
+        name: Option<String>,
+        threads: usize,
+        // Job searched before the connection was lost, kept if the coordinator still runs it
+        job: Option<u64>,
+        // Compressed public key of the worker in hex, the master seed of the derived jobs is sealed to it
+        public_key: String,
+    },
+    // Keys per second and keys generated since the previous report
+    // The keys generated while disconnected are in the first report after reconnecting
+    Rate {
+        job: u64,
+        rate: f64,
+        attempts: u64,
+        // Every index of a derived job below it was searched, see `Progress::offset`
+        #[serde(default)]
+        offset: Option<u64>,
+    },
+    Found(Submission),
+    // All the keys of the range were generated
+    Finished {
+        job: u64,
+    },
+}
+
+// Messages sent by the coordinator
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CoordinatorMessage {
+    Job(Job),
+    // The search is over, the worker exits
+    Stop,
+}
+
+// Address found by a worker
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Submission {
+    pub job: u64,
+    pub address: String,
+    pub content: String,
+    pub placement: Option<Placement>,
+    // Index of the key in a derived search
+    pub index: Option<u64>,
+    pub secret: SealedSecret,
+}
+
+// Secret encrypted to a public key: the private key of a result sealed to the coordinator,
+// or the master seed of a derived job sealed to the worker
+// ChaCha20-Poly1305 with the key H(e * P || e * G || P) for an ephemeral scalar e
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SealedSecret {
+    // All in hex
+    pub ephemeral: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+// Key pair opening the sealed secrets
+// A new one is generated by each coordinator and each worker run
+pub struct SealingKey {
+    secret: Scalar,
+    public_key: RistrettoPoint,
+}
+
+impl SealingKey {
+    pub fn generate() -> Self {
+        let secret = split::random_scalar();
+        Self {
+            secret,
+            public_key: secret * RISTRETTO_BASEPOINT_POINT,
+        }
+    }
+
+    // Public key to send in the jobs, or in the introduction of a worker
+    pub fn public_key(&self) -> String {
+        to_hex(self.public_key.compress().as_bytes())
+    }
+
+    pub fn open(&self, sealed: &SealedSecret) -> Result<String, String> {
+        let ephemeral = split::parse_public_key(&sealed.ephemeral)?;
+        let nonce = from_hex(&sealed.nonce)?;
+        if nonce.len() != NONCE_SIZE {
+            return Err(format!("expected a nonce of {} bytes, got {}", NONCE_SIZE, nonce.len()));
+        }
+
+        let ciphertext = from_hex(&sealed.ciphertext)?;
+        let key = shared_key(&(self.secret * ephemeral), &ephemeral, &self.public_key);
+        let plaintext = ChaCha20Poly1305::new(Key::from_slice(&key))
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| "couldn't decrypt the secret: wrong key or corrupted message".to_string())?;
+
+        String::from_utf8(plaintext).map_err(|_| "decrypted secret is not valid UTF-8".to_string())
+    }
+}
+
+// Encrypt a secret so only the owner of `public_key` can read it
+pub fn seal(public_key: &RistrettoPoint, secret: &str) -> Result<SealedSecret, String> {
+    let scalar = split::random_scalar();
+    let ephemeral = scalar * RISTRETTO_BASEPOINT_POINT;
+    let key = shared_key(&(scalar * public_key), &ephemeral, public_key);
+
+    let mut nonce = [0u8; NONCE_SIZE];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(&nonce), secret.as_bytes())
+        .map_err(|_| "couldn't encrypt the secret".to_string())?;
+
+    Ok(SealedSecret {
+        ephemeral: to_hex(ephemeral.compress().as_bytes()),
+        nonce: to_hex(&nonce),
+        ciphertext: to_hex(&ciphertext),
+    })
+}
+
+fn shared_key(shared: &RistrettoPoint, ephemeral: &RistrettoPoint, public_key: &RistrettoPoint) -> [u8; 32] {
+    let mut input = Vec::with_capacity(96);
+    input.extend_from_slice(shared.compress().as_bytes());
+    input.extend_from_slice(ephemeral.compress().as_bytes());
+    input.extend_from_slice(public_key.compress().as_bytes());
+
+    *hash(&input).as_bytes()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(value: &str) -> Result<Vec<u8>, String> {
+    if value.len() % 2 != 0 || !value.is_ascii() {
+        return Err("invalid hex value".to_string());
+    }
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).map_err(|e| format!("invalid hex value: {}", e)))
+        .collect()
+}
+
+// Send a message, prefixed by its length
+pub async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &impl Serialize) -> Result<(), String> {
+    let bytes = serde_json::to_vec(message)
+        .map_err(|e| format!("couldn't serialize the message: {}", e))?;
+    if bytes.len() > MAX_MESSAGE_SIZE {
+        return Err(format!("message of {} bytes is larger than the limit of {} bytes", bytes.len(), MAX_MESSAGE_SIZE));
+    }
+
+    writer.write_all(&(bytes.len() as u32).to_be_bytes()).await
+        .map_err(|e| format!("couldn't send the message: {}", e))?;
+    writer.write_all(&bytes).await
+        .map_err(|e| format!("couldn't send the message: {}", e))?;
+    writer.flush().await
+        .map_err(|e| format!("couldn't send the message: {}", e))
+}
+
+// Receive a message, the larger ones are rejected before reading them
+// Not cancel safe: a partially read message is lost, read from a dedicated task
+pub async fn read_message<R: AsyncRead + Unpin, T: DeserializeOwned>(reader: &mut R) -> Result<T, String> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len).await
+        .map_err(|e| format!("couldn't receive the message: {}", e))?;
+
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_MESSAGE_SIZE {
+        return Err(format!("message of {} bytes is larger than the limit of {} bytes", len, MAX_MESSAGE_SIZE));
+    }
+
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes).await
+        .map_err(|e| format!("couldn't receive the message: {}", e))?;
+
+    serde_json::from_slice(&bytes).map_err(|e| format!("invalid message: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use xelis_common::tokio::runtime::Builder;
+    use super::*;
+
+    fn job(key: &SealingKey, worker: &SealingKey) -> Job {
+        Job {
+            id: 3,
+            network: Network::Mainnet,
+            contents: vec!["cafe".to_string()],
+            placements: vec![vec![Placement::Prefix, Placement::Suffix]],
+            derivation: Some(JobRange {
+                master: seal(&worker.public_key, &"ab".repeat(32)).unwrap(),
+                start: 1000,
+                end: 2000,
+            }),
+            public_key: key.public_key(),
+        }
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned>(message: &T) -> T {
+        serde_json::from_slice(&serde_json::to_vec(message).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_coordinator_messages_round_trip() {
+        let job = job(&SealingKey::generate(), &SealingKey::generate());
+        for message in [CoordinatorMessage::Job(job), CoordinatorMessage::Stop] {
+            assert_eq!(round_trip(&message), message);
+        }
+    }
+
+    #[test]
+    fn test_worker_messages_round_trip() {
+        let key = SealingKey::generate();
+        let messages = [
+            WorkerMessage::Hello { version: PROTOCOL_VERSION, name: Some("rack-1".to_string()), threads: 8, job: Some(3), public_key: key.public_key() },
+            WorkerMessage::Hello { version: PROTOCOL_VERSION, name: None, threads: 1, job: None, public_key: key.public_key() },
+            WorkerMessage::Rate { job: 3, rate: 1234.5, attempts: 98765, offset: Some(1500) },
+            WorkerMessage::Found(Submission {
+                job: 3,
+                address: "xel:cafe".to_string(),
+                content: "cafe".to_string(),
+                placement: Some(Placement::Prefix),
+                index: Some(1500),
+                secret: seal(&key.public_key, "secret").unwrap(),
+            }),
+            WorkerMessage::Finished { job: 3 },
+        ];
+        for message in messages {
+            assert_eq!(round_trip(&message), message);
+        }
+    }
+
+    #[test]
+    fn test_rate_without_offset() {
+        let message: WorkerMessage = serde_json::from_str(r#"{"type":"rate","job":3,"rate":1.5,"attempts":10}"#).unwrap();
+        assert_eq!(message, WorkerMessage::Rate { job: 3, rate: 1.5, attempts: 10, offset: None });
+    }
+
+    #[test]
+    fn test_messages_are_tagged() {
+        let value = serde_json::to_value(WorkerMessage::Finished { job: 3 }).unwrap();
+        assert_eq!(value["type"], "finished");
+        assert_eq!(value["job"], 3);
+    }
+
+    #[test]
+    fn test_sealed_master_only_opens_for_the_worker() {
+        let worker = SealingKey::generate();
+        let job = round_trip(&job(&SealingKey::generate(), &worker));
+        let master = &job.derivation.as_ref().unwrap().master;
+        assert_eq!(worker.open(master).unwrap(), "ab".repeat(32));
+        assert!(SealingKey::generate().open(master).is_err());
+    }
+
+    #[test]
+    fn test_resealed_job_is_same() {
+        let (key, worker) = (SealingKey::generate(), SealingKey::generate());
+        let (first, second) = (job(&key, &worker), job(&key, &worker));
+        assert_ne!(first, second);
+        assert!(first.is_same(&second));
+
+        let mut other = second.clone();
+        other.derivation.as_mut().unwrap().start = 2000;
+        assert!(!first.is_same(&other));
+    }
+
+    #[test]
+    fn test_framed_messages() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let mut buffer = Vec::new();
+            write_message(&mut buffer, &CoordinatorMessage::Stop).await.unwrap();
+            write_message(&mut buffer, &WorkerMessage::Finished { job: 7 }).await.unwrap();
+
+            let mut reader = buffer.as_slice();
+            assert_eq!(read_message::<_, CoordinatorMessage>(&mut reader).await.unwrap(), CoordinatorMessage::Stop);
+            assert_eq!(read_message::<_, WorkerMessage>(&mut reader).await.unwrap(), WorkerMessage::Finished { job: 7 });
+            assert!(read_message::<_, WorkerMessage>(&mut reader).await.is_err());
+        });
+    }
+
+    #[test]
+    fn test_oversized_message_is_rejected() {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let bytes = ((MAX_MESSAGE_SIZE + 1) as u32).to_be_bytes();
+            let mut reader = &bytes[..];
+            assert!(read_message::<_, CoordinatorMessage>(&mut reader).await.is_err());
+        });
+    }
+}
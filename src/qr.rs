@@ -0,0 +1,41 @@
+use std::path::{Path, PathBuf};
+use image::Luma;
+use qrcode::{render::unicode::Dense1x2, QrCode};
+
+// QR codes of the found addresses, see `--qr` and `--qr-png`
+// Only rendered by the reporter, the workers never wait for them
+
+// Render the address for the terminal, two rows of modules per line
+// The colors are inverted so it can be scanned on a dark background
+pub fn render(address: &str) -> Result<String, String> {
+    let code = encode(address)?;
+    Ok(code.render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build())
+}
+
+// Write the QR code of the address in the directory, named after the address
+// The separator is replaced as it's not accepted in a file name everywhere
+pub fn save_png(address: &str, dir: &Path) -> Result<PathBuf, String> {
+    let code = encode(address)?;
+    let path = dir.join(format!("{}.png", address.replace(':', "_")));
+    code.render::<Luma<u8>>()
+        .build()
+        .save(&path)
+        .map_err(|e| format!("couldn't write the QR code {}: {}", path.display(), e))?;
+
+    Ok(path)
+}
+
+// Directory of the output file, where the PNG files are written
+pub fn output_dir(output: &Path) -> PathBuf {
+    match output.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+fn encode(address: &str) -> Result<QrCode, String> {
+    QrCode::new(address.as_bytes()).map_err(|e| format!("couldn't encode {} in a QR code: {}", address, e))
+}
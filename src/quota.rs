@@ -0,0 +1,27 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Remaining number of addresses to find for each content, see `--count-per-pattern`
+// The workers claim a slot before reporting a match, so exactly the quota is reported
+pub struct Quotas {
+    remaining: Vec<AtomicU64>,
+}
+
+impl Quotas {
+    pub fn new(contents: usize, quota: u64) -> Self {
+        Self {
+            remaining: (0..contents).map(|_| AtomicU64::new(quota)).collect(),
+        }
+    }
+
+    // Claim a match of the content, false if its quota is already filled
+    pub fn claim(&self, index: usize) -> bool {
+        self.remaining[index]
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining| remaining.checked_sub(1))
+            .is_ok()
+    }
+
+    // The content doesn't need to be searched anymore
+    pub fn is_filled(&self, index: usize) -> bool {
+        self.remaining[index].load(Ordering::Relaxed) == 0
+    }
+}
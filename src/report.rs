@@ -0,0 +1,48 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path
+};
+use serde::Serialize;
+
+// Summary of a whole search, written by `--report` once it stopped
+// It's built from the counters of the status line, like the summary logged at the end
+#[derive(Serialize)]
+pub struct RunReport<'a> {
+    // Times in milliseconds since the UNIX epoch
+    pub started: u64,
+    pub stopped: u64,
+    // Duration of the search in seconds
+    pub elapsed: f64,
+    // Why the search stopped: exit, ctrl-c, sigterm, control-socket, finished, found, timeout, max-attempts or target-score
+    pub stop_reason: &'a str,
+    pub attempts: u64,
+    // Keys per second over the whole search, and highest rate of the status line
+    pub average_rate: f64,
+    pub peak_rate: f64,
+    pub matches: usize,
+    // First addresses found, never their keys
+    pub addresses: &'a [String],
+    // Keys generated by each thread, indexed by thread
+    pub threads: Vec<u64>,
+    // Restarts of each thread after a panic, indexed by thread
+    pub restarts: Vec<u64>,
+    // Effective configuration, as logged at startup
+    pub configuration: BTreeMap<&'a str, String>,
+}
+
+impl RunReport<'_> {
+    // Write the report as a JSON file, replacing any previous one
+    // A temporary file is written first so a crash never leaves a partial report
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("couldn't serialize the report: {}", e))?;
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        fs::write(&tmp, content)
+            .and_then(|_| fs::rename(&tmp, path))
+            .map_err(|e| format!("couldn't write the report to {}: {}", path.display(), e))
+    }
+}
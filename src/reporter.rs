@@ -0,0 +1,385 @@
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    path::PathBuf,
+    sync::{atomic::Ordering, Arc},
+    time::Duration
+};
+use log::{error, info, warn};
+use xelis_common::{
+    crypto::bech32::{self, SEPARATOR},
+    prompt::{Color, ShareablePrompt},
+    serializer::Serializer,
+    tokio::{
+        self,
+        sync::{mpsc::UnboundedReceiver, Notify}
+    }
+};
+use xelis_wallet::mnemonics;
+use crate::{
+    api::{ApiResult, ApiState},
+    config::{Network, OutputField, SearchMode},
+    derivation::Shard,
+    desired::DesiredWord,
+    encoder::AddressEncoder,
+    hook::Hook,
+    keys,
+    matcher::{CHECKSUM_LEN, KEY_DATA_LEN},
+    output::{OutputFile, OutputRecord},
+    qr,
+    searcher::{FoundKey, FoundResult},
+    split,
+    stats::{MatchStats, TOTAL_ATTEMPTS, TOTAL_MATCHES}
+};
+
+// Interval of the summary of the matches that were not logged
+const COALESCE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Handles all the results found by the workers
+pub struct Reporter {
+    pub prompt: ShareablePrompt,
+    // Highlight the matched content, disabled by `--disable-log-color`
+    pub colors: bool,
+    pub network: Network,
+    // Index and name of the languages to print the seed in
+    pub languages: Vec<(usize, &'static str)>,
+    // Restore the address from the seed before reporting it
+    pub verify: bool,
+    // Option generating insecure keys, `--fast-rng` or `--rng-seed`
+    pub insecure: Option<&'static str>,
+    pub output: Option<Arc<OutputFile>>,
+    // Show the QR code of each address, see `--qr`
+    pub qr: bool,
+    // Directory of the QR codes written as PNG, see `--qr-png`
+    pub qr_dir: Option<PathBuf>,
+    // Word requested with `--desired`
+    pub desired: Option<DesiredWord>,
+    // Pretty address searched instead of a content
+    pub mode: Option<SearchMode>,
+    // Number of addresses requested, `found` is notified once they are reported
+    pub count: Option<usize>,
+    pub found: Arc<Notify>,
+    // Matches logged per second at most, see `--max-logged-rate`
+    pub max_logged_rate: u32,
+    // Results served by the status API, see `--api-bind`
+    pub api: Option<Arc<ApiState>>,
+    // Log the parts of each address, see `--verbose-output`
+    pub verbose: bool,
+    // Part of the derivation indexes searched by this machine, see `--shard`
+    pub shard: Option<Shard>,
+    // Parts of each match logged, saved and served, see `--output-fields`
+    pub fields: Vec<OutputField>,
+    // Log each match on a single line, see `--compact`
+    pub compact: bool,
+    // Command run for each match, see `--exec`
+    pub hook: Option<Arc<Hook>>,
+}
+
+impl Reporter {
+    // Report the results until all the workers are stopped
+    // Above `max_logged_rate`, the matches are only saved and summarized once per interval
+    // Without an output file, every match is logged since the log is the only copy of its key
+    // Returns the statistics of the addresses reported
+    pub async fn run(self, mut receiver: UnboundedReceiver<FoundResult>) -> MatchStats {
+        let mut stats = MatchStats::default();
+        let mut interval = tokio::time::interval(COALESCE_INTERVAL);
+        let (mut logged, mut coalesced) = (0, 0);
+        loop {
+            tokio::select! {
+                result = receiver.recv() => {
+                    let Some(result) = result else {
+                        break;
+                    };
+
+                    // The threads send their results in any order, the keys between two matches
+                    // are counted from the total at reception which only grows
+                    let since = stats.record(&result.content, self.shows(OutputField::Address).then_some(result.address.as_str()), TOTAL_ATTEMPTS.load(Ordering::Relaxed));
+                    TOTAL_MATCHES.fetch_add(1, Ordering::Relaxed);
+                    let log = self.output.is_none() || logged < self.max_logged_rate;
+                    self.report(result, log);
+                    if log {
+                        logged += 1;
+                        info!("Keys since the previous match: {} (mean: {:.0}, min: {}, max: {})", since, stats.mean(), stats.min(), stats.max());
+                    } else {
+                        coalesced += 1;
+                    }
+
+                    if Some(stats.count()) == self.count {
+                        self.found.notify_one();
+                    }
+                },
+                _ = interval.tick() => {
+                    self.summarize(coalesced, stats.count());
+                    (logged, coalesced) = (0, 0);
+                }
+            }
+        }
+
+        self.summarize(coalesced, stats.count());
+        stats
+    }
+
+    // Summary of the matches found too fast to be logged
+    fn summarize(&self, coalesced: u64, total: usize) {
+        if coalesced == 0 {
+            return;
+        }
+
+        info!("{} more matches in the last {}s (saved in the output file), {} in total", coalesced, COALESCE_INTERVAL.as_secs(), total);
+    }
+
+    fn shows(&self, field: OutputField) -> bool {
+        self.fields.contains(&field)
+    }
+
+    // Log the address found with its keys and save it in the output file
+    // Without `log`, it's only saved, the errors are logged anyway
+    fn report(&self, result: FoundResult, log: bool) {
+        let FoundResult { key, address, payment_id, index, content, placement, range, distance, thread, attempts, timestamp, .. } = result;
+        let placement = match (placement, self.mode) {
+            (Some(placement), _) => placement.to_string(),
+            (None, Some(mode)) => mode.to_string(),
+            (None, None) => "custom".to_string(),
+        };
+        let address = address.as_str();
+        // Name of the match in the errors, the content when the address isn't shown
+        let label = if self.shows(OutputField::Address) { address.to_string() } else { format!("'{}'", content) };
+        // The range of a near miss is already the compared characters
+        let range = if distance == 0 { match_range(&range, content.len()) } else { range };
+        let kind = if distance == 0 { "exact".to_string() } else { format!("near miss, {} edits away", distance) };
+        let detailed = log && !self.compact;
+        if detailed {
+            let shown = if self.shows(OutputField::Address) { format!(": {}", highlight(&self.prompt, address, range.clone(), self.colors)) } else { String::new() };
+            info!("Thread #{} found '{}' ({}, {}) after {} attempts{}", thread, content, placement, kind, attempts, shown);
+            match self.mode {
+                Some(SearchMode::DigitsSuffix) => info!("Digits at the end of the address: {}", content.len()),
+                Some(SearchMode::CharsetScore) => info!("Score: {}", content.len()),
+                _ => {}
+            }
+            if self.verbose && self.shows(OutputField::Address) {
+                log_structure(address, &range);
+            }
+            if let Some(desired) = self.desired.as_ref().filter(|desired| desired.content == content) {
+                info!("Desired word '{}': {}", desired.word, desired.closeness());
+            }
+            if let Some(option) = self.insecure {
+                warn!("This key was generated with {} and is INSECURE, never use it", option);
+            }
+        }
+
+        // Only the searched network is matched, but show the address on the other one too
+        // A split-key search only has the tweak, the private key is never known
+        let other_network = self.network.other();
+        let (other_address, private_key, tweak) = match &key {
+            FoundKey::KeyPair(keypair) => (keys::derive_address(keypair, other_network, payment_id), Some(keypair.get_private_key().to_hex()), None),
+            FoundKey::Tweak { multiplier, public_key } => (AddressEncoder::new(other_network).encode_key(public_key, payment_id).to_string(), None, Some(split::format_tweak(multiplier))),
+        };
+        // The fields excluded by `--output-fields` are neither logged, saved nor served
+        let private_key = private_key.filter(|_| self.shows(OutputField::PrivateKey));
+        let public_key = self.shows(OutputField::PublicKey)
+            .then(|| key.public_key().iter().map(|byte| format!("{:02x}", byte)).collect::<String>());
+        if detailed {
+            if self.shows(OutputField::Address) {
+                info!("{} address: {}", other_network, other_address);
+            }
+            if let Some(id) = payment_id {
+                info!("Payment ID: {}", id);
+            }
+            if let Some(private_key) = &private_key {
+                info!("Private key: {}", private_key);
+            }
+            if let Some(public_key) = &public_key {
+                info!("Public key: {}", public_key);
+            }
+            if let Some(tweak) = &tweak {
+                info!("Tweak: {}", tweak);
+                let address = if self.shows(OutputField::Address) { address } else { "<address>" };
+                info!("Only the owner of the split public key can combine it: combine --private-key <private key> --tweak {} --address {}", tweak, address);
+            }
+            if let Some(index) = index {
+                match self.shard {
+                    Some(shard) => info!("Derivation index: {} (shard {})", index, shard),
+                    None => info!("Derivation index: {}", index),
+                }
+            }
+        }
+
+        let mut seeds = BTreeMap::new();
+        if let (FoundKey::KeyPair(keypair), true) = (&key, self.shows(OutputField::Seed)) {
+            for (index, name) in self.languages.iter() {
+                let words = match mnemonics::key_to_words(keypair.get_private_key(), *index) {
+                    Ok(words) => words,
+                    Err(e) => {
+                        error!("Couldn't convert the private key to a seed in {}: {}", name, e);
+                        continue;
+                    }
+                };
+
+                let verified = !self.verify || match keys::verify_seed(&words, address, self.network, payment_id) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        error!("Seed verification failed for {} in {}: {}", label, name, e);
+                        false
+                    }
+                };
+
+                if verified {
+                    let seed = words.join(" ");
+                    if detailed {
+                        info!("Seed ({}): {}", name, seed);
+                    }
+                    seeds.insert(*name, seed);
+                } else {
+                    error!("Do NOT use the {} seed of this address, keep the private key above instead", name);
+                }
+            }
+        }
+
+        // The same fields on a single line, the errors are still logged on their own
+        if log && self.compact {
+            let mut line = vec![if self.shows(OutputField::Address) { highlight(&self.prompt, address, range.clone(), self.colors) } else { format!("'{}'", content) }];
+            line.extend(seeds.iter().map(|(name, seed)| if seeds.len() > 1 { format!("seed ({}): {}", name, seed) } else { format!("seed: {}", seed) }));
+            line.extend(private_key.iter().map(|private_key| format!("key: {}", private_key)));
+            line.extend(public_key.iter().map(|public_key| format!("public key: {}", public_key)));
+            line.extend(tweak.iter().map(|tweak| format!("tweak: {}", tweak)));
+            line.extend(payment_id.map(|id| format!("payment ID: {}", id)));
+            line.extend(index.map(|index| format!("index: {}", index)));
+            info!("{}", line.join("  |  "));
+            if let Some(option) = self.insecure {
+                warn!("This key was generated with {} and is INSECURE, never use it", option);
+            }
+        }
+
+        if let Some(api) = &self.api {
+            api.record(ApiResult {
+                address: self.shows(OutputField::Address).then(|| address.to_string()),
+                content: content.clone(),
+                placement: placement.clone(),
+                index,
+                timestamp,
+                private_key: private_key.clone(),
+            });
+        }
+
+        if self.output.is_some() || self.hook.is_some() {
+            let (mainnet_address, testnet_address) = match self.network {
+                Network::Mainnet => (address, other_address.as_str()),
+                Network::Testnet => (other_address.as_str(), address),
+            };
+            let addresses = self.shows(OutputField::Address);
+
+            let record = OutputRecord {
+                address: addresses.then_some(address),
+                network: self.network.to_string(),
+                mainnet_address: addresses.then_some(mainnet_address),
+                testnet_address: addresses.then_some(testnet_address),
+                payment_id,
+                index,
+                shard: self.shard.map(|shard| shard.to_string()),
+                private_key,
+                tweak,
+                public_key,
+                seeds,
+                pattern: &content,
+                placement,
+                match_offset: range.start,
+                exact: distance == 0,
+                distance,
+                thread,
+                attempts,
+                timestamp,
+            };
+
+            if let Some(output) = &self.output {
+                if let Err(e) = output.write(&record) {
+                    error!("Couldn't save {} in the output file: {}", label, e);
+                }
+            }
+
+            if let Some(hook) = &self.hook {
+                hook.spawn(&record);
+            }
+        }
+
+        // A QR code shows the address, it's refused without it at startup
+        if !self.shows(OutputField::Address) {
+            return;
+        }
+
+        // Only with the logs of the match, the coalesced ones are not shown
+        if self.qr && log {
+            match qr::render(address) {
+                Ok(code) => info!("QR code of {}:\n{}", address, code),
+                Err(e) => error!("{}", e),
+            }
+        }
+
+        if let Some(dir) = &self.qr_dir {
+            match qr::save_png(address, dir) {
+                Ok(path) => if log {
+                    info!("QR code of {} saved in {}", address, path.display());
+                },
+                Err(e) => error!("{}", e),
+            }
+        }
+    }
+}
+
+// Position of the content in the address
+// The matched range may include the address prefix, the content is made of its last `content_len` characters
+fn match_range(range: &Range<usize>, content_len: usize) -> Range<usize> {
+    range.end - content_len..range.end
+}
+
+// Decompose the address in its bech32 parts and show the one holding the content
+// The checksum is verified by decoding the address first
+fn log_structure(address: &str, range: &Range<usize>) {
+    let hrp = match bech32::decode(&address.to_string()) {
+        Ok((hrp, _)) => hrp,
+        Err(e) => {
+            error!("Couldn't decode {} as bech32: {}", address, e);
+            return;
+        }
+    };
+
+    let data_start = hrp.len() + SEPARATOR.len_utf8();
+    let key_end = data_start + KEY_DATA_LEN;
+    let checksum_start = address.len() - CHECKSUM_LEN;
+    info!("Human-readable part (network): {}", hrp);
+    info!("Separator: {}", SEPARATOR);
+    info!("Data part: {} ({} characters of 5 bits)", &address[data_start..checksum_start], checksum_start - data_start);
+    info!("  Public key: {}, then its last bits and the address type: {}", &address[data_start..key_end], &address[key_end..checksum_start]);
+    info!("Checksum: {}", &address[checksum_start..]);
+
+    if range.is_empty() {
+        return;
+    }
+
+    let part = if range.end <= data_start {
+        "the human-readable part"
+    } else if range.start >= checksum_start {
+        "the checksum"
+    } else if range.end > checksum_start {
+        "the data part and the checksum"
+    } else if range.start < data_start {
+        "the human-readable part and the data part"
+    } else {
+        "the data part"
+    };
+    info!("The content is at the characters {} to {} of the address, in {}", range.start, range.end - 1, part);
+}
+
+// Colorize the content matched in the address
+// Without colors, the address is returned as is
+fn highlight(prompt: &ShareablePrompt, address: &str, range: Range<usize>, colors: bool) -> String {
+    if !colors {
+        return address.to_string();
+    }
+
+    format!(
+        "{}{}{}",
+        &address[..range.start],
+        prompt.colorize_str(Color::BrightGreen, &address[range.clone()]),
+        &address[range.end..]
+    )
+}
@@ -0,0 +1,893 @@
+use std::{
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        mpsc,
+        Arc,
+        Mutex
+    },
+    thread,
+    time::{Duration, Instant}
+};
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+use log::{debug, error, info, log_enabled, warn, Level};
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use xelis_common::{
+    crypto::KeyPair,
+    time::{get_current_time_in_millis, TimestampMillis},
+    tokio::sync::mpsc::{self as channel, error::TryRecvError, UnboundedSender}
+};
+use crate::{
+    affinity,
+    best::BestMatches,
+    config::{Network, Placement},
+    derivation::{Derivation, Progress},
+    encoder::AddressEncoder,
+    keys::{AddressShape, KeyGenerator, PaymentId},
+    matcher::{self, Allowlist, KeyFilter, PatternSet},
+    pretty::{self, CharSubset, RunRecord},
+    priority,
+    quota::Quotas,
+    split::TweakGenerator,
+    stats::{RATE_COUNTER, TOTAL_ATTEMPTS}
+};
+
+// Key of an address found by a worker
+pub enum FoundKey {
+    KeyPair(KeyPair),
+    // Multiplier of the customer public key in a split-key search, see `--split-key`
+    // Only the tweak derived from it is reported
+    Tweak {
+        multiplier: Scalar,
+        public_key: [u8; 32],
+    },
+}
+
+impl FoundKey {
+    // Compressed public key, encoded in the address
+    pub fn public_key(&self) -> [u8; 32] {
+        match self {
+            FoundKey::KeyPair(keypair) => *keypair.get_public_key().compress().as_bytes(),
+            FoundKey::Tweak { public_key, .. } => *public_key,
+        }
+    }
+}
+
+// An address found by a worker, delivered to the reporter
+pub struct FoundResult {
+    pub key: FoundKey,
+    pub address: String,
+    pub payment_id: Option<u64>,
+    // Derivation index of the key, see `--derive-from`
+    pub index: Option<u64>,
+    // Content matched by the address
+    pub content: String,
+    // Placement where the content was found, None for a custom matcher or a pretty address
+    pub placement: Option<Placement>,
+    // Position of the pattern in the address
+    pub range: Range<usize>,
+    // Edits between the content and the address, 0 for an exact match, see `--fuzzy`
+    pub distance: usize,
+    pub thread: usize,
+    // Keys generated by the thread until this one
+    pub attempts: u64,
+    pub timestamp: TimestampMillis,
+}
+
+// Acceptance criteria of the addresses
+pub enum AddressMatcher {
+    // Contents at their placements
+    Patterns(PatternSet),
+    // Longest run of a repeated character, see `--mode longest-run`
+    LongestRun(Arc<RunRecord>),
+    // Palindrome of `len` characters at the start of the data or at the end of the address,
+    // see `--mode palindrome`
+    Palindrome {
+        len: usize,
+        placements: Vec<Placement>,
+    },
+    // Best score of the characters of the subset at the placements, see `--mode charset-score`
+    CharsetScore {
+        record: Arc<RunRecord>,
+        subset: CharSubset,
+        placements: Vec<Placement>,
+    },
+    // At least `min_len` digits at the end of the address, see `--mode digits-suffix`
+    DigitsSuffix {
+        min_len: usize,
+    },
+    // Closure called by the workers with each address encoded
+    // It runs once per key on the hot path: the engine generates in the order of
+    // 100k keys per second per thread, so a closure taking more than a few microseconds
+    // becomes the bottleneck. Without a `KeyFilter`, every key is also encoded to an address.
+    Custom(Box<dyn Fn(&str) -> bool + Send + Sync>),
+}
+
+// Everything a worker needs to search for an address
+pub struct SearchContext {
+    // Addresses to report
+    pub matcher: AddressMatcher,
+    // End of the area to search a suffix in, see `--before-checksum`
+    pub suffix_end: Option<usize>,
+    pub network: Network,
+    pub payment_id: Option<PaymentId>,
+    // Checked on the public key before encoding the address, if available
+    pub filter: Option<KeyFilter>,
+    // Characters the data part of the address must be made of, see `--only`
+    pub only: Option<Allowlist>,
+    // Closest partial match of each content
+    pub best: Arc<BestMatches>,
+    // Addresses left to find for each content, see `--count-per-pattern`
+    pub quotas: Option<Arc<Quotas>>,
+    // Edits accepted between a content and the address, see `--fuzzy`
+    pub fuzzy: usize,
+    // Use the insecure fast random generator, see `--fast-rng`
+    pub fast_rng: bool,
+    // Seed of the generator of each worker, see `--rng-seed`
+    pub rng_seed: Option<u64>,
+    // Derive the keys from a master seed instead, see `--derive-from`
+    pub derivation: Option<Derivation>,
+    // Public key of the customer to search tweaks of instead, see `--split-key`
+    pub split_key: Option<RistrettoPoint>,
+    // Next index of each worker, updated after each batch of derived keys
+    pub progress: Option<Arc<Progress>>,
+    // Number of keys generated at once
+    pub batch_size: usize,
+    // Percentage of the time spent working, see `--throttle`
+    pub throttle: Option<u32>,
+    // Channel to deliver the matches to the reporter
+    pub sender: UnboundedSender<FoundResult>,
+    // Called at each interval by a timer thread, see `ProgressCallback`
+    pub on_progress: Option<(Duration, ProgressCallback)>,
+    // Address taken on request to show what is compared, see `-v`
+    pub sampler: Option<Arc<Sampler>>,
+}
+
+// Called with the keys generated by the searcher, the keys per second since the previous call
+// and the time since the start. It runs on its own thread, never on the workers.
+pub type ProgressCallback = Box<dyn Fn(u64, f64, Duration) + Send>;
+
+impl SearchContext {
+    // Search for the addresses accepted by the matcher, with the default settings
+    pub fn new(network: Network, matcher: AddressMatcher, sender: UnboundedSender<FoundResult>) -> Self {
+        Self {
+            matcher,
+            suffix_end: None,
+            network,
+            payment_id: None,
+            filter: None,
+            only: None,
+            best: Arc::new(BestMatches::new(&[])),
+            quotas: None,
+            fuzzy: 0,
+            fast_rng: false,
+            rng_seed: None,
+            derivation: None,
+            split_key: None,
+            progress: None,
+            batch_size: DEFAULT_BATCH_SIZE,
+            throttle: None,
+            sender,
+            on_progress: None,
+            sampler: None,
+        }
+    }
+
+    // Search for the addresses accepted by a custom closure, with the default settings
+    // The matching keys are delivered on the channel like the built-in patterns
+    pub fn with_matcher<F>(network: Network, matcher: F, sender: UnboundedSender<FoundResult>) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static
+    {
+        Self::new(network, AddressMatcher::Custom(Box::new(matcher)), sender)
+    }
+
+    // Search for the contents at their placements, with the public key filter
+    pub fn with_patterns(network: Network, contents: &[String], placements: &[Vec<Placement>], sender: UnboundedSender<FoundResult>) -> Result<Self, String> {
+        let mut context = Self::new(network, AddressMatcher::Patterns(PatternSet::new(contents, placements, network)?), sender);
+        context.filter = KeyFilter::new(contents, placements, None);
+        Ok(context)
+    }
+}
+
+// Address generated by a worker on request, logged at each tick with `-v`
+// The workers only check a flag until a sample is requested, so it costs nothing on the hot path
+#[derive(Default)]
+pub struct Sampler {
+    // Set by `take`, cleared by the first worker offering an address
+    wanted: AtomicBool,
+    sample: Mutex<Option<String>>,
+}
+
+impl Sampler {
+    // Sample taken since the previous call, and request the next one
+    pub fn take(&self) -> Option<String> {
+        let sample = self.sample.lock().unwrap().take();
+        self.wanted.store(true, Ordering::Relaxed);
+        sample
+    }
+
+    fn is_wanted(&self) -> bool {
+        self.wanted.load(Ordering::Relaxed)
+    }
+
+    // Only the first worker offering an address after a request keeps it
+    fn offer(&self, address: &str) {
+        if self.wanted.swap(false, Ordering::Relaxed) {
+            *self.sample.lock().unwrap() = Some(address.to_string());
+        }
+    }
+}
+
+// Keys generated at once when not configured, same as `--batch-size`
+pub const DEFAULT_BATCH_SIZE: usize = 64;
+
+// Interval at which `search_blocking` checks for a match or the cancellation
+const BLOCKING_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// Check that a content can be found at the placement, like the CLI does before a search
+// It must be made of the charset, fit in the data part and not start with a character no public key has
+pub fn validate_content(content: &str, placement: Placement, network: Network) -> Result<(), String> {
+    matcher::check_content(content, network)?;
+
+    let max = AddressShape::of(network).data_len(None);
+    if content.len() > max {
+        return Err(format!("'{}' is {} characters, longer than the {} data characters of a {} address", content, content.len(), max, network));
+    }
+
+    if let Some((index, c)) = matcher::unreachable(content, placement, None) {
+        return Err(format!("'{}' can never be found as a {}: no public key has '{}' as data character {}", content, placement, c, index + 1));
+    }
+
+    Ok(())
+}
+
+// Search for an address containing the content at the placement, blocking until it's found
+// The content is checked first by `validate_content`, a search that can't succeed is refused
+// The keys are generated by `threads` worker threads in their own pool, the caller's thread
+// only waits for them. It stops like the CLI: at the first match, or once `cancel` is set,
+// returning None. If every worker exited after panicking too many times, it returns an error
+// instead, so a crash is never mistaken for a cancellation. The workers are always stopped before returning.
+pub fn search_blocking(content: &str, placement: Placement, network: Network, threads: usize, cancel: &AtomicBool) -> Result<Option<FoundResult>, String> {
+    validate_content(content, placement, network)?;
+
+    let (sender, mut receiver) = channel::unbounded_channel();
+    let context = SearchContext::with_patterns(network, &[content.to_string()], &[vec![placement]], sender)?;
+
+    let mut searcher = Searcher::new(threads, None, false)
+        .map_err(|e| format!("couldn't create the worker threads: {}", e))?;
+    searcher.start(context);
+
+    let found = loop {
+        match receiver.try_recv() {
+            Ok(result) => break Ok(Some(result)),
+            // All the workers exited, they only do it after too many panics
+            Err(TryRecvError::Disconnected) => break Err("all worker threads exited after panicking".to_string()),
+            Err(TryRecvError::Empty) => {},
+        }
+
+        if cancel.load(Ordering::Relaxed) {
+            break Ok(None);
+        }
+
+        thread::sleep(BLOCKING_POLL_INTERVAL);
+    };
+
+    searcher.stop();
+    found
+}
+
+// Number of times a panicked worker is restarted
+const MAX_RESTARTS: usize = 3;
+
+// State of a worker, watched by the watchdog
+#[derive(Default)]
+pub struct WorkerState {
+    // Keys generated since the start
+    pub attempts: AtomicU64,
+    // The worker exited, at the end of the derivation or after too many panics
+    pub exited: AtomicBool,
+    // The worker is paused or its thread is disabled, see `SearchControl`
+    pub waiting: AtomicBool,
+    // Times the worker was restarted after a panic
+    pub restarts: AtomicU64,
+    // Index of the first derived key of the current batch, a restarted worker resumes from it
+    pub next_index: AtomicU64,
+}
+
+// Interval at which a waiting worker checks if it can work again
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+
+// Pause and number of working threads, changed while the search runs
+pub struct SearchControl {
+    paused: AtomicBool,
+    // Workers with a lower index are working, the others wait
+    active: AtomicUsize,
+    // Threads of the pool, the maximum of `active`
+    threads: usize,
+    // Each thread derives its own indexes, so they all keep working
+    derived: AtomicBool,
+}
+
+impl SearchControl {
+    fn new(threads: usize) -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+            active: AtomicUsize::new(threads),
+            threads,
+            derived: AtomicBool::new(false),
+        }
+    }
+
+    // The workers finish their batch then wait
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn active_threads(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    // Only the threads of the pool can be used, it can't grow
+    // A derived search can't change it: the indexes of a waiting thread would never be searched
+    // and the search would never finish
+    pub fn set_active_threads(&self, threads: usize) -> Result<(), String> {
+        if self.derived.load(Ordering::Relaxed) {
+            return Err("the number of threads can't change in a derived search, each thread searches its own indexes".to_string());
+        }
+
+        if threads == 0 || threads > self.threads {
+            return Err(format!("number of threads must be between 1 and {}", self.threads));
+        }
+
+        self.active.store(threads, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn is_waiting(&self, thread: usize) -> bool {
+        self.is_paused() || thread >= self.active_threads()
+    }
+}
+
+// Owns the worker threads searching for an address
+// Workers are running in a dedicated pool until `stop` is called
+pub struct Searcher {
+    pool: ThreadPool,
+    threads: usize,
+    stop: Arc<AtomicBool>,
+    workers: Arc<Vec<WorkerState>>,
+    control: Arc<SearchControl>,
+    // Each worker owns a sender, the channel is closed once they all exited
+    done: Option<mpsc::Receiver<()>>,
+    // Thread calling the progress callback
+    timer: Option<thread::JoinHandle<()>>,
+}
+
+impl Searcher {
+    // With `cores`, the thread `i` is pinned to the core `cores[i]`
+    // With `low_priority`, the threads are running at the lowest scheduling priority
+    pub fn new(threads: usize, cores: Option<Vec<usize>>, low_priority: bool) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("worker-{}", i))
+            .panic_handler(|panic| error!("A worker panicked: {}", panic_message(&*panic)))
+            .start_handler(move |i| {
+                if let Some(cores) = &cores {
+                    pin_worker(i, cores[i]);
+                }
+
+                if low_priority {
+                    lower_worker_priority(i);
+                }
+            })
+            .build()?;
+
+        Ok(Self {
+            pool,
+            threads,
+            stop: Arc::new(AtomicBool::new(false)),
+            workers: Arc::new((0..threads).map(|_| WorkerState::default()).collect()),
+            control: Arc::new(SearchControl::new(threads)),
+            done: None,
+            timer: None,
+        })
+    }
+
+    // Start a worker on each thread of the pool
+    pub fn start(&mut self, mut context: SearchContext) {
+        if let Some((interval, callback)) = context.on_progress.take() {
+            let (stop, workers) = (Arc::clone(&self.stop), Arc::clone(&self.workers));
+            self.timer = Some(thread::spawn(move || report_progress(&stop, &workers, interval, callback)));
+        }
+
+        self.control.derived.store(context.derivation.is_some(), Ordering::Relaxed);
+        let context = Arc::new(context);
+        let (sender, receiver) = mpsc::channel();
+        for i in 0..self.threads {
+            let context = Arc::clone(&context);
+            let stop = Arc::clone(&self.stop);
+            let sender = sender.clone();
+            let threads = self.threads;
+            let workers = Arc::clone(&self.workers);
+            let control = Arc::clone(&self.control);
+            self.pool.spawn(move || {
+                let state = &workers[i];
+                debug!("Worker #{} started", i);
+                for restart in 0..=MAX_RESTARTS {
+                    match panic::catch_unwind(AssertUnwindSafe(|| search_for(&context, &stop, &control, i, threads, restart, state))) {
+                        Ok(()) => break,
+                        Err(panic) => {
+                            error!("Worker #{} panicked: {}", i, panic_message(&*panic));
+                            if restart == MAX_RESTARTS || stop.load(Ordering::Relaxed) {
+                                error!("Worker #{} is not restarted, the search continues with less threads", i);
+                                break;
+                            }
+
+                            warn!("Restarting worker #{}", i);
+                            state.restarts.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                }
+
+                state.exited.store(true, Ordering::Relaxed);
+                debug!("Worker #{} stopped after {} keys", i, state.attempts.load(Ordering::Relaxed));
+                drop(sender);
+            });
+        }
+
+        self.done = Some(receiver);
+    }
+
+    // State of each worker, indexed by thread
+    pub fn workers(&self) -> Arc<Vec<WorkerState>> {
+        Arc::clone(&self.workers)
+    }
+
+    // Pause the workers or change the number of working threads while they run
+    pub fn control(&self) -> Arc<SearchControl> {
+        Arc::clone(&self.control)
+    }
+
+    // Check if all the workers exited by themselves
+    // This happens once a derivation reached its end index
+    pub fn is_finished(&self) -> bool {
+        self.done.as_ref()
+            .is_some_and(|done| matches!(done.try_recv(), Err(mpsc::TryRecvError::Disconnected)))
+    }
+
+    // Ask all the workers to stop and wait for them to exit
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(done) = self.done.take() {
+            // Returns an error once all the senders are dropped
+            while done.recv().is_ok() {}
+        }
+
+        if let Some(timer) = self.timer.take() {
+            if timer.join().is_err() {
+                error!("The progress callback panicked");
+            }
+        }
+    }
+}
+
+// Longest sleep of the progress timer, so it notices the stop quickly
+const TIMER_SLEEP: Duration = Duration::from_millis(100);
+
+// Call the progress callback at each interval until the searcher is stopped
+// The keys are the ones of this searcher only, not the process-wide counter
+fn report_progress(stop: &AtomicBool, workers: &[WorkerState], interval: Duration, callback: ProgressCallback) {
+    let started = Instant::now();
+    let (mut last_time, mut last_attempts) = (started, 0);
+    loop {
+        let deadline = last_time + interval;
+        while Instant::now() < deadline {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+
+            thread::sleep(TIMER_SLEEP.min(deadline.saturating_duration_since(Instant::now())));
+        }
+
+        let attempts = workers.iter().map(|worker| worker.attempts.load(Ordering::Relaxed)).sum::<u64>();
+        let now = Instant::now();
+        let rate = (attempts - last_attempts) as f64 / now.duration_since(last_time).as_secs_f64();
+        callback(attempts, rate, now.duration_since(started));
+        (last_time, last_attempts) = (now, attempts);
+    }
+}
+
+// Pin a worker to its core, it keeps running unpinned on failure
+pub fn pin_worker(thread: usize, core: usize) {
+    if let Err(e) = affinity::pin_current_thread(core) {
+        warn!("Couldn't pin thread #{} to core {}: {}", thread, core, e);
+    }
+}
+
+// Interval in which a throttled worker works then sleeps
+const THROTTLE_INTERVAL: Duration = Duration::from_millis(100);
+
+// Keeps a worker busy only `percent` of the time
+// The time is only checked between two batches so the overhead is negligible
+struct Throttle {
+    percent: u32,
+    start: Instant,
+}
+
+impl Throttle {
+    fn new(percent: u32) -> Self {
+        Self {
+            percent,
+            start: Instant::now(),
+        }
+    }
+
+    // Sleep once the work slice of the interval is over
+    // The sleep is proportional to the time really spent working
+    fn tick(&mut self) {
+        let elapsed = self.start.elapsed();
+        if elapsed >= THROTTLE_INTERVAL * self.percent / 100 {
+            thread::sleep(elapsed * (100 - self.percent) / self.percent);
+            self.start = Instant::now();
+        }
+    }
+}
+
+// Interval of the batch timings of each worker, logged at the debug level
+const BATCH_TIMING_INTERVAL: Duration = Duration::from_secs(10);
+
+// Time spent by a worker in its batches, without its pauses and its throttle
+struct BatchTiming {
+    // Measured only at the debug level
+    enabled: bool,
+    batches: u32,
+    busy: Duration,
+    last: Instant,
+}
+
+impl BatchTiming {
+    fn new() -> Self {
+        Self {
+            enabled: log_enabled!(Level::Debug),
+            batches: 0,
+            busy: Duration::ZERO,
+            last: Instant::now(),
+        }
+    }
+
+    fn record(&mut self, thread: usize, started: Instant) {
+        if !self.enabled {
+            return;
+        }
+
+        self.batches += 1;
+        self.busy += started.elapsed();
+        if self.last.elapsed() >= BATCH_TIMING_INTERVAL {
+            debug!("Thread #{}: {} batches in {:.1}s, {}µs per batch", thread, self.batches, self.busy.as_secs_f64(), self.busy.as_micros() / self.batches as u128);
+            (self.batches, self.busy, self.last) = (0, Duration::ZERO, Instant::now());
+        }
+    }
+}
+
+// Lower the priority of a worker, it keeps running at normal priority on failure
+fn lower_worker_priority(thread: usize) {
+    match priority::lower_current_thread() {
+        // Same for all the threads, only log it once
+        Ok(applied) => if thread == 0 {
+            info!("Worker threads are running with the {}", applied);
+        },
+        Err(e) => warn!("Couldn't lower the priority of thread #{}: {}, running at normal priority", thread, e),
+    }
+}
+
+// Extract the message of a panic payload
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown cause"
+    }
+}
+
+// `restart` is the number of times the worker panicked, it doesn't generate the same keys again
+fn search_for(context: &SearchContext, stop: &AtomicBool, control: &SearchControl, thread: usize, threads: usize, restart: usize, state: &WorkerState) {
+    let mut generator = match (&context.derivation, context.rng_seed) {
+        (Some(derivation), _) => KeyGenerator::derived(derivation, thread, threads, context.batch_size),
+        (None, Some(seed)) => KeyGenerator::seeded(seed, thread, restart, context.batch_size),
+        (None, None) => KeyGenerator::new(context.fast_rng, context.batch_size),
+    };
+    // The batch interrupted by the panic is searched again
+    if restart > 0 {
+        generator.resume(state.next_index.load(Ordering::Relaxed));
+    }
+    let mut tweaks = context.split_key.map(TweakGenerator::new);
+    let mut encoder = AddressEncoder::new(context.network);
+    let mut throttle = context.throttle.map(Throttle::new);
+    let mut timing = BatchTiming::new();
+    let mut keys = Vec::with_capacity(context.batch_size);
+    let mut multiples = Vec::with_capacity(context.batch_size);
+    let mut batch = Vec::with_capacity(context.batch_size);
+    let mut attempts = 0;
+    while !stop.load(Ordering::Relaxed) {
+        if control.is_waiting(thread) {
+            state.waiting.store(true, Ordering::Relaxed);
+            while control.is_waiting(thread) && !stop.load(Ordering::Relaxed) {
+                thread::sleep(WAIT_INTERVAL);
+            }
+            state.waiting.store(false, Ordering::Relaxed);
+
+            // The wait is not some work to compensate
+            throttle = context.throttle.map(Throttle::new);
+            continue;
+        }
+
+        let batch_started = Instant::now();
+        // Derived keys of the batch are `threads` indexes apart
+        let first_index = generator.next_index();
+        if let Some(first) = first_index {
+            state.next_index.store(first, Ordering::Relaxed);
+        }
+        match &mut tweaks {
+            Some(tweaks) => {
+                tweaks.generate_batch(&mut multiples, context.batch_size);
+                batch.extend(multiples.drain(..).map(|(multiplier, public_key)| FoundKey::Tweak { multiplier, public_key }));
+            },
+            None => {
+                generator.generate_batch(&mut keys);
+                batch.extend(keys.drain(..).map(FoundKey::KeyPair));
+            }
+        }
+        // The derivation reached its end
+        if batch.is_empty() {
+            break;
+        }
+
+        let count = batch.len();
+        for (attempts_in_batch, found_key) in (1..).zip(batch.drain(..)) {
+            attempts += 1;
+            let key = found_key.public_key();
+            if let Some(filter) = &context.filter {
+                if !filter.is_matching(&key) {
+                    // Only encode the address of a better partial match
+                    if let Some((index, len)) = filter.partial(&key).filter(|(index, len)| *len > context.best.len(*index)) {
+                        let address = encoder.encode_key(&key, context.payment_id.map(|v| v.generate()));
+                        context.best.offer(index, len, address);
+                    }
+
+                    // Rejected on the public key, the address is only encoded for the sample
+                    if let Some(sampler) = context.sampler.as_ref().filter(|sampler| sampler.is_wanted()) {
+                        sampler.offer(encoder.encode_key(&key, context.payment_id.map(|v| v.generate())));
+                    }
+                    continue;
+                }
+            }
+
+            let id = context.payment_id.map(|v| v.generate());
+            let address = encoder.encode_key(&key, id);
+            // A single key per tick, it's practically never a match
+            if let Some(sampler) = context.sampler.as_ref().filter(|sampler| sampler.is_wanted()) {
+                sampler.offer(address);
+            }
+            if context.only.as_ref().is_some_and(|only| !only.is_matching(address)) {
+                continue;
+            }
+
+            let (content, placement, range, distance) = match &context.matcher {
+                AddressMatcher::Patterns(patterns) => {
+                    let area = matcher::match_area(address, context.suffix_end);
+                    let active = |index: usize| context.quotas.as_ref().map_or(true, |quotas| !quotas.is_filled(index));
+                    let found = patterns.find_where(area, active)
+                        .or_else(|| (context.fuzzy > 0).then(|| patterns.find_fuzzy(area, context.fuzzy, active)).flatten());
+
+                    let Some(found) = found else {
+                        if let Some((index, len)) = patterns.partial(area) {
+                            context.best.offer(index, len, address);
+                        }
+                        continue;
+                    };
+
+                    // Another worker may have filled the quota in the meantime
+                    if context.quotas.as_ref().is_some_and(|quotas| !quotas.claim(found.index)) {
+                        continue;
+                    }
+
+                    (patterns.contents()[found.index].clone(), Some(found.placement), found.range, found.distance)
+                },
+                AddressMatcher::LongestRun(record) => {
+                    let start = matcher::data_start(context.network);
+                    let (offset, len) = pretty::longest_run(&address.as_bytes()[start..]);
+                    if len <= record.len() || !record.raise(len, address) {
+                        continue;
+                    }
+
+                    let range = start + offset..start + offset + len;
+                    (address[range.clone()].to_string(), None, range, 0)
+                },
+                AddressMatcher::Palindrome { len, placements } => {
+                    let area = matcher::match_area(address, context.suffix_end);
+                    let found = placements.iter().find_map(|placement| {
+                        let range = match placement {
+                            Placement::Suffix => area.len() - len..area.len(),
+                            _ => {
+                                let start = matcher::data_start(context.network);
+                                start..start + len
+                            },
+                        };
+
+                        pretty::is_palindrome(&area.as_bytes()[range.clone()]).then_some((*placement, range))
+                    });
+
+                    let Some((placement, range)) = found else {
+                        continue;
+                    };
+
+                    (address[range.clone()].to_string(), Some(placement), range, 0)
+                },
+                AddressMatcher::CharsetScore { record, subset, placements } => {
+                    let start = matcher::data_start(context.network);
+                    let data = &matcher::match_area(address, context.suffix_end).as_bytes()[start..];
+                    let best = placements.iter()
+                        .map(|placement| (*placement, subset.score(data, *placement)))
+                        .max_by_key(|(_, range)| range.len());
+
+                    let Some((placement, range)) = best else {
+                        continue;
+                    };
+
+                    if range.len() <= record.len() || !record.raise(range.len(), address) {
+                        continue;
+                    }
+
+                    let range = start + range.start..start + range.end;
+                    (address[range.clone()].to_string(), Some(placement), range, 0)
+                },
+                AddressMatcher::DigitsSuffix { min_len } => {
+                    let area = matcher::match_area(address, context.suffix_end);
+                    let len = pretty::trailing_digits(&area.as_bytes()[matcher::data_start(context.network)..]);
+                    if len < *min_len {
+                        continue;
+                    }
+
+                    // The whole run is reported, it can be longer than requested
+                    let range = area.len() - len..area.len();
+                    (address[range.clone()].to_string(), Some(Placement::Suffix), range, 0)
+                },
+                // Nothing to highlight in the address
+                AddressMatcher::Custom(accept) if accept(address) => (String::new(), None, 0..0, 0),
+                AddressMatcher::Custom(_) => continue,
+            };
+
+            let result = FoundResult {
+                address: address.to_string(),
+                key: found_key,
+                payment_id: id,
+                index: first_index.map(|first| first + (attempts_in_batch - 1) * threads as u64),
+                content,
+                placement,
+                range,
+                distance,
+                thread,
+                attempts,
+                timestamp: get_current_time_in_millis(),
+            };
+
+            // The reporter is doing all the work, go back to searching immediately
+            if context.sender.send(result).is_err() {
+                error!("Thread #{} couldn't send its result, the reporter is stopped", thread);
+            }
+        }
+
+        RATE_COUNTER.fetch_add(count, Ordering::Relaxed);
+        TOTAL_ATTEMPTS.fetch_add(count as u64, Ordering::Relaxed);
+        state.attempts.fetch_add(count as u64, Ordering::Relaxed);
+        if let (Some(progress), Some(next)) = (&context.progress, generator.next_index()) {
+            progress.set(thread, next);
+        }
+        timing.record(thread, batch_started);
+
+        if let Some(throttle) = &mut throttle {
+            throttle.tick();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::{
+        derivation::{self, MASTER_SIZE},
+        keys
+    };
+    use super::*;
+
+    #[test]
+    fn test_restarted_worker_resumes_its_derivation() {
+        const KEYS: u64 = 40;
+        const BATCH_SIZE: usize = 4;
+
+        // Panics once, in the middle of the third batch
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let (calls, addresses) = (Arc::new(AtomicUsize::new(0)), Arc::clone(&seen));
+        let (sender, _receiver) = channel::unbounded_channel();
+        let mut context = SearchContext::with_matcher(Network::Mainnet, move |address| {
+            if calls.fetch_add(1, Ordering::Relaxed) == 2 * BATCH_SIZE + 1 {
+                panic!("injected panic");
+            }
+            addresses.lock().unwrap().push(address.to_string());
+            false
+        }, sender);
+        let master = [7u8; MASTER_SIZE];
+        context.derivation = Some(Derivation { master, start: 0, end: Some(KEYS) });
+        context.batch_size = BATCH_SIZE;
+
+        let mut searcher = Searcher::new(1, None, false).unwrap();
+        let workers = searcher.workers();
+        searcher.start(context);
+        while !searcher.is_finished() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        searcher.stop();
+
+        assert_eq!(workers[0].restarts.load(Ordering::Relaxed), 1);
+        let seen = seen.lock().unwrap();
+        // Only the keys of the interrupted batch before the panic are searched twice
+        assert_eq!(seen.len() as u64, KEYS + 1);
+
+        let expected = (0..KEYS).map(|index| {
+            let keypair = keys::keypair_from_entropy(derivation::derive_entropy(&master, index));
+            keys::derive_address(&keypair, Network::Mainnet, None)
+        }).collect::<HashSet<_>>();
+        assert_eq!(seen.iter().cloned().collect::<HashSet<_>>(), expected);
+    }
+
+    #[test]
+    fn test_prefix_starts_after_the_separator() {
+        let found = search_blocking("ac", Placement::Prefix, Network::Mainnet, 1, &AtomicBool::new(false)).unwrap().unwrap();
+        assert!(found.address.starts_with("xel:ac"), "{}", found.address);
+        assert_eq!(found.content, "ac");
+    }
+
+    #[test]
+    fn test_restarted_seeded_worker_gets_new_keys() {
+        let mut keys = Vec::new();
+        let mut first = KeyGenerator::seeded(42, 0, 0, 8);
+        first.generate_batch(&mut keys);
+        let first = keys.iter().map(|key| key.get_public_key().compress()).collect::<Vec<_>>();
+
+        KeyGenerator::seeded(42, 0, 1, 8).generate_batch(&mut keys);
+        assert!(keys.iter().all(|key| !first.contains(&key.get_public_key().compress())));
+    }
+
+    #[test]
+    fn test_validate_content() {
+        assert!(validate_content("cafe", Placement::Prefix, Network::Mainnet).is_ok());
+        assert!(validate_content("aa", Placement::Suffix, Network::Mainnet).is_ok());
+        assert!(validate_content("", Placement::Prefix, Network::Mainnet).is_err());
+        assert!(validate_content("b0b", Placement::Suffix, Network::Mainnet).unwrap_err().starts_with("Invalid character"));
+
+        let data_len = AddressShape::of(Network::Testnet).data_len(None);
+        assert!(validate_content(&"q".repeat(data_len), Placement::Suffix, Network::Testnet).is_ok());
+        assert!(validate_content(&"q".repeat(data_len + 1), Placement::Suffix, Network::Testnet).unwrap_err().contains("longer than"));
+
+        // No public key has 'a' as second data character
+        assert!(validate_content("aa", Placement::Prefix, Network::Mainnet).unwrap_err().contains("data character 2"));
+    }
+
+    #[test]
+    fn test_search_blocking_refuses_an_impossible_content() {
+        let cancel = AtomicBool::new(false);
+        assert!(search_blocking("aa", Placement::Prefix, Network::Mainnet, 1, &cancel).is_err());
+        assert!(search_blocking("xel:", Placement::Prefix, Network::Mainnet, 1, &cancel).is_err());
+    }
+}
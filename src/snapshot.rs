@@ -0,0 +1,91 @@
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Instant
+};
+use crate::{
+    best::BestMatches,
+    estimate,
+    lifetime::Lifetime,
+    searcher::WorkerState,
+    stats::{self, RATE_HISTORY, RATE_TREND, TOTAL_ATTEMPTS, TOTAL_MATCHES}
+};
+
+// Bars of the sparkline of the last hour
+const SPARKLINE_WIDTH: usize = 60;
+
+// Statistics of the running search, shown by the `stats` command and logged on SIGUSR1
+// Only addresses are shown, never a key
+pub struct Snapshot {
+    started: Instant,
+    workers: Arc<Vec<WorkerState>>,
+    best: Arc<BestMatches>,
+    // Unknown with `--disable-state-file`
+    lifetime: Option<Arc<Lifetime>>,
+}
+
+impl Snapshot {
+    pub fn new(workers: Arc<Vec<WorkerState>>, best: Arc<BestMatches>, lifetime: Option<Arc<Lifetime>>) -> Self {
+        Self {
+            started: Instant::now(),
+            workers,
+            best,
+            lifetime,
+        }
+    }
+
+    // Lines of the snapshot, each one is logged or shown on its own
+    pub async fn lines(&self) -> Vec<String> {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let attempts = TOTAL_ATTEMPTS.load(Ordering::Relaxed);
+        let mut lines = vec![format!(
+            "Uptime {}, {} keys tried ({}), {} found",
+            estimate::format_duration(elapsed),
+            attempts,
+            stats::format_count(attempts),
+            TOTAL_MATCHES.load(Ordering::Relaxed)
+        )];
+
+        let peak = {
+            let history = RATE_HISTORY.lock().await;
+            lines.push(format!("Hashrate: current {}, avg {}, peak {}", stats::format_rate(history.last()), stats::format_rate(history.average()), stats::format_rate(history.peak())));
+            history.peak()
+        };
+
+        {
+            let trend = RATE_TREND.lock().await;
+            if trend.count() == 0 {
+                lines.push("No hashrate measured yet".to_string());
+            } else {
+                lines.push(format!("Hashrate of the last {} ticks: min {}, avg {}, max {}", trend.count(), stats::format_rate(trend.min()), stats::format_rate(trend.average()), stats::format_rate(trend.max())));
+                lines.push(trend.sparkline(SPARKLINE_WIDTH));
+            }
+        }
+
+        // Average of each thread since the start, a slow or stopped one stands out
+        for (index, worker) in self.workers.iter().enumerate() {
+            let attempts = worker.attempts.load(Ordering::Relaxed);
+            let state = if worker.exited.load(Ordering::Relaxed) {
+                " (exited)"
+            } else if worker.waiting.load(Ordering::Relaxed) {
+                " (waiting)"
+            } else {
+                ""
+            };
+            lines.push(format!("Thread #{}: {} keys, {}{}", index, stats::format_count(attempts), stats::format_rate(attempts as f64 / elapsed), state));
+        }
+
+        match self.best.snapshot().first() {
+            Some((content, len, address)) => lines.push(format!("Closest partial match: {} {}/{} characters in {}", content, len, content.len(), address)),
+            None => lines.push("No partial match yet (only tracked for prefixes and suffixes)".to_string()),
+        }
+
+        if let Some(lifetime) = &self.lifetime {
+            let session = lifetime.session(peak);
+            lines.push(format!("Session: {}", session.summary()));
+            let total = lifetime.total(&session);
+            lines.push(format!("Lifetime: {} over {} searches", total.summary(), total.runs));
+        }
+
+        lines
+    }
+}
@@ -0,0 +1,124 @@
+use curve25519_dalek::{
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar
+};
+use rand::{rngs::OsRng, RngCore};
+use xelis_common::{
+    crypto::{KeyPair, PrivateKey},
+    serializer::Serializer
+};
+
+// Split-key search, see `--split-key`
+// XELIS public keys are P = s^-1 * H for a private key s. The customer keeping s
+// multiplies it by a tweak t: the public key of s * t is t^-1 * P, computed from P alone.
+// The workers search for the multiplier m = t^-1 and only the tweak is reported,
+// so nobody but the customer ever holds the combined private key.
+
+// Size of a compressed public key, a private key or a tweak in bytes
+const KEY_SIZE: usize = 32;
+
+fn parse_hex(value: &str, name: &str) -> Result<[u8; KEY_SIZE], String> {
+    let value = value.trim();
+    if let Some(position) = value.chars().position(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("invalid hex character in the {} at position {}", name, position));
+    }
+
+    if value.len() != KEY_SIZE * 2 {
+        return Err(format!("expected {} hex characters for the {}, got {}", KEY_SIZE * 2, name, value.len()));
+    }
+
+    let mut bytes = [0u8; KEY_SIZE];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16)
+            .map_err(|e| format!("invalid hex byte in the {} at position {}: {}", name, i * 2, e))?;
+    }
+
+    Ok(bytes)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Parse the compressed public key of the customer
+// Used as a clap value parser
+pub fn parse_public_key(value: &str) -> Result<RistrettoPoint, String> {
+    CompressedRistretto(parse_hex(value, "public key")?)
+        .decompress()
+        .ok_or_else(|| "invalid public key: not a valid point".to_string())
+}
+
+// Parse a tweak found by a split-key search
+// Used as a clap value parser
+pub fn parse_tweak(value: &str) -> Result<Scalar, String> {
+    Option::from(Scalar::from_canonical_bytes(parse_hex(value, "tweak")?))
+        .filter(|tweak: &Scalar| *tweak != Scalar::ZERO)
+        .ok_or_else(|| "invalid tweak: not a canonical non-zero scalar".to_string())
+}
+
+// Tweak to send to the customer for a multiplier found by the workers
+pub fn format_tweak(multiplier: &Scalar) -> String {
+    to_hex(multiplier.invert().as_bytes())
+}
+
+// Private key of the customer combined with the tweak
+pub fn combine(private_key: &PrivateKey, tweak: &Scalar) -> Result<PrivateKey, String> {
+    let mut bytes = [0u8; KEY_SIZE];
+    bytes.copy_from_slice(&private_key.to_bytes());
+    let scalar: Scalar = Option::from(Scalar::from_canonical_bytes(bytes))
+        .ok_or_else(|| "private key is not a canonical scalar".to_string())?;
+
+    PrivateKey::from_bytes(&(scalar * tweak).to_bytes())
+        .map_err(|e| format!("invalid combined private key: {}", e))
+}
+
+// Check the relation between the private and the public keys on a random key
+// The multiplied public key must be the one of the combined private key
+pub fn self_check() -> bool {
+    let keypair = KeyPair::new();
+    let Ok(public_key) = parse_public_key(&to_hex(keypair.get_public_key().compress().as_bytes())) else {
+        return false;
+    };
+
+    let multiplier = random_scalar();
+    let Ok(combined) = combine(keypair.get_private_key(), &multiplier.invert()) else {
+        return false;
+    };
+
+    KeyPair::from_private_key(combined).get_public_key().compress().as_bytes() == (multiplier * public_key).compress().as_bytes()
+}
+
+pub(crate) fn random_scalar() -> Scalar {
+    let mut bytes = [0u8; KEY_SIZE * 2];
+    OsRng.fill_bytes(&mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+// Public keys searched by a worker: consecutive multiples of the customer public key
+// from a random multiplier, so each key only costs a point addition
+pub struct TweakGenerator {
+    base: RistrettoPoint,
+    multiplier: Scalar,
+    point: RistrettoPoint,
+}
+
+impl TweakGenerator {
+    pub fn new(base: RistrettoPoint) -> Self {
+        let multiplier = random_scalar();
+        Self {
+            base,
+            multiplier,
+            point: multiplier * base,
+        }
+    }
+
+    // Replace the content of `keys` by the next multipliers and their compressed public keys
+    pub fn generate_batch(&mut self, keys: &mut Vec<(Scalar, [u8; KEY_SIZE])>, batch_size: usize) {
+        keys.clear();
+        for _ in 0..batch_size {
+            self.multiplier += Scalar::ONE;
+            self.point += self.base;
+            keys.push((self.multiplier, self.point.compress().to_bytes()));
+        }
+    }
+}
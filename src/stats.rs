@@ -0,0 +1,292 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        OnceLock
+    },
+    time::Instant
+};
+use lazy_static::lazy_static;
+use xelis_common::{tokio::sync::Mutex, utils::format_hashrate};
+use crate::config::RateUnit;
+
+// Number of ticks used for the rolling average
+pub const RATE_HISTORY_SIZE: usize = 10;
+// Number of ticks kept for the sparkline and the `stats` command, an hour of prompt refreshes
+pub const RATE_TREND_SIZE: usize = 3600;
+
+// Bars of the sparkline, from the lowest rate to the highest
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Keys generated since the previous rate sample, swapped to zero by each sample
+pub static RATE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+// Keys generated by all the workers since the start, never reset
+pub static TOTAL_ATTEMPTS: AtomicU64 = AtomicU64::new(0);
+// Addresses reported since the start
+pub static TOTAL_MATCHES: AtomicU64 = AtomicU64::new(0);
+// Unit of the rates, set once from `--rate-unit` before any is logged
+static RATE_UNIT: OnceLock<RateUnit> = OnceLock::new();
+lazy_static! {
+    pub static ref RATE_LAST_TIME: Mutex<Instant> = Mutex::new(Instant::now());
+    pub static ref RATE_HISTORY: Mutex<RateHistory> = Mutex::new(RateHistory::new(RATE_HISTORY_SIZE));
+    pub static ref RATE_TREND: Mutex<RateHistory> = Mutex::new(RateHistory::new(RATE_TREND_SIZE));
+}
+
+// Measure the rate since the previous call and add it to the history
+// Returns the current, average and peak rates
+pub async fn sample_rate() -> (f64, f64, f64) {
+    let mut last_time = RATE_LAST_TIME.lock().await;
+    let counter = RATE_COUNTER.swap(0, Ordering::Relaxed);
+
+    let hashrate = 1000f64 / (last_time.elapsed().as_millis() as f64 / counter as f64);
+    *last_time = Instant::now();
+
+    RATE_TREND.lock().await.push(hashrate);
+    let mut history = RATE_HISTORY.lock().await;
+    history.push(hashrate);
+
+    (hashrate, history.average(), history.peak())
+}
+
+pub fn set_rate_unit(unit: RateUnit) {
+    let _ = RATE_UNIT.set(unit);
+}
+
+// Format a rate in the unit of `--rate-unit`, scaled to the rate by default
+pub fn format_rate(rate: f64) -> String {
+    match RATE_UNIT.get().and_then(RateUnit::scale) {
+        Some((size, name)) => format!("{:.2} {}", rate / size, name),
+        None => format_hashrate(rate),
+    }
+}
+
+// Format a number of keys with a unit, like 1.2B
+pub fn format_count(count: u64) -> String {
+    const UNITS: [(&str, f64); 4] = [
+        ("T", 1e12),
+        ("B", 1e9),
+        ("M", 1e6),
+        ("K", 1e3),
+    ];
+
+    for (unit, size) in UNITS {
+        if count as f64 >= size {
+            return format!("{:.1}{}", count as f64 / size, unit);
+        }
+    }
+
+    count.to_string()
+}
+
+// Ring buffer of the last rates measured, one per tick
+pub struct RateHistory {
+    samples: VecDeque<f64>,
+    capacity: usize,
+    // Highest rate seen since the start or the last reset
+    peak: f64,
+}
+
+impl RateHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+            peak: 0f64,
+        }
+    }
+
+    // Add a new sample, dropping the oldest one if full
+    pub fn push(&mut self, rate: f64) {
+        if !rate.is_finite() {
+            return;
+        }
+
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(rate);
+
+        if rate > self.peak {
+            self.peak = rate;
+        }
+    }
+
+    // Average of the samples in the buffer
+    pub fn average(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0f64;
+        }
+
+        self.samples.iter().sum::<f64>() / self.samples.len() as f64
+    }
+
+    pub fn peak(&self) -> f64 {
+        self.peak
+    }
+
+    // Lowest and highest samples in the buffer
+    pub fn min(&self) -> f64 {
+        self.samples.iter().copied().reduce(f64::min).unwrap_or(0f64)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.samples.iter().copied().reduce(f64::max).unwrap_or(0f64)
+    }
+
+    // Number of samples in the buffer
+    pub fn count(&self) -> usize {
+        self.samples.len()
+    }
+
+    // Bars of the samples from the oldest to the latest, at most `width`: the samples are averaged by groups
+    // The bars start at zero, so a small variation of the rate doesn't look like a drop
+    pub fn sparkline(&self, width: usize) -> String {
+        let max = self.max();
+        let group = self.samples.len().div_ceil(width.max(1)).max(1);
+        self.samples.iter()
+            .copied()
+            .collect::<Vec<_>>()
+            .chunks(group)
+            .map(|rates| {
+                let rate = rates.iter().sum::<f64>() / rates.len() as f64;
+                let level = if max > 0f64 { (rate / max * (SPARKLINE_BARS.len() - 1) as f64).round() as usize } else { 0 };
+                SPARKLINE_BARS[level.min(SPARKLINE_BARS.len() - 1)]
+            })
+            .collect()
+    }
+
+    // Most recent sample, the rate of the last tick
+    pub fn last(&self) -> f64 {
+        self.samples.back().copied().unwrap_or(0f64)
+    }
+
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.peak = 0f64;
+    }
+}
+
+// Addresses kept for the summary at the end of a search, the next ones are only counted
+pub const MAX_SUMMARY_ADDRESSES: usize = 100;
+
+// Keys generated between two matches, sampled from the total attempts when a match is reported
+#[derive(Default)]
+pub struct MatchStats {
+    // Total attempts at the previous match
+    last: u64,
+    count: usize,
+    sum: u64,
+    min: u64,
+    max: u64,
+    // Matches of each content, with the total attempts at the last one
+    contents: BTreeMap<String, (usize, u64)>,
+    // First addresses found, never their keys
+    addresses: Vec<String>,
+}
+
+impl MatchStats {
+    // Record a match, returns the keys generated since the previous one
+    // The address is None when it's excluded by `--output-fields`
+    // A total below the previous one counts no keys instead of going back
+    pub fn record(&mut self, content: &str, address: Option<&str>, total_attempts: u64) -> u64 {
+        if let Some(address) = address.filter(|_| self.addresses.len() < MAX_SUMMARY_ADDRESSES) {
+            self.addresses.push(address.to_string());
+        }
+
+        let total_attempts = total_attempts.max(self.last);
+        let since = total_attempts - self.last;
+        self.last = total_attempts;
+
+        let entry = self.contents.entry(content.to_string()).or_default();
+        entry.0 += 1;
+        entry.1 = total_attempts;
+
+        if self.count == 0 || since < self.min {
+            self.min = since;
+        }
+        if since > self.max {
+            self.max = since;
+        }
+        self.sum += since;
+        self.count += 1;
+
+        since
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            return 0f64;
+        }
+
+        self.sum as f64 / self.count as f64
+    }
+
+    pub fn min(&self) -> u64 {
+        self.min
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn addresses(&self) -> &[String] {
+        &self.addresses
+    }
+
+    // Content, its number of matches and the total attempts at the last one
+    pub fn contents(&self) -> impl Iterator<Item = (&str, usize, u64)> {
+        self.contents.iter().map(|(content, (count, attempts))| (content.as_str(), *count, *attempts))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_stats() {
+        let mut stats = MatchStats::default();
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.mean(), 0f64);
+
+        assert_eq!(stats.record("cafe", Some("xel:cafe1"), 100), 100);
+        assert_eq!(stats.record("dog", Some("xel:dog1"), 130), 30);
+        assert_eq!(stats.record("cafe", None, 330), 200);
+        assert_eq!(stats.count(), 3);
+        assert_eq!(stats.mean(), 110f64);
+        assert_eq!(stats.min(), 30);
+        assert_eq!(stats.max(), 200);
+
+        // The addresses excluded by `--output-fields` are only counted
+        assert_eq!(stats.addresses(), ["xel:cafe1", "xel:dog1"]);
+        assert_eq!(stats.contents().collect::<Vec<_>>(), [("cafe", 2, 330), ("dog", 1, 130)]);
+    }
+
+    #[test]
+    fn test_match_stats_never_go_back() {
+        let mut stats = MatchStats::default();
+        stats.record("cafe", None, 500);
+        // Sampled before the previous match, no keys were generated in between
+        assert_eq!(stats.record("cafe", None, 450), 0);
+        assert_eq!(stats.record("cafe", None, 600), 100);
+        assert_eq!(stats.min(), 0);
+        assert_eq!(stats.max(), 500);
+        assert_eq!(stats.mean(), 200f64);
+        assert_eq!(stats.contents().next(), Some(("cafe", 3, 600)));
+    }
+
+    #[test]
+    fn test_match_stats_keep_the_first_addresses() {
+        let mut stats = MatchStats::default();
+        for i in 0..MAX_SUMMARY_ADDRESSES + 10 {
+            stats.record("cafe", Some(&format!("xel:cafe{}", i)), i as u64);
+        }
+        assert_eq!(stats.count(), MAX_SUMMARY_ADDRESSES + 10);
+        assert_eq!(stats.addresses().len(), MAX_SUMMARY_ADDRESSES);
+        assert_eq!(stats.addresses()[0], "xel:cafe0");
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration
+};
+#[cfg(target_os = "linux")]
+use std::{
+    env,
+    os::{linux::net::SocketAddrExt, unix::net::{SocketAddr, UnixDatagram}},
+    process
+};
+use log::warn;
+use xelis_common::tokio;
+use crate::{
+    searcher::WorkerState,
+    stats::{self, RATE_HISTORY, TOTAL_ATTEMPTS, TOTAL_MATCHES}
+};
+
+// Readiness, watchdog and status notifications to systemd, see `--systemd`
+// The messages are datagrams sent to the socket of `NOTIFY_SOCKET`, only on Linux
+
+// Interval of the status updates without a watchdog
+const STATUS_INTERVAL: Duration = Duration::from_secs(10);
+
+#[cfg(target_os = "linux")]
+pub struct Notifier {
+    socket: UnixDatagram,
+    address: SocketAddr,
+    // Timeout of the service watchdog, `WatchdogSec` in the unit
+    watchdog: Option<Duration>,
+}
+
+#[cfg(target_os = "linux")]
+impl Notifier {
+    // Enabled when started by systemd with `Type=notify`, `forced` only warns without it
+    pub fn new(forced: bool) -> Option<Self> {
+        let Some(path) = env::var("NOTIFY_SOCKET").ok().filter(|path| !path.is_empty()) else {
+            if forced {
+                warn!("--systemd is set but NOTIFY_SOCKET is not, the service must have Type=notify");
+            }
+            return None;
+        };
+
+        // A name starting with @ is in the abstract namespace
+        let address = match path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name),
+            None => SocketAddr::from_pathname(&path),
+        };
+        let socket = UnixDatagram::unbound();
+        let (address, socket) = match address.and_then(|address| socket.map(|socket| (address, socket))) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("Couldn't open the systemd notification socket {}: {}", path, e);
+                return None;
+            }
+        };
+
+        // Only for this process, not one of its parents
+        let pid = env::var("WATCHDOG_PID").ok().and_then(|pid| pid.parse::<u32>().ok());
+        let watchdog = env::var("WATCHDOG_USEC").ok()
+            .and_then(|usec| usec.parse().ok())
+            .filter(|_| pid.map_or(true, |pid| pid == process::id()))
+            .map(Duration::from_micros);
+
+        Some(Self { socket, address, watchdog })
+    }
+
+    fn send(&self, message: &str) {
+        if let Err(e) = self.socket.send_to_addr(message.as_bytes(), &self.address) {
+            warn!("Couldn't notify systemd: {}", e);
+        }
+    }
+}
+
+// Compiled out on the other platforms, it's never enabled
+#[cfg(not(target_os = "linux"))]
+pub struct Notifier {
+    watchdog: Option<Duration>,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Notifier {
+    pub fn new(forced: bool) -> Option<Self> {
+        if forced {
+            warn!("--systemd is only available on Linux");
+        }
+        None
+    }
+
+    fn send(&self, _: &str) {}
+}
+
+impl Notifier {
+    // The worker threads are started
+    pub fn ready(&self) {
+        self.send("READY=1");
+    }
+
+    // Sent before stopping the workers
+    pub fn stopping(&self) {
+        self.send("STOPPING=1");
+    }
+
+    fn status(&self, status: &str) {
+        self.send(&format!("STATUS={}", status));
+    }
+
+    fn ping(&self) {
+        self.send("WATCHDOG=1");
+    }
+
+    // Twice per watchdog timeout, so a single late tick doesn't restart the service
+    fn interval(&self) -> Duration {
+        self.watchdog.map_or(STATUS_INTERVAL, |timeout| (timeout / 2).min(STATUS_INTERVAL))
+    }
+}
+
+// Update the status shown by `systemctl status` and ping the watchdog until the task is aborted
+// The watchdog is only pinged while the workers generate keys, so a hung search is restarted
+pub async fn run(notifier: Arc<Notifier>, workers: Arc<Vec<WorkerState>>) {
+    let mut interval = tokio::time::interval(notifier.interval());
+    let mut last = 0;
+    loop {
+        interval.tick().await;
+        let attempts = TOTAL_ATTEMPTS.load(Ordering::Relaxed);
+        // Paused on purpose, see the `pause` command
+        let waiting = workers.iter().any(|worker| worker.waiting.load(Ordering::Relaxed));
+        if notifier.watchdog.is_some() && (attempts != last || waiting) {
+            notifier.ping();
+        }
+        last = attempts;
+
+        let (rate, average) = {
+            let history = RATE_HISTORY.lock().await;
+            (history.last(), history.average())
+        };
+        notifier.status(&format!(
+            "{} (avg: {}) | {} tried | {} found",
+            stats::format_rate(rate),
+            stats::format_rate(average),
+            stats::format_count(attempts),
+            TOTAL_MATCHES.load(Ordering::Relaxed)
+        ));
+    }
+}
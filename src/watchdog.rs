@@ -0,0 +1,50 @@
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration
+};
+use log::{info, warn};
+use xelis_common::tokio;
+use crate::searcher::WorkerState;
+
+// Interval between two checks of the workers
+const TICK: Duration = Duration::from_secs(1);
+// Ticks without any new key before a worker is considered stalled
+const STALL_TICKS: u32 = 5;
+
+// Warn about the workers not generating keys anymore
+// A worker can be stalled by a deadlock, a CPU starved by `--low-priority`, or a bad pinning
+pub async fn run(workers: Arc<Vec<WorkerState>>) {
+    let mut last = vec![0u64; workers.len()];
+    let mut stalled = vec![0u32; workers.len()];
+    let mut interval = tokio::time::interval(TICK);
+    loop {
+        interval.tick().await;
+        for (i, worker) in workers.iter().enumerate() {
+            // Exits are already logged by the searcher
+            if worker.exited.load(Ordering::Relaxed) {
+                continue;
+            }
+
+            // Paused on purpose
+            if worker.waiting.load(Ordering::Relaxed) {
+                stalled[i] = 0;
+                continue;
+            }
+
+            let attempts = worker.attempts.load(Ordering::Relaxed);
+            if attempts != last[i] {
+                if stalled[i] >= STALL_TICKS {
+                    info!("Worker #{} is generating keys again", i);
+                }
+                last[i] = attempts;
+                stalled[i] = 0;
+                continue;
+            }
+
+            stalled[i] += 1;
+            if stalled[i] == STALL_TICKS {
+                warn!("Worker #{} didn't generate any key for {}s, it may be stalled", i, (TICK * STALL_TICKS).as_secs());
+            }
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    collections::VecDeque,
+    future,
+    sync::{atomic::Ordering, Arc},
+    time::{Duration, Instant}
+};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use log::{error, info, warn};
+use xelis_common::{
+    crypto::bech32::CHARSET,
+    tokio::{
+        self,
+        net::{tcp::OwnedWriteHalf, TcpStream},
+        sync::mpsc::{self, UnboundedReceiver}
+    }
+};
+use crate::{
+    best::BestMatches,
+    derivation::{self, Derivation, Progress},
+    protocol::{self, CoordinatorMessage, Job, SealingKey, Submission, WorkerMessage, PROTOCOL_VERSION},
+    searcher::{FoundKey, FoundResult, SearchContext, Searcher},
+    split,
+    stats::TOTAL_ATTEMPTS
+};
+
+// Delay before reconnecting to the coordinator, doubled after each failure
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// Interval between two rate reports to the coordinator
+const RATE_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+// Settings of a worker, see `worker --connect`
+pub struct WorkerOptions {
+    // Address of the coordinator (host:port)
+    pub address: String,
+    // Name shown by the coordinator, its peer address is used without it
+    pub name: Option<String>,
+    pub threads: usize,
+    pub cores: Option<Vec<usize>>,
+    pub low_priority: bool,
+    pub batch_size: usize,
+    // Only accept the jobs sealing the secrets to this key, see `--coordinator-key`
+    pub coordinator_key: Option<RistrettoPoint>,
+}
+
+// Job searched by the local threads
+struct ActiveJob {
+    job: Job,
+    public_key: RistrettoPoint,
+    searcher: Searcher,
+    receiver: UnboundedReceiver<FoundResult>,
+    // Indexes derived by the threads in a derived job, reported to the coordinator
+    progress: Option<Arc<Progress>>,
+    finished: bool,
+}
+
+// Why a connection to the coordinator ended
+enum Disconnect {
+    // The coordinator stopped the search
+    Stopped,
+    // Network error, the worker reconnects
+    Lost(String),
+    // Invalid job, reconnecting would receive it again
+    Fatal(String),
+}
+
+struct Worker {
+    options: WorkerOptions,
+    // Opens the master seed of the derived jobs
+    key: SealingKey,
+    // Key of the coordinator, from `--coordinator-key` or the first job
+    pinned: Option<RistrettoPoint>,
+    active: Option<ActiveJob>,
+    // Results not delivered to the coordinator yet, sent again after reconnecting
+    pending: VecDeque<Submission>,
+    // Total attempts in the last rate report, kept over the reconnections
+    reported: u64,
+}
+
+// Connect to the coordinator and search its jobs until it stops the search
+// A lost connection is retried with a backoff while the last job keeps running locally
+pub async fn run(options: WorkerOptions) -> Result<(), String> {
+    let mut worker = Worker {
+        key: SealingKey::generate(),
+        pinned: options.coordinator_key,
+        options,
+        active: None,
+        pending: VecDeque::new(),
+        reported: 0,
+    };
+
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        match TcpStream::connect(&worker.options.address).await {
+            Ok(stream) => {
+                info!("Connected to the coordinator {}", worker.options.address);
+                match worker.session(stream, &mut backoff).await {
+                    Disconnect::Stopped => {
+                        worker.stop();
+                        return Ok(());
+                    },
+                    Disconnect::Lost(e) => warn!("Connection to the coordinator lost: {}", e),
+                    Disconnect::Fatal(e) => {
+                        worker.stop();
+                        return Err(e);
+                    },
+                }
+            },
+            Err(e) => warn!("Couldn't connect to the coordinator {}: {}", worker.options.address, e),
+        }
+
+        match &worker.active {
+            Some(active) => info!("Reconnecting in {}s, job #{} continues meanwhile", backoff.as_secs(), active.job.id),
+            None => info!("Reconnecting in {}s", backoff.as_secs()),
+        }
+
+        // Keep collecting the results while waiting
+        let deadline = tokio::time::Instant::now() + backoff;
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => break,
+                Some(result) = next_result(&mut worker.active) => worker.submit(result),
+            }
+        }
+
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+// Next result of the active job, never returns without one
+async fn next_result(active: &mut Option<ActiveJob>) -> Option<FoundResult> {
+    match active {
+        Some(active) => active.receiver.recv().await,
+        None => future::pending().await,
+    }
+}
+
+impl Worker {
+    async fn session(&mut self, stream: TcpStream, backoff: &mut Duration) -> Disconnect {
+        let (mut reader, mut writer) = stream.into_split();
+
+        // Reading a message is not cancel safe, so it's done by its own task
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        let reader_task = tokio::spawn(async move {
+            loop {
+                let message = protocol::read_message::<_, CoordinatorMessage>(&mut reader).await;
+                let failed = message.is_err();
+                if sender.send(message).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        let disconnect = self.exchange(&mut writer, &mut messages, backoff).await;
+        reader_task.abort();
+        disconnect
+    }
+
+    async fn exchange(&mut self, writer: &mut OwnedWriteHalf, messages: &mut UnboundedReceiver<Result<CoordinatorMessage, String>>, backoff: &mut Duration) -> Disconnect {
+        let hello = WorkerMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: self.options.name.clone(),
+            threads: self.options.threads,
+            job: self.active.as_ref().map(|active| active.job.id),
+            public_key: self.key.public_key(),
+        };
+        if let Err(e) = protocol::write_message(writer, &hello).await {
+            return Disconnect::Lost(e);
+        }
+        *backoff = MIN_BACKOFF;
+
+        // Results found while disconnected
+        if let Err(e) = self.flush(writer).await {
+            return Disconnect::Lost(e);
+        }
+
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + RATE_REPORT_INTERVAL, RATE_REPORT_INTERVAL);
+        let mut last = (Instant::now(), TOTAL_ATTEMPTS.load(Ordering::Relaxed));
+        loop {
+            tokio::select! {
+                message = messages.recv() => match message {
+                    Some(Ok(CoordinatorMessage::Job(job))) => if let Err(e) = self.start(job) {
+                        return Disconnect::Fatal(e);
+                    },
+                    Some(Ok(CoordinatorMessage::Stop)) => {
+                        info!("The coordinator stopped the search");
+                        return Disconnect::Stopped;
+                    },
+                    Some(Err(e)) => return Disconnect::Lost(e),
+                    None => return Disconnect::Lost("connection closed".to_string()),
+                },
+                Some(result) = next_result(&mut self.active) => {
+                    self.submit(result);
+                    if let Err(e) = self.flush(writer).await {
+                        return Disconnect::Lost(e);
+                    }
+                },
+                _ = interval.tick() => if let Err(e) = self.report_rate(writer, &mut last).await {
+                    return Disconnect::Lost(e);
+                },
+            }
+        }
+    }
+
+    // Start searching a job, the previous one is stopped
+    // The same job sent again after a reconnection keeps running
+    fn start(&mut self, job: Job) -> Result<(), String> {
+        if self.active.as_ref().is_some_and(|active| active.job.is_same(&job)) {
+            info!("Continuing job #{}", job.id);
+            return Ok(());
+        }
+
+        let public_key = split::parse_public_key(&job.public_key)
+            .map_err(|e| format!("invalid job #{}: {}", job.id, e))?;
+        match self.pinned {
+            Some(expected) if expected != public_key => match self.options.coordinator_key {
+                Some(_) => return Err(format!("job #{} is sealed to another key than --coordinator-key", job.id)),
+                None => return Err(format!("job #{} is sealed to another key than the first job, restart the worker if the coordinator was restarted", job.id)),
+            },
+            Some(_) => {},
+            None => {
+                warn!("Trusting the coordinator key {} of the first job, pass it with --coordinator-key on untrusted networks", job.public_key);
+                self.pinned = Some(public_key);
+            }
+        }
+
+        if job.contents.is_empty() || job.contents.len() != job.placements.len() {
+            return Err(format!("invalid job #{}: expected placements for each content", job.id));
+        }
+
+        if let Some(c) = job.contents.iter().flat_map(|content| content.chars()).find(|c| !CHARSET.contains(*c)) {
+            return Err(format!("invalid job #{}: invalid character in content: {}", job.id, c));
+        }
+
+        let derivation = match &job.derivation {
+            Some(range) => Some(Derivation {
+                master: self.key.open(&range.master)
+                    .and_then(|master| derivation::parse_master(&master))
+                    .map_err(|e| format!("invalid job #{}: {}", job.id, e))?,
+                start: range.start,
+                end: Some(range.end),
+            }),
+            None => None,
+        };
+
+        self.stop();
+
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut context = SearchContext::with_patterns(job.network, &job.contents, &job.placements, sender)?;
+        context.best = Arc::new(BestMatches::new(&job.contents));
+        let progress = derivation.map(|derivation| Arc::new(Progress::new(derivation.start, self.options.threads)));
+        context.derivation = derivation;
+        context.progress = progress.clone();
+        context.batch_size = self.options.batch_size;
+
+        let mut searcher = Searcher::new(self.options.threads, self.options.cores.clone(), self.options.low_priority)
+            .map_err(|e| format!("couldn't create the worker threads: {}", e))?;
+        searcher.start(context);
+
+        match &job.derivation {
+            Some(range) => info!("Searching job #{}: {} on {}, indexes {} to {}", job.id, job.contents.join(", "), job.network, range.start, range.end),
+            None => info!("Searching job #{}: {} on {}", job.id, job.contents.join(", "), job.network),
+        }
+
+        self.active = Some(ActiveJob {
+            job,
+            public_key,
+            searcher,
+            receiver,
+            progress,
+            finished: false,
+        });
+
+        Ok(())
+    }
+
+    // Stop the active job, its last results are kept to be sent
+    fn stop(&mut self) {
+        let Some(mut active) = self.active.take() else {
+            return;
+        };
+
+        active.searcher.stop();
+        while let Ok(result) = active.receiver.try_recv() {
+            self.seal(&active, result);
+        }
+    }
+
+    fn submit(&mut self, result: FoundResult) {
+        if let Some(active) = self.active.take() {
+            self.seal(&active, result);
+            self.active = Some(active);
+        }
+    }
+
+    // Encrypt the key of a result to the coordinator and queue it
+    // The key is never logged nor written by the worker
+    fn seal(&mut self, active: &ActiveJob, result: FoundResult) {
+        let FoundKey::KeyPair(keypair) = &result.key else {
+            return;
+        };
+
+        match protocol::seal(&active.public_key, &keypair.get_private_key().to_hex()) {
+            Ok(secret) => {
+                info!("Found '{}' in job #{}: {}", result.content, active.job.id, result.address);
+                self.pending.push_back(Submission {
+                    job: active.job.id,
+                    address: result.address,
+                    content: result.content,
+                    placement: result.placement,
+                    index: result.index,
+                    secret,
+                });
+            },
+            Err(e) => error!("Couldn't seal the key of {}: {}", result.address, e),
+        }
+    }
+
+    // Send the queued results, in order
+    async fn flush(&mut self, writer: &mut OwnedWriteHalf) -> Result<(), String> {
+        while let Some(submission) = self.pending.front() {
+            protocol::write_message(writer, &WorkerMessage::Found(submission.clone())).await?;
+            self.pending.pop_front();
+        }
+
+        Ok(())
+    }
+
+    // Send the rate since the previous report, and the end of a derived range once reached
+    async fn report_rate(&mut self, writer: &mut OwnedWriteHalf, last: &mut (Instant, u64)) -> Result<(), String> {
+        let Some(active) = &mut self.active else {
+            return Ok(());
+        };
+
+        let attempts = TOTAL_ATTEMPTS.load(Ordering::Relaxed);
+        let rate = (attempts - last.1) as f64 / last.0.elapsed().as_secs_f64();
+        *last = (Instant::now(), attempts);
+
+        let message = WorkerMessage::Rate {
+            job: active.job.id,
+            rate,
+            attempts: attempts - self.reported,
+            offset: active.progress.as_ref().map(|progress| progress.offset()),
+        };
+        protocol::write_message(writer, &message).await?;
+        self.reported = attempts;
+
+        if !active.finished && active.searcher.is_finished() {
+            info!("Job #{} is finished", active.job.id);
+            active.finished = true;
+            protocol::write_message(writer, &WorkerMessage::Finished { job: active.job.id }).await?;
+        }
+
+        Ok(())
+    }
+}